@@ -10,53 +10,62 @@ if (isReleaseBuild) {
   console.log('Compiling with debug build')
 }
 
+// Build the single player crate with the provided feature
+// to a WASM binary with the provided name
+function buildWasm(feature, wasmName) {
+  const wasmBuildFlags = [
+    'build',
+    '-p',
+    'single_player',
+    '--features',
+    feature,
+    '--target',
+    'wasm32-unknown-unknown',
+  ]
+  if (isReleaseBuild) {
+    wasmBuildFlags.push('--release')
+  }
+
+  const wasmBuildCmd = Deno.spawnSync(
+    'cargo',
+    { args: wasmBuildFlags },
+    { cwd: cwd }
+  )
+
+  console.log(new TextDecoder().decode(wasmBuildCmd.stderr))
+  console.log(new TextDecoder().decode(wasmBuildCmd.stdout))
+
+  fs.renameSync(
+    path.joinGlobs([
+      cwd,
+      'target',
+      'wasm32-unknown-unknown',
+      isReleaseBuild ? 'release' : 'debug',
+      'single_player.wasm',
+    ]),
+    path.joinGlobs([
+      cwd,
+      'target',
+      'wasm32-unknown-unknown',
+      isReleaseBuild ? 'release' : 'debug',
+      `${wasmName}.wasm`,
+    ])
+  )
+
+  if (wasmBuildCmd.code !== 0) {
+    Deno.exit(1)
+  }
+}
+
 const gltfLevelsPath = path.joinGlobs([cwd, 'assets', 'gltf', 'levels'])
 fs.readdirSync(gltfLevelsPath).forEach((fileOrDir) => {
   if (fs.statSync(path.joinGlobs([gltfLevelsPath, fileOrDir])).isDirectory()) {
     const levelName = fileOrDir
-
-    // Build the level to a WASM binary
-    const wasmBuildFlags = [
-      'build',
-      '-p',
-      'single_player',
-      '--features',
-      `level_${levelName}`,
-      '--target',
-      'wasm32-unknown-unknown',
-    ]
-    if (isReleaseBuild) {
-      wasmBuildFlags.push('--release')
-    }
-
-    const wasmBuildCmd = Deno.spawnSync(
-      'cargo',
-      { args: wasmBuildFlags },
-      { cwd: cwd }
-    )
-
-    console.log(new TextDecoder().decode(wasmBuildCmd.stderr))
-    console.log(new TextDecoder().decode(wasmBuildCmd.stdout))
-
-    fs.renameSync(
-      path.joinGlobs([
-        cwd,
-        'target',
-        'wasm32-unknown-unknown',
-        isReleaseBuild ? 'release' : 'debug',
-        'single_player.wasm',
-      ]),
-      path.joinGlobs([
-        cwd,
-        'target',
-        'wasm32-unknown-unknown',
-        isReleaseBuild ? 'release' : 'debug',
-        `level_${levelName}.wasm`,
-      ])
-    )
-
-    if (wasmBuildCmd.code !== 0) {
-      Deno.exit(1)
-    }
+    buildWasm(`level_${levelName}`, `level_${levelName}`)
   }
 })
+
+// Build a binary for levels loaded at runtime, and one more
+// hosting every level for level select menus
+buildWasm('custom_level', 'custom_level')
+buildWasm('all_levels', 'all_levels')
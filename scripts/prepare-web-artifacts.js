@@ -91,3 +91,41 @@ fs.readdirSync(gltfLevelsPath).forEach((fileOrDir) => {
     )
   }
 })
+
+// Custom levels have no glTF of their own to copy, just a WASM binary and a wrapper
+fs.mkdirSync(path.joinGlobs([cwd, 'dist', 'wasm', 'levels', 'custom']), {
+  recursive: true,
+})
+fs.copyFileSync(
+  path.joinGlobs([
+    cwd,
+    'target',
+    'wasm32-unknown-unknown',
+    isReleaseBuild ? 'release' : 'debug',
+    'custom_level.wasm',
+  ]),
+  path.joinGlobs([cwd, 'dist', 'wasm', 'levels', 'custom', 'sim.wasm']),
+  fs.constants.COPYFILE_FICLONE
+)
+fs.mkdirSync(path.joinGlobs([cwd, 'dist', 'js', 'levels', 'custom']), {
+  recursive: true,
+})
+fs.copyFileSync(
+  path.joinGlobs([
+    cwd,
+    'single_player',
+    'wasm_js_wrappers',
+    'levels',
+    'custom',
+    'sim.js',
+  ]),
+  path.joinGlobs([
+    cwd,
+    'dist',
+    'js',
+    'levels',
+    'custom',
+    'CustomLevelSim.module.js',
+  ]),
+  fs.constants.COPYFILE_FICLONE
+)
@@ -0,0 +1,574 @@
+use crate::registry::{create_level, Level, LevelImport, PlayerCharacter, Vehicle, LEVELS};
+use crate::shared::ffi_status::{report_status, str_from_ffi, write_to_slot, FfiError, FfiStatus};
+use perigee::prelude::*;
+use std::ffi::CString;
+use std::marker::PhantomData;
+
+/// Whichever level the interface chose to play. Functions only some levels have
+/// report an invalid state when another level is being played, unless they
+/// return a pointer, in which case they return a null pointer.
+pub struct Sim<'a> {
+    level: Box<dyn Level>,
+    /// Why the most recent FFI call that failed couldn't be completed
+    last_error: String,
+    gltf_validation_bytes: Vec<u8>,
+    // FFI generation expects simulations to have a lifetime
+    _lifetime: PhantomData<&'a ()>,
+}
+
+impl<'a> Sim<'a> {
+    fn player(&self) -> Result<&dyn PlayerCharacter, FfiError> {
+        self.level.player().ok_or_else(|| {
+            FfiError::invalid_state("Only levels played as the player character have a player")
+        })
+    }
+
+    fn vehicle(&self) -> Result<&dyn Vehicle, FfiError> {
+        self.level
+            .vehicle()
+            .ok_or_else(|| FfiError::invalid_state("Only levels played driving a car have cars"))
+    }
+
+    fn vehicle_mut(&mut self) -> Result<&mut dyn Vehicle, FfiError> {
+        self.level
+            .vehicle_mut()
+            .ok_or_else(|| FfiError::invalid_state("Only levels played driving a car have cars"))
+    }
+
+    fn level_import_mut(&mut self) -> Result<&mut dyn LevelImport, FfiError> {
+        self.level.level_import_mut().ok_or_else(|| {
+            FfiError::invalid_state("Only levels built outside of this module are imported")
+        })
+    }
+}
+
+#[ffi]
+impl<'a> Sim<'a> {
+    pub fn scene_gltf_bytes_ptr(&self) -> *const u8 {
+        self.level.scene_gltf_bytes().as_ptr()
+    }
+
+    pub fn scene_gltf_bytes_len(&self) -> usize {
+        self.level.scene_gltf_bytes().len()
+    }
+
+    pub fn prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.level.prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.last_error, prop_result)
+    }
+
+    pub fn previous_prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.level.previous_prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.last_error, prop_result)
+    }
+
+    pub fn poi(
+        &mut self,
+        poi_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let poi_result = str_from_ffi(poi_name)
+            .and_then(|poi_name| self.level.poi(poi_name))
+            .and_then(|poi_isometry| write_to_slot(isometry_slot, poi_isometry));
+        report_status(&mut self.last_error, poi_result)
+    }
+
+    pub fn initialize_sim(&mut self) -> FfiStatus {
+        let init_result = self.level.initialize().map_err(FfiError::from);
+        report_status(&mut self.last_error, init_result)
+    }
+
+    /// Why the most recent call that didn't return an OK status failed.
+    pub fn last_error_ptr(&self) -> *const u8 {
+        self.last_error.as_ptr()
+    }
+
+    pub fn last_error_len(&self) -> usize {
+        self.last_error.len()
+    }
+
+    /// Make room for a glTF of the provided length for the interface to copy into.
+    pub fn gltf_validation_buffer(&mut self, gltf_len: usize) -> *mut u8 {
+        self.gltf_validation_bytes = vec![0; gltf_len];
+        self.gltf_validation_bytes.as_mut_ptr()
+    }
+
+    /// Check the glTF in the validation buffer has everything the level needs.
+    pub fn validate_gltf(&mut self) -> FfiStatus {
+        let validation_result = self
+            .level
+            .requirements()
+            .validate_gltf(&self.gltf_validation_bytes)
+            .map_err(FfiError::from);
+        report_status(&mut self.last_error, validation_result)
+    }
+
+    pub fn restart_level(&mut self) -> FfiStatus {
+        let restart_result = self.level.restart();
+        report_status(&mut self.last_error, restart_result)
+    }
+
+    pub fn desired_fps(&self) -> f32 {
+        self.level.desired_fps()
+    }
+
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.level.interpolation_alpha()
+    }
+
+    pub fn step(&mut self, delta_seconds: f32) -> FfiStatus {
+        let step_result = self.level.step(delta_seconds);
+        report_status(&mut self.last_error, step_result)
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.level.time_scale()
+    }
+
+    pub fn set_time_scale(&mut self, new_time_scale: f32) {
+        self.level.set_time_scale(new_time_scale);
+    }
+
+    pub fn is_paused(&self) -> u8 {
+        self.level.is_paused() as u8
+    }
+
+    pub fn pause_sim(&mut self) {
+        self.level.pause();
+    }
+
+    pub fn resume_sim(&mut self) {
+        self.level.resume();
+    }
+
+    pub fn settings_left_right_look_sensitivity(&self) -> u8 {
+        self.level.settings().left_right_look_sensitivity()
+    }
+
+    pub fn settings_up_down_look_sensitivity(&self) -> u8 {
+        self.level.settings().up_down_look_sensitivity()
+    }
+
+    pub fn settings_set_left_right_look_sensitivity(&mut self, new_sensitivity: i32) {
+        self.level
+            .settings_mut()
+            .set_left_right_look_sensitivity(new_sensitivity as u8);
+    }
+
+    pub fn settings_set_up_down_look_sensitivity(&mut self, new_sensitivity: i32) {
+        self.level
+            .settings_mut()
+            .set_up_down_look_sensitivity(new_sensitivity as u8);
+    }
+
+    pub fn input_set_move_forward(&mut self, new_magnitude: f32) {
+        self.level.input_mut().set_move_forward(new_magnitude);
+    }
+
+    pub fn input_set_move_right(&mut self, new_magnitude: f32) {
+        self.level.input_mut().set_move_right(new_magnitude);
+    }
+
+    /// How far to look up since the last frame. This adds up until a tick uses it.
+    pub fn input_set_rotate_up(&mut self, new_magnitude: f32) {
+        self.level.input_mut().add_rotate_up(new_magnitude);
+    }
+
+    /// How far to turn right since the last frame. This adds up until a tick uses it.
+    pub fn input_set_rotate_right(&mut self, new_magnitude: f32) {
+        self.level.input_mut().add_rotate_right(new_magnitude);
+    }
+
+    pub fn input_set_jump(&mut self, jump_val: u8) {
+        self.level.input_mut().set_jump(jump_val > 0);
+    }
+
+    pub fn input_set_aim(&mut self, aim_val: u8) {
+        self.level.input_mut().set_aim(aim_val > 0);
+    }
+
+    pub fn input_set_dash(&mut self, dash_val: u8) {
+        self.level.input_mut().set_dash(dash_val > 0);
+    }
+
+    pub fn input_set_grapple(&mut self, grapple_val: u8) {
+        self.level.input_mut().set_grapple(grapple_val > 0);
+    }
+
+    pub fn input_set_reel(&mut self, new_magnitude: f32) {
+        self.level.input_mut().set_reel(new_magnitude);
+    }
+
+    pub fn input_set_look_back(&mut self, look_back_val: u8) {
+        self.level.input_mut().set_look_back(look_back_val > 0);
+    }
+
+    #[slot_return]
+    pub fn camera_global_isometry(&self) -> Isometry3<f32> {
+        self.level.camera_global_isometry()
+    }
+
+    #[slot_return]
+    pub fn previous_camera_global_isometry(&self) -> Isometry3<f32> {
+        self.level.previous_camera_global_isometry()
+    }
+
+    pub fn stop_ghost_recording(&mut self) {
+        self.level.stop_ghost_recording();
+    }
+
+    pub fn ghost_recording_ptr(&self) -> *const u8 {
+        self.level.ghost_recording_bytes().as_ptr()
+    }
+
+    pub fn ghost_recording_len(&self) -> usize {
+        self.level.ghost_recording_bytes().len()
+    }
+
+    pub fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        self.level.ghost_import_buffer(recording_len)
+    }
+
+    pub fn load_ghost(&mut self) -> FfiStatus {
+        let ghost_result = self.level.load_ghost();
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    pub fn has_ghost(&self) -> u8 {
+        self.level.has_ghost() as u8
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.level.elapsed_seconds()
+    }
+
+    pub fn state_checksum(&self) -> u32 {
+        self.level.state_checksum()
+    }
+
+    pub fn input_log_ptr(&self) -> *const u8 {
+        self.level.input_log_bytes().as_ptr()
+    }
+
+    pub fn input_log_len(&self) -> usize {
+        self.level.input_log_bytes().len()
+    }
+
+    pub fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        self.level.replay_import_buffer(input_log_len)
+    }
+
+    pub fn verify_replay(&mut self, claimed_completion_seconds: f32) -> FfiStatus {
+        let verification = self.level.verify_replay(claimed_completion_seconds);
+        report_status(&mut self.last_error, verification)
+    }
+
+    // Levels played as the player character
+
+    pub fn player_gltf_bytes_ptr(&self) -> *const u8 {
+        self.player().map_or(std::ptr::null(), |player| {
+            player.player_gltf_bytes().as_ptr()
+        })
+    }
+
+    pub fn player_gltf_bytes_len(&self) -> usize {
+        self.player()
+            .map_or(0, |player| player.player_gltf_bytes().len())
+    }
+
+    pub fn player_body_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let body_result = self
+            .player()
+            .and_then(|player| write_to_slot(isometry_slot, player.body_isometry()));
+        report_status(&mut self.last_error, body_result)
+    }
+
+    pub fn previous_player_body_isometry(
+        &mut self,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let body_result = self
+            .player()
+            .and_then(|player| write_to_slot(isometry_slot, player.previous_body_isometry()));
+        report_status(&mut self.last_error, body_result)
+    }
+
+    pub fn ghost_body_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let ghost_result = self
+            .player()
+            .and_then(|player| player.ghost_body_isometry())
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    // Levels built outside of this module
+
+    pub fn scene_gltf_import_buffer(&mut self, scene_gltf_len: usize) -> *mut u8 {
+        self.level_import_mut()
+            .map_or(std::ptr::null_mut(), |level_import| {
+                level_import.scene_gltf_import_buffer(scene_gltf_len)
+            })
+    }
+
+    pub fn manifest_import_buffer(&mut self, manifest_len: usize) -> *mut u8 {
+        self.level_import_mut()
+            .map_or(std::ptr::null_mut(), |level_import| {
+                level_import.manifest_import_buffer(manifest_len)
+            })
+    }
+
+    pub fn load_manifest(&mut self) -> FfiStatus {
+        let manifest_result = self
+            .level_import_mut()
+            .and_then(|level_import| level_import.load_manifest());
+        report_status(&mut self.last_error, manifest_result)
+    }
+
+    pub fn is_initialized(&self) -> u8 {
+        self.level.is_initialized() as u8
+    }
+
+    // Levels played driving a car
+
+    pub fn camera_fov_kick(&mut self, fov_kick_slot: *mut f32) -> FfiStatus {
+        let fov_kick_result = self
+            .vehicle()
+            .and_then(|vehicle| write_to_slot(fov_kick_slot, vehicle.camera_fov_kick()));
+        report_status(&mut self.last_error, fov_kick_result)
+    }
+
+    pub fn car_cabin_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let cabin_result = self
+            .vehicle()
+            .and_then(|vehicle| write_to_slot(isometry_slot, vehicle.cabin_isometry()));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.wheel_isometry(wheel_idx))
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn previous_car_cabin_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let cabin_result = self
+            .vehicle()
+            .and_then(|vehicle| write_to_slot(isometry_slot, vehicle.previous_cabin_isometry()));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn previous_wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.previous_wheel_isometry(wheel_idx))
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn cycle_camera_mode(&mut self) -> FfiStatus {
+        let cycle_result = self
+            .vehicle_mut()
+            .map(|vehicle| vehicle.cycle_camera_mode());
+        report_status(&mut self.last_error, cycle_result)
+    }
+
+    pub fn opponent_count(&mut self, count_slot: *mut u32) -> FfiStatus {
+        let count_result = self
+            .vehicle()
+            .and_then(|vehicle| write_to_slot(count_slot, vehicle.opponent_count()));
+        report_status(&mut self.last_error, count_result)
+    }
+
+    pub fn opponent_cabin_isometry(
+        &mut self,
+        opponent_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let cabin_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.opponent_cabin_isometry(opponent_idx))
+            .and_then(|cabin_isometry| write_to_slot(isometry_slot, cabin_isometry));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn opponent_wheel_isometry(
+        &mut self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.opponent_wheel_isometry(opponent_idx, wheel_idx))
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn previous_opponent_cabin_isometry(
+        &mut self,
+        opponent_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let cabin_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.previous_opponent_cabin_isometry(opponent_idx))
+            .and_then(|cabin_isometry| write_to_slot(isometry_slot, cabin_isometry));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn previous_opponent_wheel_isometry(
+        &mut self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.previous_opponent_wheel_isometry(opponent_idx, wheel_idx))
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn ghost_cabin_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let ghost_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.ghost_cabin_isometry())
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    pub fn ghost_wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let ghost_result = self
+            .vehicle()
+            .and_then(|vehicle| vehicle.ghost_wheel_isometry(wheel_idx))
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    pub fn car_damage(&mut self, damage_slot: *mut f32) -> FfiStatus {
+        let damage_result = self
+            .vehicle()
+            .and_then(|vehicle| write_to_slot(damage_slot, vehicle.damage()));
+        report_status(&mut self.last_error, damage_result)
+    }
+
+    /// Put the car back at the start of the track on the next step.
+    pub fn reset_car(&mut self) -> FfiStatus {
+        let reset_result = self.vehicle_mut().map(|vehicle| vehicle.reset());
+        report_status(&mut self.last_error, reset_result)
+    }
+}
+
+/// How many levels the interface can choose from.
+#[no_mangle]
+pub extern "C" fn level_count() -> u32 {
+    LEVELS.len() as u32
+}
+
+/// The ID of the level at the provided index, for passing to `create_sim`.
+/// Returns `u32::MAX` if there's no level at the provided index.
+#[no_mangle]
+pub extern "C" fn level_id(level_idx: u32) -> u32 {
+    LEVELS
+        .get(level_idx as usize)
+        .map_or(u32::MAX, |registered_level| registered_level.id)
+}
+
+#[no_mangle]
+pub extern "C" fn level_name_ptr(level_idx: u32) -> *const u8 {
+    LEVELS
+        .get(level_idx as usize)
+        .map_or(std::ptr::null(), |registered_level| {
+            registered_level.name.as_ptr()
+        })
+}
+
+#[no_mangle]
+pub extern "C" fn level_name_len(level_idx: u32) -> usize {
+    LEVELS
+        .get(level_idx as usize)
+        .map_or(0, |registered_level| registered_level.name.len())
+}
+
+#[no_mangle]
+pub extern "C" fn destroy_sim(sim_ptr: *mut Sim) {
+    // Box will deallocate the memory on drop
+    unsafe { Box::from_raw(sim_ptr) };
+}
+
+/// Create the level with the provided ID. Returns a null pointer if there's no such level.
+#[no_mangle]
+pub extern "C" fn create_sim(level_id: u32) -> *mut Sim<'static> {
+    init_perigee_logger();
+    match create_level(level_id) {
+        Some(level) => Box::into_raw(Box::new(Sim {
+            level,
+            last_error: String::new(),
+            gltf_validation_bytes: Vec::new(),
+            _lifetime: PhantomData,
+        })),
+        None => {
+            warn!("No level has ID {}", level_id);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sim_for_level(level_id: u32) -> Sim<'static> {
+        Sim {
+            level: create_level(level_id).unwrap(),
+            last_error: String::new(),
+            gltf_validation_bytes: Vec::new(),
+            _lifetime: PhantomData,
+        }
+    }
+
+    #[test]
+    fn reports_calls_the_level_cant_answer() {
+        let mut isometry = Isometry3::identity();
+        let mut damage = 0.0;
+
+        let mut level_1_sim = sim_for_level(1);
+        assert_eq!(level_1_sim.car_damage(&mut damage), FfiStatus::InvalidState);
+        assert!(!level_1_sim.last_error.is_empty());
+        assert_eq!(level_1_sim.load_manifest(), FfiStatus::InvalidState);
+
+        let mut level_2_sim = sim_for_level(2);
+        assert_eq!(
+            level_2_sim.player_body_isometry(&mut isometry),
+            FfiStatus::InvalidState
+        );
+        assert!(level_2_sim.player_gltf_bytes_ptr().is_null());
+        assert_eq!(level_2_sim.car_damage(&mut damage), FfiStatus::Ok);
+    }
+}
@@ -88,6 +88,21 @@ pub struct CharacterControllerConfig {
     /// How many seconds must pass before another jump is possible.
     /// while the character controller is crouching.
     pub min_jump_crouched_cooldown_duration: f32,
+    /// How many times the character controller can jump in the air before it
+    /// lands, touches a wall or grabs a ledge again.
+    pub max_air_jumps: u8,
+    /// How much force is used to make the character controller jump in the air.
+    pub air_jump_acceleration: f32,
+    /// How many seconds must pass after any jump before the character controller can jump in the air.
+    pub min_air_jump_cooldown_duration: f32,
+    /// How many times the character controller can dash in the air before it
+    /// lands, touches a wall or grabs a ledge again.
+    pub max_air_dashes: u8,
+    /// The increase in horizontal velocity applied to the rigid body in the
+    /// direction of the character controller's movement when it dashes.
+    pub air_dash_impulse: f32,
+    /// How many seconds must pass before another air dash is possible.
+    pub min_air_dash_cooldown_duration: f32,
     /// The scale factor of jump force (up + forward) when wallrunning.
     pub jump_wallrunning_scale: f32,
     /// How close to straight down the body must be moving when wallrunning for the
@@ -113,6 +128,23 @@ pub struct CharacterControllerConfig {
     /// The gravity scale of the character controller's body once wallrunning
     /// has started.
     pub start_wallrunning_gravity_scale: f32,
+    /// How many seconds the character controller can wallrun before falling off the wall. The
+    /// same wall can't be run on again until the character controller lands or grabs a ledge.
+    pub max_wallrunning_duration: f32,
+    /// The gravity scale of the character controller's body once it has wallrun for the max
+    /// duration. Gravity ramps up to it from the start wallrunning gravity scale.
+    pub end_wallrunning_gravity_scale: f32,
+    /// How many times in a row the character controller can jump off of the same wall
+    /// before it has to land, grab a ledge or jump off of a different wall.
+    pub max_consecutive_wall_kicks: u8,
+    /// The max angle (in degrees) between the character controller's back and a wall's
+    /// normal for it to climb the wall.
+    pub max_wall_climb_facing_angle: f32,
+    /// How fast the character controller climbs up a wall.
+    pub wall_climb_speed: f32,
+    /// How many seconds the character controller can climb a wall before it falls off. Only one
+    /// wall climb is possible before the character controller lands or grabs a ledge.
+    pub max_wall_climb_duration: f32,
     /// How many seconds should pass before another footstep is taken
     /// when moving at the max speed while grounded.
     pub grounded_seconds_per_footstep: f32,
@@ -140,6 +172,41 @@ pub struct CharacterControllerConfig {
     pub endless_slide_ground_normal_max_up_angle: f32,
     /// The acceleration applied to endless / downhill slides.
     pub endless_sliding_acceleration: [f32; 3],
+    /// How far in front of its capsule the character controller can reach to grab a ledge.
+    pub ledge_grab_forward_reach: f32,
+    /// How far above the top of its capsule's cylinder (its shoulders) the character
+    /// controller can reach to grab a ledge.
+    pub ledge_grab_upward_reach: f32,
+    /// The max angle (in degrees) between the top of a ledge and the up vector for
+    /// the ledge to be grabbed.
+    pub max_ledge_slope_angle: f32,
+    /// How many seconds it takes the character controller to mantle up onto a ledge.
+    pub ledge_mantle_duration: f32,
+    /// How many seconds must pass after letting go of a ledge before another can be grabbed.
+    pub min_ledge_regrab_cooldown_duration: f32,
+    /// The upward acceleration water gives the character controller's body when it's fully
+    /// submerged. Above gravity the body floats, and the higher it is the higher the body floats.
+    pub swim_buoyancy_acceleration: f32,
+    /// How much of its velocity the character controller's body loses per second in water.
+    pub swim_drag: f32,
+    /// The max horizontal speed of the character controller while swimming.
+    pub max_swim_speed: f32,
+    /// How quickly the character controller reaches its swimming speed.
+    pub swim_acceleration: f32,
+    /// How fast the character controller dives down or swims back up to the surface.
+    pub swim_vertical_speed: f32,
+    /// How far below the water's surface the character controller's head can be while
+    /// still swimming on the surface rather than underwater.
+    pub max_swim_surface_depth: f32,
+    /// How far in front of its capsule the character controller can reach to grab a ladder.
+    pub ladder_grab_reach: f32,
+    /// The max angle (in degrees) between the character controller's back and a ladder's
+    /// normal for it to grab the ladder.
+    pub max_ladder_facing_angle: f32,
+    /// How fast the character controller climbs up or down a ladder.
+    pub ladder_climb_speed: f32,
+    /// How many seconds must pass after letting go of a ladder before another can be grabbed.
+    pub min_ladder_regrab_cooldown_duration: f32,
     /// The max capacity of the event channel used by the character controller structure.
     pub event_queue_capacity: usize,
     /// The length of the default boom arm.
@@ -215,6 +282,12 @@ impl Default for CharacterControllerConfig {
             jump_crouched_acceleration: 3.5,
             min_jump_standing_cooldown_duration: 0.3,
             min_jump_crouched_cooldown_duration: 0.5,
+            max_air_jumps: 0,
+            air_jump_acceleration: 5.0,
+            min_air_jump_cooldown_duration: 0.2,
+            max_air_dashes: 0,
+            air_dash_impulse: 8.0,
+            min_air_dash_cooldown_duration: 0.4,
             jump_wallrunning_scale: 1.0,
             jump_wallrunning_normal_scale: 0.35,
             jump_wallrunning_down_velocity_angle_threshold: 30.0,
@@ -223,6 +296,12 @@ impl Default for CharacterControllerConfig {
             max_wallrunning_forward_angle: 75.0,
             start_wallrunning_up_impulse: 4.0,
             start_wallrunning_gravity_scale: 0.5,
+            max_wallrunning_duration: 2.5,
+            end_wallrunning_gravity_scale: 1.0,
+            max_consecutive_wall_kicks: 3,
+            max_wall_climb_facing_angle: 30.0,
+            wall_climb_speed: 4.0,
+            max_wall_climb_duration: 0.6,
             grounded_seconds_per_footstep: 1.0 / 4.0,
             wallrunning_seconds_per_footstep: 1.0 / 6.0,
             sliding_speed_factor: 0.8,
@@ -232,6 +311,21 @@ impl Default for CharacterControllerConfig {
             endless_slide_downhill_max_down_angle: 80.0,
             endless_slide_ground_normal_max_up_angle: 30.0,
             endless_sliding_acceleration: [0.0, 0.0, -10.0],
+            ledge_grab_forward_reach: 0.3,
+            ledge_grab_upward_reach: 0.6,
+            max_ledge_slope_angle: 30.0,
+            ledge_mantle_duration: 0.5,
+            min_ledge_regrab_cooldown_duration: 0.5,
+            swim_buoyancy_acceleration: 12.0,
+            swim_drag: 2.0,
+            max_swim_speed: 3.0,
+            swim_acceleration: 10.0,
+            swim_vertical_speed: 2.5,
+            max_swim_surface_depth: 0.2,
+            ladder_grab_reach: 0.3,
+            max_ladder_facing_angle: 45.0,
+            ladder_climb_speed: 2.5,
+            min_ladder_regrab_cooldown_duration: 0.3,
             event_queue_capacity: 10,
             default_boom_arm_length: 3.0,
             default_boom_arm_pitch_angle: 0.0,
@@ -0,0 +1,134 @@
+use crate::config::PlayerLevelConfig;
+use perigee::{
+    toml,
+    traits::{TryFromToml, TryToToml},
+};
+use serde::{Deserialize, Serialize};
+
+/// Describes how to play a level made outside of this crate. Everything
+/// it names is expected to be in the level's glTF.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LevelManifest {
+    /// The point of interest the player starts at and is reset to.
+    pub spawn_poi: String,
+    /// The sensor that completes the level once the player enters it.
+    pub finish_sensor: String,
+    /// The audio looped on the player while the level is being played, if any.
+    #[serde(default)]
+    pub music: Option<String>,
+    #[serde(default = "default_music_volume")]
+    pub music_volume: f32,
+    #[serde(default)]
+    pub config: PlayerLevelConfig,
+}
+
+fn default_music_volume() -> f32 {
+    0.2
+}
+
+impl Default for LevelManifest {
+    fn default() -> Self {
+        Self {
+            spawn_poi: String::from("Player Start"),
+            finish_sensor: String::from("Finish Sensor"),
+            music: None,
+            music_volume: default_music_volume(),
+            config: PlayerLevelConfig::default(),
+        }
+    }
+}
+
+impl LevelManifest {
+    /// Make sure every setting in the manifest can be played with,
+    /// listing the ones that can't if there are any.
+    pub fn validate(&self) -> Result<(), String> {
+        let is_positive = |value: f32| value.is_finite() && value > 0.0;
+        let invalid_fields: Vec<&str> = [
+            ("config.tick_rate", is_positive(self.config.tick_rate)),
+            (
+                "config.ghost_sample_rate",
+                is_positive(self.config.ghost_sample_rate),
+            ),
+            (
+                "config.max_ghost_seconds",
+                is_positive(self.config.max_ghost_seconds),
+            ),
+            ("music_volume", (0.0..=1.0).contains(&self.music_volume)),
+        ]
+        .into_iter()
+        .filter(|(_, is_valid)| !is_valid)
+        .map(|(field_name, _)| field_name)
+        .collect();
+        if invalid_fields.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Level manifest has invalid values for {}",
+                invalid_fields.join(", ")
+            ))
+        }
+    }
+}
+
+impl TryFromToml for LevelManifest {
+    fn try_from_toml(toml_str: &str) -> Result<Self, String> {
+        match toml::from_str::<LevelManifest>(toml_str) {
+            Ok(manifest) => Ok(manifest),
+            Err(toml_de_err) => Err(toml_de_err.to_string()),
+        }
+    }
+}
+
+impl TryToToml for LevelManifest {
+    fn try_to_toml(&self) -> Result<String, String> {
+        match toml::to_string(self) {
+            Ok(manifest_toml) => Ok(manifest_toml),
+            Err(toml_ser_err) => Err(toml_ser_err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_minimal_manifest() {
+        let manifest = LevelManifest::try_from_toml(
+            r#"
+            spawn_poi = "Spawn"
+            finish_sensor = "Goal"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.spawn_poi, "Spawn");
+        assert_eq!(manifest.finish_sensor, "Goal");
+        assert!(manifest.music.is_none());
+        assert_eq!(
+            manifest.config.tick_rate,
+            PlayerLevelConfig::default().tick_rate
+        );
+        assert!(manifest.validate().is_ok());
+    }
+
+    #[test]
+    fn lists_invalid_fields() {
+        let manifest = LevelManifest::try_from_toml(
+            r#"
+            spawn_poi = "Spawn"
+            finish_sensor = "Goal"
+            music_volume = 1.5
+
+            [config]
+            tick_rate = 0.0
+            ghost_sample_rate = -20.0
+            "#,
+        )
+        .unwrap();
+        let validation_err = manifest.validate().unwrap_err();
+        assert!(validation_err.contains("config.tick_rate"));
+        assert!(validation_err.contains("config.ghost_sample_rate"));
+        assert!(validation_err.contains("music_volume"));
+        assert!(!validation_err.contains("config.max_ghost_seconds"));
+    }
+}
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Configuration parameters for the [GrappleController](crate::shared::controllers::GrappleController).
+#[derive(Debug, Deserialize, Serialize, Clone, Copy)]
+pub struct GrappleConfig {
+    /// How far from the camera something can be to grapple onto it.
+    pub max_grapple_distance: f32,
+    /// The shortest the rope can be reeled in to.
+    pub min_rope_length: f32,
+    /// How fast the rope is reeled in or out.
+    pub reel_speed: f32,
+}
+
+impl Default for GrappleConfig {
+    fn default() -> Self {
+        Self {
+            max_grapple_distance: 30.0,
+            min_rope_length: 1.5,
+            reel_speed: 8.0,
+        }
+    }
+}
@@ -1,6 +1,5 @@
-use crate::config::PlayerConfig;
+use crate::config::PlayerLevelConfig;
 use perigee::{
-    config::PhysicsConfig,
     toml,
     traits::{TryFromToml, TryToToml},
 };
@@ -9,18 +8,15 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Level1Config {
     pub launch_impulse: f32,
-    #[serde(default)]
-    pub physics: PhysicsConfig,
-    #[serde(default)]
-    pub player: PlayerConfig,
+    #[serde(flatten)]
+    pub level: PlayerLevelConfig,
 }
 
 impl Default for Level1Config {
     fn default() -> Self {
         Self {
             launch_impulse: 25.0,
-            physics: PhysicsConfig::default(),
-            player: PlayerConfig::default(),
+            level: PlayerLevelConfig::default(),
         }
     }
 }
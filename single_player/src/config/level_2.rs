@@ -1,4 +1,4 @@
-use crate::config::SedanConfig;
+use crate::config::{racing_driver::DriverDifficulty, RacingDriverConfig, SedanConfig};
 use perigee::{
     config::PhysicsConfig,
     toml,
@@ -12,6 +12,38 @@ pub struct Level2Config {
     pub physics: PhysicsConfig,
     #[serde(default)]
     pub car: SedanConfig,
+    /// How many AI-driven cars race against the player.
+    #[serde(default = "default_opponent_count")]
+    pub opponent_count: usize,
+    #[serde(default)]
+    pub opponent_difficulty: DriverDifficulty,
+    #[serde(default)]
+    pub racing_driver: RacingDriverConfig,
+    /// How many times per second the player's run is sampled for their ghost.
+    #[serde(default = "default_ghost_sample_rate")]
+    pub ghost_sample_rate: f32,
+    /// The longest run a ghost is recorded for. Runs that go on longer don't leave a ghost.
+    #[serde(default = "default_max_ghost_seconds")]
+    pub max_ghost_seconds: f32,
+    /// How many times per second the simulation ticks, no matter how often it's stepped.
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f32,
+}
+
+fn default_opponent_count() -> usize {
+    3
+}
+
+fn default_ghost_sample_rate() -> f32 {
+    20.0
+}
+
+fn default_max_ghost_seconds() -> f32 {
+    600.0
+}
+
+fn default_tick_rate() -> f32 {
+    30.0
 }
 
 impl Default for Level2Config {
@@ -19,6 +51,12 @@ impl Default for Level2Config {
         Self {
             physics: PhysicsConfig::default(),
             car: SedanConfig::default(),
+            opponent_count: default_opponent_count(),
+            opponent_difficulty: DriverDifficulty::default(),
+            racing_driver: RacingDriverConfig::default(),
+            ghost_sample_rate: default_ghost_sample_rate(),
+            max_ghost_seconds: default_max_ghost_seconds(),
+            tick_rate: default_tick_rate(),
         }
     }
 }
@@ -1,15 +1,26 @@
 pub use character_controller::CharacterControllerConfig;
+#[cfg(feature = "custom_level")]
+pub use custom_level::LevelManifest;
+pub use grapple::GrappleConfig;
+#[cfg(feature = "level_1")]
 pub use level_1::Level1Config;
+#[cfg(feature = "level_2")]
 pub use level_2::Level2Config;
 pub use plane_controller::PlaneControllerConfig;
 pub use player::PlayerConfig;
+pub use player_level::PlayerLevelConfig;
+pub use racing_driver::RacingDriverConfig;
 pub use raycast_vehicle::RaycastVehicleConfig;
 pub use sedan::SedanConfig;
 
 pub mod character_controller;
+pub mod custom_level;
+pub mod grapple;
 pub mod level_1;
 pub mod level_2;
 pub mod plane_controller;
 pub mod player;
+pub mod player_level;
+pub mod racing_driver;
 pub mod raycast_vehicle;
 pub mod sedan;
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
 
-use crate::config::CharacterControllerConfig;
+use crate::config::{CharacterControllerConfig, GrappleConfig};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayerConfig {
     pub character_controller: CharacterControllerConfig,
     pub event_queue_capacity: usize,
     pub aerial_max_move_acceleration: f32,
+    /// How the player grapples onto objects tagged `grapple`. The player can't grapple if this is `None`.
+    #[serde(default = "default_grapple")]
+    pub grapple: Option<GrappleConfig>,
+}
+
+fn default_grapple() -> Option<GrappleConfig> {
+    Some(GrappleConfig::default())
 }
 
 impl Default for PlayerConfig {
@@ -19,6 +26,7 @@ impl Default for PlayerConfig {
             },
             event_queue_capacity: 10,
             aerial_max_move_acceleration: 5.0,
+            grapple: default_grapple(),
         }
     }
 }
@@ -0,0 +1,67 @@
+use crate::config::PlayerConfig;
+use perigee::{
+    config::PhysicsConfig,
+    toml,
+    traits::{TryFromToml, TryToToml},
+};
+use serde::{Deserialize, Serialize};
+
+/// How a level played as the player character plays, whichever level it is.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PlayerLevelConfig {
+    #[serde(default)]
+    pub physics: PhysicsConfig,
+    #[serde(default)]
+    pub player: PlayerConfig,
+    /// How many times per second the player's run is sampled for their ghost.
+    #[serde(default = "default_ghost_sample_rate")]
+    pub ghost_sample_rate: f32,
+    /// The longest run a ghost is recorded for. Runs that go on longer don't leave a ghost.
+    #[serde(default = "default_max_ghost_seconds")]
+    pub max_ghost_seconds: f32,
+    /// How many times per second the simulation ticks, no matter how often it's stepped.
+    #[serde(default = "default_tick_rate")]
+    pub tick_rate: f32,
+}
+
+fn default_ghost_sample_rate() -> f32 {
+    20.0
+}
+
+fn default_max_ghost_seconds() -> f32 {
+    600.0
+}
+
+fn default_tick_rate() -> f32 {
+    30.0
+}
+
+impl Default for PlayerLevelConfig {
+    fn default() -> Self {
+        Self {
+            physics: PhysicsConfig::default(),
+            player: PlayerConfig::default(),
+            ghost_sample_rate: default_ghost_sample_rate(),
+            max_ghost_seconds: default_max_ghost_seconds(),
+            tick_rate: default_tick_rate(),
+        }
+    }
+}
+
+impl TryFromToml for PlayerLevelConfig {
+    fn try_from_toml(toml_str: &str) -> Result<Self, String> {
+        match toml::from_str::<PlayerLevelConfig>(toml_str) {
+            Ok(config) => Ok(config),
+            Err(toml_de_err) => Err(toml_de_err.to_string()),
+        }
+    }
+}
+
+impl TryToToml for PlayerLevelConfig {
+    fn try_to_toml(&self) -> Result<String, String> {
+        match toml::to_string(self) {
+            Ok(config_toml) => Ok(config_toml),
+            Err(toml_ser_err) => Err(toml_ser_err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DriverDifficulty {
+    #[serde(rename = "easy")]
+    Easy,
+    #[default]
+    #[serde(rename = "medium")]
+    Medium,
+    #[serde(rename = "hard")]
+    Hard,
+}
+
+impl DriverDifficulty {
+    /// How much of the configured speed and grip a driver of this difficulty dares to use.
+    pub fn pace_scale(&self) -> f32 {
+        match self {
+            Self::Easy => 0.75,
+            Self::Medium => 0.88,
+            Self::Hard => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RacingDriverConfig {
+    /// How far (in meters) ahead along the racing line the driver always looks to steer.
+    pub min_lookahead_distance: f32,
+    /// How many seconds ahead along the racing line the driver looks to steer
+    /// at their current speed, on top of the minimum lookahead distance.
+    pub lookahead_time: f32,
+    /// How far (in meters) ahead along the racing line the driver looks for corners to brake for.
+    pub braking_lookahead_distance: f32,
+    /// The fastest the driver will ever try to go, in meters per second.
+    pub max_speed: f32,
+    /// The most sideways acceleration (in meters per second squared) the driver
+    /// expects the car to be able to hold through a corner.
+    pub max_lateral_acceleration: f32,
+    /// How hard (in meters per second squared) the driver expects to be able to brake.
+    pub braking_deceleration: f32,
+    /// How much steer input to apply per radian of heading error toward the lookahead point.
+    pub steering_gain: f32,
+    /// How much throttle to apply per meter per second below the target speed.
+    pub throttle_gain: f32,
+    /// How much brake to apply per meter per second above the target speed.
+    pub brake_gain: f32,
+}
+
+impl Default for RacingDriverConfig {
+    fn default() -> Self {
+        Self {
+            min_lookahead_distance: 5.0,
+            lookahead_time: 0.6,
+            braking_lookahead_distance: 60.0,
+            max_speed: 30.0,
+            max_lateral_acceleration: 12.0,
+            braking_deceleration: 10.0,
+            steering_gain: 2.5,
+            throttle_gain: 0.5,
+            brake_gain: 0.3,
+        }
+    }
+}
@@ -12,6 +12,20 @@ pub struct WheelConfig {
     pub radius: Option<f32>,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct VehicleDamageConfig {
+    /// Impacts with less force than this don't damage the vehicle.
+    pub min_damaging_force: f32,
+    /// How much damage (where 1 is fully wrecked) is taken per unit of
+    /// impact force above `min_damaging_force`.
+    pub damage_per_force: f32,
+    /// The fraction of the throttle force lost once the vehicle is fully wrecked.
+    pub max_engine_power_loss: f32,
+    /// How far off center (in degrees) the steered wheels are once the vehicle is
+    /// fully wrecked. The wheels pull toward the side that took the most hits.
+    pub max_steering_misalignment: f32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Getters)]
 pub struct RaycastVehicleConfig {
     pub cabin_half_width: f32,
@@ -25,11 +39,45 @@ pub struct RaycastVehicleConfig {
     pub wheel_grip: f32,
     pub wheel_left_turn_angle: f32,
     pub wheel_right_turn_angle: f32,
+    /// How quickly (in degrees per second) the steered wheels turn toward
+    /// the angle requested by the steering input.
+    pub steering_rate: f32,
+    /// How quickly (in degrees per second) the steered wheels return to center
+    /// when there's no steering input.
+    pub steering_return_rate: f32,
+    /// The forward speed at and above which the steering angle is
+    /// scaled all the way down to `min_steering_angle_scale`.
+    pub steering_reduction_speed: f32,
+    /// How much of the max steering angle is available once the vehicle
+    /// reaches `steering_reduction_speed`.
+    pub min_steering_angle_scale: f32,
+    /// How much Ackermann geometry is applied to the steered wheels. 0 keeps
+    /// the steered wheels parallel and 1 makes the inner wheel turn tighter
+    /// than the outer one so that both follow the same turning circle.
+    pub ackermann_factor: f32,
     #[getset(get = "pub")]
     pub wheels: Vec<WheelConfig>,
     pub suspension_rest_length: f32,
     pub wheel_radius: f32,
     pub cabin_center_of_mass: Point<f32>,
+    /// How strongly the anti-roll bar of the front axle (wheels ahead of the cabin center)
+    /// resists the difference in suspension compression between its left and right wheels.
+    /// Like the suspension stiffness, this is scaled by the cabin mass.
+    pub front_anti_roll_stiffness: f32,
+    /// How strongly the anti-roll bar of the rear axle (wheels behind the cabin center)
+    /// resists the difference in suspension compression between its left and right wheels.
+    /// Like the suspension stiffness, this is scaled by the cabin mass.
+    pub rear_anti_roll_stiffness: f32,
+    /// The downward force applied to the cabin per squared unit of forward speed.
+    pub downforce_coefficient: f32,
+    /// The force opposing the cabin's movement per squared unit of speed.
+    pub drag_coefficient: f32,
+    /// Contacts between the cabin and other colliders with less total force than this
+    /// aren't reported as impacts. Keep this high enough that only a few contacts
+    /// are reported each step, as the physics world's event queue is small.
+    pub impact_force_threshold: f32,
+    /// How impacts wear the vehicle down. The vehicle can't be damaged if this is `None`.
+    pub damage: Option<VehicleDamageConfig>,
 }
 
 impl Default for RaycastVehicleConfig {
@@ -42,6 +90,12 @@ impl Default for RaycastVehicleConfig {
             cabin_half_height,
             cabin_half_length,
             cabin_center_of_mass: Point::new(0.0, -cabin_half_height, 0.0),
+            front_anti_roll_stiffness: 0.0,
+            rear_anti_roll_stiffness: 0.0,
+            downforce_coefficient: 0.0,
+            drag_coefficient: 0.0,
+            impact_force_threshold: 1000.0,
+            damage: None,
             suspension_spring_stiffness: 150.0,
             suspension_spring_dampening: 12.0,
             mass: 100.0,
@@ -52,6 +106,11 @@ impl Default for RaycastVehicleConfig {
             wheel_grip: 10.5,
             wheel_left_turn_angle: 40.0,
             wheel_right_turn_angle: -40.0,
+            steering_rate: 160.0,
+            steering_return_rate: 240.0,
+            steering_reduction_speed: 20.0,
+            min_steering_angle_scale: 0.5,
+            ackermann_factor: 0.0,
             wheels: vec![
                 WheelConfig {
                     suspension_rest_length: None,
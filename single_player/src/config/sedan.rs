@@ -1,7 +1,7 @@
 use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::config::raycast_vehicle::WheelConfig;
+use crate::config::raycast_vehicle::{VehicleDamageConfig, WheelConfig};
 use crate::config::RaycastVehicleConfig;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
@@ -10,6 +10,25 @@ pub enum CameraMode {
     Free,
     #[serde(rename = "track")]
     Track,
+    #[serde(rename = "hood")]
+    Hood,
+    #[serde(rename = "bumper")]
+    Bumper,
+    #[serde(rename = "cinematic")]
+    Cinematic,
+}
+
+impl CameraMode {
+    /// The mode that comes after this one when cycling through camera modes.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Free => Self::Track,
+            Self::Track => Self::Hood,
+            Self::Hood => Self::Bumper,
+            Self::Bumper => Self::Cinematic,
+            Self::Cinematic => Self::Free,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +43,26 @@ pub struct SedanConfig {
     pub track_mode_look_pitch_angle: f32,
     pub track_mode_look_yaw_angle: f32,
     pub track_mode_cam_lerp_factor: f32,
+    /// The forward speed at which the track camera reacts to speed the most.
+    pub track_mode_reference_speed: f32,
+    /// How much farther (in meters) the track camera pulls back at the reference speed.
+    pub track_mode_speed_boom_extension: f32,
+    /// How much lower (in degrees) the track camera looks down at the car at the reference speed.
+    pub track_mode_speed_pitch_offset: f32,
+    /// How far (in degrees) the track camera swings to look into turns at full steer.
+    pub track_mode_turn_look_yaw_angle: f32,
+    /// How much the camera's field of view (in degrees) should widen at the reference speed.
+    pub max_fov_kick: f32,
+    /// The cabin-relative position of the camera in hood mode.
+    pub hood_camera_offset: [f32; 3],
+    /// The cabin-relative position of the camera in bumper mode.
+    pub bumper_camera_offset: [f32; 3],
+    /// How many seconds it takes for the camera to blend from one mode to the next.
+    pub camera_mode_blend_duration: f32,
+    /// Impacts with this much force or more play their sound at full volume.
+    pub impact_audio_max_force: f32,
+    /// How many seconds must pass after an impact before another one is registered.
+    pub min_impact_interval: f32,
 }
 
 impl Default for SedanConfig {
@@ -43,6 +82,16 @@ impl Default for SedanConfig {
             track_mode_look_pitch_angle: -15.0,
             track_mode_look_yaw_angle: 0.0,
             track_mode_cam_lerp_factor: 0.99,
+            track_mode_reference_speed: 25.0,
+            track_mode_speed_boom_extension: 2.0,
+            track_mode_speed_pitch_offset: 7.0,
+            track_mode_turn_look_yaw_angle: 12.0,
+            max_fov_kick: 10.0,
+            hood_camera_offset: [0.0, cabin_half_height + 0.2, -cabin_half_length * 0.3],
+            bumper_camera_offset: [0.0, -cabin_half_height * 0.2, -cabin_half_length - 0.05],
+            camera_mode_blend_duration: 0.4,
+            impact_audio_max_force: 100000.0,
+            min_impact_interval: 0.3,
             raycast_vehicle_controller: RaycastVehicleConfig {
                 cabin_half_height,
                 cabin_half_length,
@@ -50,11 +99,27 @@ impl Default for SedanConfig {
                 suspension_spring_stiffness: 70.0,
                 suspension_spring_dampening: 5.0,
                 cabin_center_of_mass: Point::new(0.0, -cabin_half_height * 0.9, 0.0),
+                front_anti_roll_stiffness: 20.0,
+                rear_anti_roll_stiffness: 15.0,
+                downforce_coefficient: 0.2,
+                drag_coefficient: 0.02,
+                impact_force_threshold: 5000.0,
+                damage: Some(VehicleDamageConfig {
+                    min_damaging_force: 20000.0,
+                    damage_per_force: 1.0 / 400000.0,
+                    max_engine_power_loss: 0.6,
+                    max_steering_misalignment: 4.0,
+                }),
                 wheel_radius: 0.3,
                 throttle_force: 100.0,
                 brake_force: 100.0,
                 wheel_left_turn_angle: 10.0,
                 wheel_right_turn_angle: -10.0,
+                steering_rate: 40.0,
+                steering_return_rate: 60.0,
+                steering_reduction_speed: 25.0,
+                min_steering_angle_scale: 0.4,
+                ackermann_factor: 1.0,
                 mass: 50.0,
                 suspension_rest_length: 0.0,
                 wheels: vec![
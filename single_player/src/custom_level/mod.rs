@@ -0,0 +1,154 @@
+use crate::config::{LevelManifest, PlayerLevelConfig};
+use crate::registry::LevelImport;
+use crate::shared::{
+    ffi_status::{report_status, FfiError, FfiStatus},
+    player_level::{PlayerLevelContent, PlayerLevelSim},
+};
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A level built outside of this crate. The interface copies in the level's glTF
+/// and its manifest, then initializes it like any other level.
+pub type Sim<'a> = PlayerLevelSim<'a, CustomLevel>;
+
+/// What the interface copies in to describe the custom level.
+#[derive(Serialize, Deserialize)]
+pub struct CustomLevel {
+    manifest: LevelManifest,
+    scene_gltf_bytes: Vec<u8>,
+    /// Where the interface copies a manifest to before loading it
+    manifest_import_bytes: Vec<u8>,
+}
+
+impl<'a> PlayerLevelContent<'a> for CustomLevel {
+    fn config(&self) -> &PlayerLevelConfig {
+        &self.manifest.config
+    }
+
+    fn scene_gltf_bytes(&self) -> &[u8] {
+        &self.scene_gltf_bytes
+    }
+
+    fn spawn_poi(&self) -> &str {
+        &self.manifest.spawn_poi
+    }
+
+    fn finish_sensor(&self) -> &str {
+        &self.manifest.finish_sensor
+    }
+
+    fn music(&self) -> Option<(&str, f32)> {
+        self.manifest
+            .music
+            .as_deref()
+            .map(|music| (music, self.manifest.music_volume))
+    }
+
+    fn replay_copy(&self) -> Self {
+        Self {
+            manifest: self.manifest.clone(),
+            scene_gltf_bytes: self.scene_gltf_bytes.clone(),
+            manifest_import_bytes: Vec::new(),
+        }
+    }
+
+    fn level_import_mut<'s>(sim: &'s mut Sim<'a>) -> Option<&'s mut dyn LevelImport> {
+        Some(sim)
+    }
+}
+
+impl<'a> FromConfig for Sim<'a> {
+    type Config<'b> = LevelManifest;
+
+    fn from_config<'b>(manifest: Self::Config<'b>) -> Self {
+        Sim::new(CustomLevel {
+            manifest,
+            scene_gltf_bytes: Vec::new(),
+            manifest_import_bytes: Vec::new(),
+        })
+    }
+
+    fn set_config<'b>(&mut self, manifest: Self::Config<'b>) {
+        if self.level.is_initialized() {
+            warn!("Custom level Sim doesn't allow resetting its manifest once initialized");
+            return;
+        }
+        if let Err(manifest_err) = manifest.validate() {
+            warn!("{}", manifest_err);
+            return;
+        }
+        // Everything configured by the manifest is rebuilt with the new one
+        self.level.reconfigure(&manifest.config);
+        self.content.manifest = manifest;
+    }
+}
+
+impl<'a> Sim<'a> {
+    pub fn load_imported_manifest(&mut self) -> Result<(), FfiError> {
+        if self.level.is_initialized() {
+            return Err(FfiError::invalid_state(
+                "Custom level doesn't allow resetting its manifest once initialized",
+            ));
+        }
+        let manifest = String::from_utf8(self.content.manifest_import_bytes.clone())
+            .map_err(|utf8_err| utf8_err.to_string())
+            .and_then(|manifest_toml| LevelManifest::try_from_toml(&manifest_toml))
+            .map_err(|manifest_err| {
+                FfiError::invalid_input(format!("Couldn't load level manifest: {}", manifest_err))
+            })?;
+        manifest.validate().map_err(FfiError::invalid_input)?;
+        self.set_config(manifest);
+        Ok(())
+    }
+}
+
+// Builds hosting every level export their own FFI instead
+#[cfg_attr(not(feature = "all_levels"), ffi)]
+impl<'a> Sim<'a> {
+    /// Make room for the level's glTF for the interface to copy into.
+    pub fn scene_gltf_import_buffer(&mut self, scene_gltf_len: usize) -> *mut u8 {
+        self.content.scene_gltf_bytes = vec![0; scene_gltf_len];
+        self.content.scene_gltf_bytes.as_mut_ptr()
+    }
+
+    /// Make room for the level's manifest for the interface to copy into.
+    pub fn manifest_import_buffer(&mut self, manifest_len: usize) -> *mut u8 {
+        self.content.manifest_import_bytes = vec![0; manifest_len];
+        self.content.manifest_import_bytes.as_mut_ptr()
+    }
+
+    /// Configure the level with the manifest in the import buffer.
+    /// This must happen before initializing.
+    pub fn load_manifest(&mut self) -> FfiStatus {
+        let manifest_result = self.load_imported_manifest();
+        report_status(&mut self.level.last_error, manifest_result)
+    }
+}
+
+impl LevelImport for Sim<'_> {
+    fn scene_gltf_import_buffer(&mut self, scene_gltf_len: usize) -> *mut u8 {
+        Sim::scene_gltf_import_buffer(self, scene_gltf_len)
+    }
+
+    fn manifest_import_buffer(&mut self, manifest_len: usize) -> *mut u8 {
+        Sim::manifest_import_buffer(self, manifest_len)
+    }
+
+    fn load_manifest(&mut self) -> Result<(), FfiError> {
+        self.load_imported_manifest()
+    }
+}
+
+#[cfg(not(feature = "all_levels"))]
+#[no_mangle]
+pub extern "C" fn destroy_sim(sim_ptr: *mut Sim) {
+    // Box will deallocate the memory on drop
+    unsafe { Box::from_raw(sim_ptr) };
+}
+
+#[cfg(not(feature = "all_levels"))]
+#[no_mangle]
+pub extern "C" fn create_sim() -> *mut Sim<'static> {
+    init_perigee_logger();
+    Box::into_raw(Box::new(Sim::from_config(LevelManifest::default())))
+}
@@ -3,7 +3,10 @@ use std::time::Duration;
 use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::shared::checksum::StateChecksum;
 use crate::shared::descriptor::Descriptor;
+use crate::shared::level_requirements::LevelRequirements;
+use crate::shared::spline::Spline;
 
 #[derive(Serialize, Deserialize)]
 enum PlatformOperation {
@@ -23,6 +26,8 @@ pub struct MovingPlatform<'a> {
     sensor_name: &'a str,
     supported_bodies: Vec<RigidBodyHandle>,
     waypoints: Vec<Isometry3<f32>>,
+    /// The closed path through the waypoints that the platform travels along
+    path: Option<Spline>,
     waypoint_idx: usize,
     movement_state: PlatformMovementState,
     move_duration: Duration,
@@ -39,6 +44,7 @@ impl<'a> MovingPlatform<'a> {
             descriptor: name.into(),
             supported_bodies: Vec::new(),
             waypoints: Vec::new(),
+            path: None,
             waypoint_idx: 0,
             move_duration: Duration::from_secs_f32(3.0),
             wait_duration: Duration::from_secs_f32(2.0),
@@ -52,6 +58,11 @@ impl<'a> MovingPlatform<'a> {
     }
 
     pub fn initialize(&mut self, waypoints: Vec<Isometry3<f32>>, physics: &mut PhysicsWorld) {
+        let waypoint_translations: Vec<Vector3<f32>> = waypoints
+            .iter()
+            .map(|waypoint| waypoint.translation.vector)
+            .collect();
+        self.path = Spline::from_catmull_rom(&waypoint_translations, true);
         self.waypoints = waypoints;
 
         if let Some(sensor_handle) = physics.named_sensors.handle_with_name(self.sensor_name) {
@@ -82,17 +93,43 @@ impl<'a> MovingPlatform<'a> {
         }
     }
 
+    /// Put the platform back at its first waypoint, about to move to the next one.
+    pub fn reset(&mut self, physics: &mut PhysicsWorld) {
+        self.waypoint_idx = 0;
+        self.movement_state
+            .operation
+            .transition_to(PlatformOperation::Transitioning);
+        self.movement_state.clock.reset();
+        self.supported_bodies.clear();
+        while self.sensor_event_channel.get_message().is_ok() {}
+
+        let start_position = self.waypoint(self.waypoint_idx);
+        if let Some(plat_body) = physics
+            .named_rigid_bodies
+            .handle_with_name(self.descriptor.object_name())
+            .and_then(|plat_handle| physics.rigid_body_set.get_mut(*plat_handle))
+        {
+            plat_body.set_position(start_position, true);
+        }
+        if let Some(sensor) = physics
+            .named_sensors
+            .handle_with_name(self.sensor_name)
+            .and_then(|sens_handle| physics.collider_set.get_mut(*sens_handle))
+        {
+            sensor.set_position(start_position * self.sensor_local_iso);
+        }
+    }
+
     pub fn update(&mut self, physics: &mut PhysicsWorld, delta_seconds: f32) {
         self.movement_state.clock.tick(delta_seconds);
 
         match self.movement_state.operation.current_state() {
             &PlatformOperation::Transitioning => {
-                let t = self.movement_state.clock.elapsed().as_secs_f32()
-                    / self.move_duration.as_secs_f32();
+                let t = (self.movement_state.clock.elapsed().as_secs_f32()
+                    / self.move_duration.as_secs_f32())
+                .min(1.0);
 
-                let current_position = self
-                    .waypoint(self.waypoint_idx)
-                    .lerp_slerp(&self.waypoint(self.next_waypoint_index()), t);
+                let current_position = self.isometry_between_waypoints(t);
 
                 let mut platform_dist_traveled = Vector3::zeros();
 
@@ -146,6 +183,22 @@ impl<'a> MovingPlatform<'a> {
         }
     }
 
+    /// Where the platform is the provided fraction of the way from its
+    /// current waypoint to the next one along its path.
+    fn isometry_between_waypoints(&self, t: f32) -> Isometry3<f32> {
+        let from = self.waypoint(self.waypoint_idx);
+        let to = self.waypoint(self.next_waypoint_index());
+        let translation = match &self.path {
+            Some(path) => path.point_at_distance(lerp(
+                path.segment_start_distance(self.waypoint_idx),
+                path.segment_start_distance(self.waypoint_idx + 1),
+                t,
+            )),
+            None => from.translation.vector.lerp(&to.translation.vector, t),
+        };
+        Isometry3::from_parts(translation.into(), from.rotation.slerp(&to.rotation, t))
+    }
+
     pub fn waypoint(&self, i: usize) -> Isometry3<f32> {
         self.waypoints[i]
     }
@@ -154,6 +207,18 @@ impl<'a> MovingPlatform<'a> {
         (self.waypoint_idx + 1) % self.waypoints.len()
     }
 
+    /// Add what the platform needs from the level's glTF to the provided requirements.
+    pub fn add_requirements(&self, requirements: &mut LevelRequirements) {
+        requirements
+            .rigid_bodies
+            .push(String::from(self.descriptor.object_name()));
+        requirements.sensors.push(String::from(self.sensor_name));
+    }
+
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        checksum.write_state(&self.movement_state.operation);
+    }
+
     pub fn add_supported_body(&mut self, new_body: RigidBodyHandle) {
         self.supported_bodies.push(new_body);
     }
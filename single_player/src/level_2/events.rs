@@ -1,18 +1,26 @@
+use crate::shared::events::ResetReason;
 use serde::{Deserialize, Serialize};
 
+/// Something that happened in the level that the interface should know about.
+/// It's sent to the interface as JSON, with its variant's name in `type`.
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum Level2Event {
-    LevelCompleted,
-    PlayerReset,
-    CheckpointReached,
-}
-
-impl AsRef<str> for Level2Event {
-    fn as_ref(&self) -> &str {
-        match self {
-            Self::LevelCompleted => "LEVEL_COMPLETED",
-            Self::PlayerReset => "PLAYER_RESET",
-            Self::CheckpointReached => "CHECKPOINT_REACHED",
-        }
-    }
+    LevelCompleted {
+        completion_seconds: f32,
+    },
+    PlayerReset {
+        reason: ResetReason,
+        /// Where the player's car was put back
+        position: [f32; 3],
+        run_seconds: f32,
+    },
+    CheckpointReached {
+        checkpoint_index: u8,
+        run_seconds: f32,
+    },
+    /// The player's car drove into water and its engine cut out
+    EngineDrowned {
+        run_seconds: f32,
+    },
 }
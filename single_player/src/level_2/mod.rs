@@ -1,16 +1,38 @@
 use crate::config::Level2Config;
-use crate::shared::{input::Input, prefabs::Sedan, settings::GameSettings};
+use crate::registry::{Level, Vehicle};
+use crate::shared::{
+    checksum::StateChecksum,
+    events::ResetReason,
+    ffi_status::{report_status, str_from_ffi, write_to_slot, FfiError, FfiStatus},
+    ghost::{GhostKind, GhostPlayback, GhostRecorder},
+    input::Input,
+    level_requirements::{LevelInitError, LevelRequirements},
+    prefabs::Sedan,
+    replay::{InputLog, ReplayedFrame},
+    settings::GameSettings,
+    spline::{path_points_of_interest, Spline},
+    time_scale::TimeScale,
+    timestep::{FixedTimestep, RigidBodySnapshot},
+    water::WaterVolumes,
+};
 
 use events::Level2Event;
+use opponent::Opponent;
 use perigee::prelude::*;
+use perigee::rapier3d::na::Translation3;
 use serde::{Deserialize, Serialize};
+use std::ffi::CString;
 
 mod events;
+mod opponent;
 
 extern "C" {
-    fn level_event_hook(event_type_ptr: *const u8, event_type_len: usize);
+    fn level_event_hook(event_json_ptr: *const u8, event_json_len: usize);
 }
 
+/// The sensor marking the finish line, which not every version of the track has.
+static FINISH_SENSOR: &str = "Finish Sensor";
+
 #[derive(Serialize, Deserialize)]
 pub struct Sim<'a> {
     version: (u8, u8, u8),
@@ -19,8 +41,43 @@ pub struct Sim<'a> {
     pub physics: PhysicsWorld,
     pois: PointsOfInterest,
     pub car: Sedan<'a>,
+    opponents: Vec<Opponent<'a>>,
+    racing_line: Option<Spline>,
+    ghost_recorder: GhostRecorder,
+    ghost_playback: Option<GhostPlayback>,
+    /// The most recently finished recording of the player's run
+    ghost_recording_bytes: Vec<u8>,
+    /// Where the interface copies a recording to before loading it as the ghost
+    ghost_import_bytes: Vec<u8>,
+    /// Everything the player did up until the level was completed
+    input_log: InputLog,
+    /// The input log of the completed run, ready for the interface to save
+    input_log_bytes: Vec<u8>,
+    /// Where the interface copies an input log to before verifying it
+    replay_import_bytes: Vec<u8>,
+    /// Where the interface copies a glTF to before validating it
+    gltf_validation_bytes: Vec<u8>,
+    /// Why the most recent FFI call that failed couldn't be completed
+    last_error: String,
+    /// The checksum of the simulation's state as of the latest tick
+    state_checksum: u32,
+    timestep: FixedTimestep,
+    time_scale: TimeScale,
+    /// The player's car's cabin followed by its wheels as of the previous tick
+    previous_car_isometries: Vec<Isometry3<f32>>,
+    /// Each opponent's cabin followed by its wheels as of the previous tick
+    previous_opponent_isometries: Vec<Vec<Isometry3<f32>>>,
+    previous_camera_isometry: Isometry3<f32>,
+    #[serde(skip)]
+    previous_rigid_body_isometries: RigidBodySnapshot,
+    initialized: bool,
+    level_completed: bool,
+    /// Whether the interface is kept out of what the sim does, as when checking a replay
+    headless: bool,
     scene_gltf_bytes: &'a [u8],
     #[serde(skip)]
+    finish_sensor_event_channel: ColliderEventChannel,
+    #[serde(skip)]
     pub input: Input,
 }
 
@@ -30,11 +87,44 @@ impl<'a> FromConfig for Sim<'a> {
     fn from_config<'b>(config: Self::Config<'b>) -> Self {
         let physics = PhysicsWorld::from_config(&config.physics);
         let car = Sedan::from_config(&config.car);
+        let opponents = (0..config.opponent_count)
+            .map(|_| Opponent::new(&config))
+            .collect();
+        // The car's ghost is made of its cabin followed by each of its wheels
+        let ghost_recorder = GhostRecorder::new(
+            GhostKind::Sedan,
+            config.ghost_sample_rate,
+            config.max_ghost_seconds,
+            1 + config.car.raycast_vehicle_controller.wheels.len(),
+        );
+        let timestep = FixedTimestep::new(config.tick_rate);
 
         Self {
             version: (0, 0, 0),
             config,
             car,
+            opponents,
+            racing_line: None,
+            ghost_recorder,
+            ghost_playback: None,
+            ghost_recording_bytes: Vec::new(),
+            ghost_import_bytes: Vec::new(),
+            input_log: InputLog::default(),
+            input_log_bytes: Vec::new(),
+            replay_import_bytes: Vec::new(),
+            gltf_validation_bytes: Vec::new(),
+            last_error: String::new(),
+            state_checksum: 0,
+            timestep,
+            time_scale: TimeScale::default(),
+            previous_car_isometries: Vec::new(),
+            previous_opponent_isometries: Vec::new(),
+            previous_camera_isometry: Isometry::identity(),
+            previous_rigid_body_isometries: RigidBodySnapshot::default(),
+            initialized: false,
+            level_completed: false,
+            headless: false,
+            finish_sensor_event_channel: ColliderEventChannel::default(),
             physics,
             settings: GameSettings::default(),
             input: Input::default(),
@@ -55,18 +145,45 @@ impl<'a> Sim<'a> {
     }
 
     pub fn send_level_event(&self, evt: Level2Event) {
-        let level_event = evt.as_ref();
-        unsafe { level_event_hook(level_event.as_ptr(), level_event.len()) };
+        if self.headless {
+            return;
+        }
+        match serde_json::to_string(&evt) {
+            Ok(level_event) => unsafe { level_event_hook(level_event.as_ptr(), level_event.len()) },
+            Err(json_err) => warn!("Couldn't serialize level event {:?}: {}", evt, json_err),
+        }
     }
 
-    pub fn initialize(&mut self) {
+    /// Everything the level expects to find in its glTF. The finish line,
+    /// trackside cameras, and racing line are all optional.
+    pub fn requirements(&self) -> LevelRequirements {
+        LevelRequirements {
+            pois: vec![String::from("Track Start")],
+            sensors: Vec::new(),
+            rigid_bodies: Vec::new(),
+        }
+    }
+
+    fn finish_sensor_handle(&self) -> Option<ColliderHandle> {
+        self.physics
+            .named_sensors
+            .handle_with_name(FINISH_SENSOR)
+            .copied()
+    }
+
+    pub fn initialize(&mut self) -> Result<(), LevelInitError> {
+        if self.initialized {
+            return Err(LevelInitError::AlreadyInitialized);
+        }
+
         // Load static colliders using trimeshes extracted from geometries
         // within a glTF. This lets you create a level using your favoritte 3D
         // modeling tool.
-        let scene_gltf = Gltf::from_slice(self.scene_gltf_bytes).unwrap();
-
-        self.physics.load_from_gltf(&scene_gltf, None).unwrap();
-        self.pois.load_from_gltf(&scene_gltf).unwrap();
+        let scene_gltf = self.requirements().load_gltf(
+            self.scene_gltf_bytes,
+            &mut self.physics,
+            &mut self.pois,
+        )?;
 
         self.car.initialize(
             &self.config.car,
@@ -74,12 +191,516 @@ impl<'a> Sim<'a> {
             Some(self.pois["Track Start"]),
             Some(String::from("Sedan")),
         );
+        let water_volumes = WaterVolumes::from_gltf(&scene_gltf, &self.physics);
+        self.car.controller.set_water_volumes(water_volumes.clone());
+        // Trackside cameras are laid out as a path, e.g. `Cinematic Camera.path.1`
+        let (cinematic_camera_isometries, _) = path_points_of_interest(
+            &self.pois,
+            scene_gltf.nodes().filter_map(|node| node.name()),
+            "Cinematic Camera",
+        );
+        self.car
+            .set_cinematic_camera_isometries(cinematic_camera_isometries);
 
-        loop_audio(self.car.scene_object_name(), "LEVEL_MUSIC", 1.0, 0.2);
+        self.racing_line = Spline::from_points_of_interest(
+            &self.pois,
+            scene_gltf.nodes().filter_map(|node| node.name()),
+            "Racing Line",
+        );
+        let track_start = self.pois["Track Start"];
+        for (opponent_index, opponent) in self.opponents.iter_mut().enumerate() {
+            opponent.initialize(
+                &self.config,
+                &mut self.physics,
+                self.racing_line.as_ref(),
+                Self::grid_isometry(track_start, opponent_index),
+                format!("Opponent {}", opponent_index + 1),
+            );
+            opponent
+                .car
+                .controller
+                .set_water_volumes(water_volumes.clone());
+        }
+
+        // Not every version of the track has a finish line
+        if let Some(finish_sensor_handle) = self.finish_sensor_handle() {
+            self.physics.listen_to_collider(
+                finish_sensor_handle,
+                ColliderEventRelayer::from(self.finish_sensor_event_channel.clone_sender()),
+            );
+        }
+
+        self.time_scale
+            .loop_audio(self.car.scene_object_name(), "LEVEL_MUSIC", 1.0, 0.2);
+
+        self.ghost_recorder.start();
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// Where an opponent starts. Opponents line up in a staggered grid behind the player.
+    fn grid_isometry(track_start: Isometry3<f32>, opponent_index: usize) -> Isometry3<f32> {
+        let grid_side = if opponent_index.is_multiple_of(2) {
+            -1.0
+        } else {
+            1.0
+        };
+        let grid_row = (opponent_index / 2 + 1) as f32;
+        track_start * Translation3::new(3.0 * grid_side, 0.0, 7.0 * grid_row)
+    }
+
+    /// Where the car goes when the player asks to reset: back onto the racing line where
+    /// the car is and facing along it, or to the start of the track if there's no racing line.
+    fn reset_isometry(&self) -> Isometry3<f32> {
+        let track_start = self.pois["Track Start"];
+        self.racing_line
+            .as_ref()
+            .map_or(track_start, |racing_line| {
+                let cabin_position = self.car.controller.cabin_isometry().translation.vector;
+                racing_line.frame_at_distance(racing_line.closest_distance(&cabin_position))
+            })
+    }
+
+    /// Put everything back the way it was when the level started,
+    /// reusing what's already been loaded into the physics world.
+    pub fn restart(&mut self) -> Result<(), FfiError> {
+        if !self.initialized {
+            return Err(FfiError::invalid_state(
+                "Level 2 can't be restarted before it's initialized",
+            ));
+        }
+        let track_start = self.pois["Track Start"];
+        self.car
+            .respawn(&self.config.car, &mut self.physics, track_start);
+        for (opponent_index, opponent) in self.opponents.iter_mut().enumerate() {
+            opponent.restart(
+                &self.config,
+                &mut self.physics,
+                self.racing_line.as_ref(),
+                Self::grid_isometry(track_start, opponent_index),
+            );
+        }
+        self.level_completed = false;
+
+        // Anything that happened before the restart no longer matters
+        while self.finish_sensor_event_channel.get_message().is_ok() {}
+        self.input.wipe();
+        self.timestep.reset();
+        self.capture_previous_isometries();
+
+        self.input_log = InputLog::default();
+        self.input_log_bytes.clear();
+        self.ghost_recorder.start();
+        if let Some(ghost_playback) = &mut self.ghost_playback {
+            ghost_playback.restart();
+        }
+
+        let car_name = self.car.scene_object_name();
+        for audio_name in ["LEVEL_MUSIC", "LEVEL_VICTORY"] {
+            self.time_scale.stop_audio(car_name, audio_name);
+        }
+        self.time_scale
+            .loop_audio(car_name, "LEVEL_MUSIC", 1.0, 0.2);
+        Ok(())
+    }
+
+    fn ghost_isometries_per_frame(&self) -> usize {
+        1 + self.config.car.raycast_vehicle_controller.wheels.len()
+    }
+
+    /// Stop recording the player's run and keep it around for the interface to save.
+    pub fn finish_ghost_recording(&mut self) {
+        if self.ghost_recorder.is_recording() {
+            self.ghost_recording_bytes = self.ghost_recorder.stop().to_bytes();
+        }
+    }
+
+    /// Keep the completed run's input log around for the interface to save.
+    fn finish_input_log(&mut self) {
+        match self.input_log.try_to_toml() {
+            Ok(input_log_toml) => self.input_log_bytes = input_log_toml.into_bytes(),
+            Err(input_log_err) => warn!("Couldn't save input log: {}", input_log_err),
+        }
+    }
+
+    /// Re-run the level from scratch with the provided input log and make sure
+    /// it's completed at the claimed time.
+    pub fn verify_input_log(
+        config: Level2Config,
+        input_log: &InputLog,
+        claimed_completion_seconds: f32,
+    ) -> Result<(), String> {
+        let mut replay_sim = Self::from_config(config);
+        replay_sim.make_headless();
+        replay_sim
+            .initialize()
+            .map_err(|init_err| init_err.to_string())?;
+        // Without a finish line, no run on the track can ever be completed
+        if replay_sim.finish_sensor_handle().is_none() {
+            return Err(format!(
+                "Track has no \"{}\", so runs on it can't be verified",
+                FINISH_SENSOR
+            ));
+        }
+        let tick_seconds = replay_sim.timestep.tick_seconds();
+        input_log.verify_completion(tick_seconds, claimed_completion_seconds, |frame| {
+            replay_sim.settings = frame.settings;
+            replay_sim.input = frame.input;
+            replay_sim.tick(frame.delta_seconds);
+            ReplayedFrame {
+                level_completed: replay_sim.level_completed,
+                state_checksum: replay_sim.state_checksum,
+            }
+        })
+    }
+
+    /// Keep the interface from hearing about or playing anything the sim does.
+    fn make_headless(&mut self) {
+        self.headless = true;
+        self.time_scale.set_headless(true);
+        self.car.set_headless(true);
+        for opponent in self.opponents.iter_mut() {
+            opponent.car.set_headless(true);
+        }
+    }
+
+    /// Hash everything that decides how the level plays out from here on.
+    fn compute_state_checksum(&self) -> u32 {
+        let mut checksum = StateChecksum::default();
+        checksum.write_rigid_bodies(&self.physics.rigid_body_set);
+        self.car.hash_state(&mut checksum);
+        for opponent in &self.opponents {
+            opponent.car.hash_state(&mut checksum);
+        }
+        checksum.value()
+    }
+
+    /// Keep where everything is so the interface can interpolate from it.
+    fn capture_previous_isometries(&mut self) {
+        self.previous_rigid_body_isometries
+            .capture(&self.physics.rigid_body_set);
+        self.previous_car_isometries = self.car.isometries();
+        self.previous_opponent_isometries = self
+            .opponents
+            .iter()
+            .map(|opponent| opponent.car.isometries())
+            .collect();
+        self.previous_camera_isometry = self.car.camera_isometry();
+    }
+
+    /// Advance the game simulation by the provided number of seconds. The
+    /// simulation ticks at a fixed rate, so this may run any number of ticks.
+    pub fn advance(&mut self, delta_seconds: f32) -> Result<(), FfiError> {
+        if !self.initialized {
+            return Err(FfiError::invalid_state(
+                "Level 2 can't be stepped before it's initialized",
+            ));
+        }
+        let tick_count = self
+            .timestep
+            .accumulate(self.time_scale.scaled_delta_seconds(delta_seconds));
+        for _ in 0..tick_count {
+            self.tick(self.timestep.tick_seconds());
+            // Looking only happens once however many ticks the step runs
+            self.input.consume_look();
+        }
+        // Input is held onto until a tick has gotten to use it
+        if tick_count > 0 {
+            self.input.wipe();
+        }
+        Ok(())
+    }
+
+    /// Tick the game simulation forward by the provided number of seconds.
+    pub fn tick(&mut self, delta_seconds: f32) {
+        // There's nothing to simulate without a level
+        if !self.initialized {
+            return;
+        }
+
+        self.capture_previous_isometries();
+
+        // The frame the level is completed on is the last one logged
+        let logging_frame = !self.level_completed;
+        let frame_input = self.input;
+        // How long the player will have been playing once this tick is logged
+        let run_seconds = self.input_log.elapsed_seconds() + delta_seconds;
+
+        if self.input.reset() {
+            let reset_isometry = self.reset_isometry();
+            self.car
+                .respawn(&self.config.car, &mut self.physics, reset_isometry);
+            // The car jumps straight back onto the track rather than sliding over to it
+            self.capture_previous_isometries();
+            self.send_level_event(Level2Event::PlayerReset {
+                reason: ResetReason::Requested,
+                position: reset_isometry.translation.vector.into(),
+                run_seconds,
+            });
+        }
+
+        let engine_was_drowned = self.car.controller.is_engine_drowned();
+        self.car.update(
+            &self.config.car,
+            &self.settings,
+            &self.input,
+            &mut self.physics,
+            delta_seconds,
+        );
+        if !engine_was_drowned && self.car.controller.is_engine_drowned() {
+            self.send_level_event(Level2Event::EngineDrowned { run_seconds });
+        }
+        for opponent in self.opponents.iter_mut() {
+            opponent.update(
+                &self.config,
+                self.racing_line.as_ref(),
+                &mut self.physics,
+                delta_seconds,
+            );
+        }
+
+        self.physics.step(delta_seconds);
+
+        self.finish_game_on_finish_sensor_detection(run_seconds);
+        self.update_ghost(delta_seconds);
+
+        self.state_checksum = self.compute_state_checksum();
+        if logging_frame {
+            self.input_log.record(
+                delta_seconds,
+                self.settings,
+                frame_input,
+                self.state_checksum,
+            );
+            if self.level_completed {
+                self.finish_input_log();
+            }
+        }
+    }
+
+    fn finish_game_on_finish_sensor_detection(&mut self, run_seconds: f32) {
+        while let Ok(finish_sensor_event) = self.finish_sensor_event_channel.get_message() {
+            match finish_sensor_event {
+                ColliderEvent::IntersectionStart(other) => {
+                    // Only the player's car can complete the level
+                    if !self.level_completed
+                        && self
+                            .physics
+                            .collider_set
+                            .get(other)
+                            .and_then(|other_collider| other_collider.parent())
+                            .filter(|other_body_handle| {
+                                other_body_handle == &self.car.controller.cabin_body_handle()
+                            })
+                            .is_some()
+                    {
+                        self.send_level_event(Level2Event::LevelCompleted {
+                            completion_seconds: run_seconds,
+                        });
+                        self.finish_ghost_recording();
+                        self.time_scale
+                            .stop_audio(self.car.scene_object_name(), "LEVEL_MUSIC");
+                        self.time_scale.play_audio(
+                            self.car.scene_object_name(),
+                            "LEVEL_VICTORY",
+                            1.0,
+                            0.5,
+                        );
+                        self.level_completed = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Have every car's sounds play as fast as simulated time passes.
+    fn apply_playback_rate(&mut self) {
+        let playback_rate = self.time_scale.playback_rate();
+        self.car.set_time_scale(playback_rate);
+        for opponent in self.opponents.iter_mut() {
+            opponent.car.set_time_scale(playback_rate);
+        }
+    }
+
+    fn update_ghost(&mut self, delta_seconds: f32) {
+        self.ghost_recorder
+            .update(&self.car.isometries(), 0, delta_seconds);
+
+        if let Some(ghost_playback) = &mut self.ghost_playback {
+            ghost_playback.advance(delta_seconds);
+        }
+    }
+
+    fn prop_body_handle(&self, prop_name: &str) -> Result<RigidBodyHandle, FfiError> {
+        self.physics
+            .named_rigid_bodies
+            .handle_with_name(prop_name)
+            .copied()
+            .ok_or_else(|| FfiError::not_found(format!("No prop is named \"{}\"", prop_name)))
+    }
+
+    pub fn find_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        let prop_body_handle = self.prop_body_handle(prop_name)?;
+        self.physics
+            .rigid_body_set
+            .get(prop_body_handle)
+            .map(|prop_body| *prop_body.position())
+            .ok_or_else(|| {
+                FfiError::not_found(format!("Prop \"{}\" isn't in the physics world", prop_name))
+            })
+    }
+
+    pub fn find_previous_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        let prop_body_handle = self.prop_body_handle(prop_name)?;
+        self.previous_rigid_body_isometries
+            .isometry(prop_body_handle)
+            .copied()
+            .ok_or_else(|| {
+                FfiError::not_found(format!(
+                    "Prop \"{}\" wasn't in the physics world last tick",
+                    prop_name
+                ))
+            })
+    }
+
+    pub fn find_poi(&self, poi_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.pois.point_with_name(poi_name).copied().ok_or_else(|| {
+            FfiError::not_found(format!("No point of interest is named \"{}\"", poi_name))
+        })
+    }
+
+    /// Make sure every car has a wheel with the provided index.
+    fn wheel_index(&self, wheel_idx: u32) -> Result<usize, FfiError> {
+        let wheel_count = self.car.controller.wheel_count();
+        if (wheel_idx as usize) < wheel_count {
+            Ok(wheel_idx as usize)
+        } else {
+            Err(FfiError::not_found(format!(
+                "Cars have {} wheels, so there's no wheel {}",
+                wheel_count, wheel_idx
+            )))
+        }
+    }
+
+    fn opponent_index(&self, opponent_idx: u32) -> Result<usize, FfiError> {
+        if (opponent_idx as usize) < self.opponents.len() {
+            Ok(opponent_idx as usize)
+        } else {
+            Err(FfiError::not_found(format!(
+                "There are {} opponents, so there's no opponent {}",
+                self.opponents.len(),
+                opponent_idx
+            )))
+        }
+    }
+
+    pub fn find_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        let wheel_index = self.wheel_index(wheel_idx)?;
+        Ok(self.car.controller.wheel_isometry(wheel_index))
+    }
+
+    pub fn find_previous_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        let wheel_index = self.wheel_index(wheel_idx)?;
+        Ok(self
+            .previous_car_isometries
+            .get(1 + wheel_index)
+            .copied()
+            .unwrap_or_else(Isometry3::identity))
+    }
+
+    pub fn find_opponent_cabin_isometry(
+        &self,
+        opponent_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        let opponent_index = self.opponent_index(opponent_idx)?;
+        Ok(*self.opponents[opponent_index]
+            .car
+            .controller
+            .cabin_isometry())
+    }
+
+    pub fn find_opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        let opponent_index = self.opponent_index(opponent_idx)?;
+        let wheel_index = self.wheel_index(wheel_idx)?;
+        Ok(self.opponents[opponent_index]
+            .car
+            .controller
+            .wheel_isometry(wheel_index))
+    }
+
+    pub fn find_previous_opponent_cabin_isometry(
+        &self,
+        opponent_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        let opponent_index = self.opponent_index(opponent_idx)?;
+        Ok(self
+            .previous_opponent_isometries
+            .get(opponent_index)
+            .and_then(|opponent_isometries| opponent_isometries.first())
+            .copied()
+            .unwrap_or_else(Isometry3::identity))
+    }
+
+    pub fn find_previous_opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        let opponent_index = self.opponent_index(opponent_idx)?;
+        let wheel_index = self.wheel_index(wheel_idx)?;
+        Ok(self
+            .previous_opponent_isometries
+            .get(opponent_index)
+            .and_then(|opponent_isometries| opponent_isometries.get(1 + wheel_index))
+            .copied()
+            .unwrap_or_else(Isometry3::identity))
+    }
+
+    pub fn find_ghost_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        let wheel_index = self.wheel_index(wheel_idx)?;
+        self.ghost_isometry(1 + wheel_index)
+    }
+
+    pub fn ghost_isometry(&self, isometry_index: usize) -> Result<Isometry3<f32>, FfiError> {
+        self.ghost_playback
+            .as_ref()
+            .map(|ghost_playback| ghost_playback.isometry(isometry_index))
+            .ok_or_else(|| FfiError::invalid_state("No ghost is loaded"))
+    }
+
+    fn load_imported_ghost(&mut self) -> Result<(), FfiError> {
+        let ghost_playback = GhostPlayback::from_bytes(
+            &self.ghost_import_bytes,
+            GhostKind::Sedan,
+            self.ghost_isometries_per_frame(),
+        )
+        .map_err(|ghost_err| {
+            FfiError::invalid_input(format!("Couldn't load ghost: {}", ghost_err))
+        })?;
+        self.ghost_playback = Some(ghost_playback);
+        Ok(())
+    }
+
+    fn verify_imported_replay(&self, claimed_completion_seconds: f32) -> Result<(), FfiError> {
+        String::from_utf8(self.replay_import_bytes.clone())
+            .map_err(|utf8_err| utf8_err.to_string())
+            .and_then(|input_log_toml| InputLog::try_from_toml(&input_log_toml))
+            .and_then(|input_log| {
+                Sim::verify_input_log(self.config.clone(), &input_log, claimed_completion_seconds)
+            })
+            .map_err(|verification_err| {
+                FfiError::invalid_input(format!("Couldn't verify replay: {}", verification_err))
+            })
     }
 }
 
-#[ffi]
+// Builds hosting every level export their own FFI instead
+#[cfg_attr(not(feature = "all_levels"), ffi)]
 impl<'a> Sim<'a> {
     pub fn scene_gltf_bytes_ptr(&self) -> *const u8 {
         self.scene_gltf_bytes().as_ptr()
@@ -89,48 +710,118 @@ impl<'a> Sim<'a> {
         self.scene_gltf_bytes().len()
     }
 
-    #[slot_return]
-    pub fn prop_isometry(&self, prop_name: &str) -> &Isometry3<f32> {
-        let prop_body_handle = self
-            .physics
-            .named_rigid_bodies
-            .handle_with_name(prop_name)
-            .expect("No prop has provided name.");
-        self.physics
-            .rigid_body_set
-            .get(*prop_body_handle)
-            .expect("Prop with provided name doesn't exist in physics world.")
-            .position()
+    pub fn prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.find_prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.last_error, prop_result)
     }
 
-    #[slot_return]
-    pub fn poi(&self, poi_name: &str) -> Isometry3<f32> {
-        self.pois[poi_name]
+    pub fn previous_prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.find_previous_prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.last_error, prop_result)
+    }
+
+    pub fn poi(
+        &mut self,
+        poi_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let poi_result = str_from_ffi(poi_name)
+            .and_then(|poi_name| self.find_poi(poi_name))
+            .and_then(|poi_isometry| write_to_slot(isometry_slot, poi_isometry));
+        report_status(&mut self.last_error, poi_result)
     }
 
     // Making this an FFI-only wrapper because if the WASM has a
     // function "initialize" it's not obvious what type it's initializing.
-    pub fn initialize_sim(&mut self) {
-        self.initialize();
+    pub fn initialize_sim(&mut self) -> FfiStatus {
+        let init_result = self.initialize().map_err(FfiError::from);
+        report_status(&mut self.last_error, init_result)
+    }
+
+    /// Why the most recent call that didn't return an OK status failed.
+    pub fn last_error_ptr(&self) -> *const u8 {
+        self.last_error.as_ptr()
+    }
+
+    pub fn last_error_len(&self) -> usize {
+        self.last_error.len()
+    }
+
+    /// Make room for a glTF of the provided length for the interface to copy into.
+    pub fn gltf_validation_buffer(&mut self, gltf_len: usize) -> *mut u8 {
+        self.gltf_validation_bytes = vec![0; gltf_len];
+        self.gltf_validation_bytes.as_mut_ptr()
+    }
+
+    /// Check the glTF in the validation buffer has everything the level needs.
+    pub fn validate_gltf(&mut self) -> FfiStatus {
+        let validation_result = self
+            .requirements()
+            .validate_gltf(&self.gltf_validation_bytes)
+            .map_err(FfiError::from);
+        report_status(&mut self.last_error, validation_result)
+    }
+
+    pub fn restart_level(&mut self) -> FfiStatus {
+        let restart_result = self.restart();
+        report_status(&mut self.last_error, restart_result)
     }
 
     pub fn desired_fps(&self) -> f32 {
-        30.0
+        self.config.tick_rate
     }
 
-    /// Step the game simulation by the provided number of seconds.
-    pub fn step(&mut self, delta_seconds: f32) {
-        self.car.update(
-            &self.config.car,
-            &self.settings,
-            &self.input,
-            &mut self.physics,
-            delta_seconds,
-        );
+    /// How far (from 0 to 1) rendering is between the previous tick and the current one.
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.timestep.alpha()
+    }
 
-        self.physics.step(delta_seconds);
+    /// Step the game simulation by the provided number of seconds. The
+    /// simulation ticks at a fixed rate, so a step may run any number of ticks.
+    pub fn step(&mut self, delta_seconds: f32) -> FfiStatus {
+        let step_result = self.advance(delta_seconds);
+        report_status(&mut self.last_error, step_result)
+    }
 
-        self.input.wipe();
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale.scale()
+    }
+
+    /// Make simulated time pass at the provided rate relative to real time.
+    pub fn set_time_scale(&mut self, new_time_scale: f32) {
+        // Animations and looping audio start over when given a new rate, so only do it if needed
+        if self.time_scale.set_scale(new_time_scale) {
+            self.apply_playback_rate();
+        }
+    }
+
+    pub fn is_paused(&self) -> u8 {
+        self.time_scale.is_paused() as u8
+    }
+
+    // Naming these "pause" and "resume" would clash with libc's pause function.
+    /// Stop simulated time from passing, no matter how the simulation is stepped.
+    pub fn pause_sim(&mut self) {
+        self.time_scale.pause();
+        self.apply_playback_rate();
+    }
+
+    /// Let simulated time pass again. Looping audio starts over from the beginning.
+    pub fn resume_sim(&mut self) {
+        self.time_scale.resume();
+        self.apply_playback_rate();
     }
 
     pub fn settings_left_right_look_sensitivity(&self) -> u8 {
@@ -159,39 +850,430 @@ impl<'a> Sim<'a> {
         self.input.set_move_right(new_magnitude);
     }
 
+    /// How far to look up since the last frame. This adds up until a tick uses it.
     pub fn input_set_rotate_up(&mut self, new_magnitude: f32) {
-        self.input.set_rotate_up(new_magnitude);
+        self.input.add_rotate_up(new_magnitude);
     }
 
+    /// How far to turn right since the last frame. This adds up until a tick uses it.
     pub fn input_set_rotate_right(&mut self, new_magnitude: f32) {
-        self.input.set_rotate_right(new_magnitude);
+        self.input.add_rotate_right(new_magnitude);
     }
 
+    pub fn input_set_look_back(&mut self, look_back_val: u8) {
+        self.input.set_look_back(look_back_val > 0);
+    }
     pub fn input_set_jump(&mut self, _new_magnitude: f32) {}
     pub fn input_set_aim(&mut self, _new_magnitude: f32) {}
+    pub fn input_set_dash(&mut self, _dash_val: u8) {}
+    pub fn input_set_grapple(&mut self, _grapple_val: u8) {}
+    pub fn input_set_reel(&mut self, _new_magnitude: f32) {}
 
     #[slot_return]
     pub fn camera_global_isometry(&self) -> Isometry3<f32> {
         self.car.camera_isometry()
     }
 
+    #[slot_return]
+    pub fn previous_camera_global_isometry(&self) -> Isometry3<f32> {
+        self.previous_camera_isometry
+    }
+
+    pub fn camera_fov_kick(&self) -> f32 {
+        self.car.camera_fov_kick()
+    }
+
     #[slot_return]
     pub fn car_cabin_isometry(&self) -> Isometry3<f32> {
         *self.car.controller.cabin_isometry()
     }
 
+    pub fn wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .find_wheel_isometry(wheel_idx)
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
     #[slot_return]
-    pub fn wheel_isometry(&self, wheel_idx: u32) -> Isometry3<f32> {
-        self.car.controller.wheel_isometry(wheel_idx as usize)
+    pub fn previous_car_cabin_isometry(&self) -> Isometry3<f32> {
+        self.previous_car_isometries
+            .first()
+            .copied()
+            .unwrap_or_else(Isometry3::identity)
+    }
+
+    pub fn previous_wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .find_previous_wheel_isometry(wheel_idx)
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn cycle_camera_mode(&mut self) {
+        self.car.cycle_camera_mode(&self.physics);
+    }
+
+    pub fn opponent_count(&self) -> u32 {
+        self.opponents.len() as u32
+    }
+
+    pub fn opponent_cabin_isometry(
+        &mut self,
+        opponent_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let cabin_result = self
+            .find_opponent_cabin_isometry(opponent_idx)
+            .and_then(|cabin_isometry| write_to_slot(isometry_slot, cabin_isometry));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn opponent_wheel_isometry(
+        &mut self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .find_opponent_wheel_isometry(opponent_idx, wheel_idx)
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn previous_opponent_cabin_isometry(
+        &mut self,
+        opponent_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let cabin_result = self
+            .find_previous_opponent_cabin_isometry(opponent_idx)
+            .and_then(|cabin_isometry| write_to_slot(isometry_slot, cabin_isometry));
+        report_status(&mut self.last_error, cabin_result)
+    }
+
+    pub fn previous_opponent_wheel_isometry(
+        &mut self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let wheel_result = self
+            .find_previous_opponent_wheel_isometry(opponent_idx, wheel_idx)
+            .and_then(|wheel_isometry| write_to_slot(isometry_slot, wheel_isometry));
+        report_status(&mut self.last_error, wheel_result)
+    }
+
+    pub fn stop_ghost_recording(&mut self) {
+        self.finish_ghost_recording();
+    }
+
+    pub fn ghost_recording_ptr(&self) -> *const u8 {
+        self.ghost_recording_bytes.as_ptr()
+    }
+
+    pub fn ghost_recording_len(&self) -> usize {
+        self.ghost_recording_bytes.len()
+    }
+
+    /// Make room for a recording of the provided length for the interface to copy into.
+    pub fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        self.ghost_import_bytes = vec![0; recording_len];
+        self.ghost_import_bytes.as_mut_ptr()
+    }
+
+    /// Play back the recording in the import buffer alongside the car.
+    pub fn load_ghost(&mut self) -> FfiStatus {
+        let ghost_result = self.load_imported_ghost();
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    pub fn has_ghost(&self) -> u8 {
+        self.ghost_playback.is_some() as u8
+    }
+
+    pub fn ghost_cabin_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let ghost_result = self
+            .ghost_isometry(0)
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    pub fn ghost_wheel_isometry(
+        &mut self,
+        wheel_idx: u32,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let ghost_result = self
+            .find_ghost_wheel_isometry(wheel_idx)
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.last_error, ghost_result)
+    }
+
+    /// How many seconds the player has been playing, which is
+    /// their completion time once the level is completed.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.input_log.elapsed_seconds()
+    }
+
+    pub fn state_checksum(&self) -> u32 {
+        self.state_checksum
+    }
+
+    pub fn input_log_ptr(&self) -> *const u8 {
+        self.input_log_bytes.as_ptr()
+    }
+
+    pub fn input_log_len(&self) -> usize {
+        self.input_log_bytes.len()
+    }
+
+    /// Make room for an input log of the provided length for the interface to copy into.
+    pub fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        self.replay_import_bytes = vec![0; input_log_len];
+        self.replay_import_bytes.as_mut_ptr()
+    }
+
+    /// Re-run the level with the input log in the import buffer
+    /// and make sure it's completed at the claimed time.
+    pub fn verify_replay(&mut self, claimed_completion_seconds: f32) -> FfiStatus {
+        let verification = self.verify_imported_replay(claimed_completion_seconds);
+        report_status(&mut self.last_error, verification)
+    }
+
+    pub fn car_damage(&self) -> f32 {
+        self.car.controller.damage()
+    }
+
+    /// Put the car back at the start of the track on the next step.
+    pub fn reset_car(&mut self) {
+        self.input.set_reset(true);
+    }
+}
+
+impl Level for Sim<'static> {
+    fn scene_gltf_bytes(&self) -> &[u8] {
+        Sim::scene_gltf_bytes(self)
+    }
+
+    fn requirements(&self) -> LevelRequirements {
+        Sim::requirements(self)
+    }
+
+    fn initialize(&mut self) -> Result<(), LevelInitError> {
+        Sim::initialize(self)
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    fn restart(&mut self) -> Result<(), FfiError> {
+        Sim::restart(self)
+    }
+
+    fn step(&mut self, delta_seconds: f32) -> Result<(), FfiError> {
+        self.advance(delta_seconds)
+    }
+
+    fn desired_fps(&self) -> f32 {
+        Sim::desired_fps(self)
+    }
+
+    fn interpolation_alpha(&self) -> f32 {
+        Sim::interpolation_alpha(self)
+    }
+
+    fn time_scale(&self) -> f32 {
+        Sim::time_scale(self)
+    }
+
+    fn set_time_scale(&mut self, new_time_scale: f32) {
+        Sim::set_time_scale(self, new_time_scale);
+    }
+
+    fn is_paused(&self) -> bool {
+        self.time_scale.is_paused()
+    }
+
+    fn pause(&mut self) {
+        self.pause_sim();
+    }
+
+    fn resume(&mut self) {
+        self.resume_sim();
+    }
+
+    fn settings(&self) -> &GameSettings {
+        &self.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut GameSettings {
+        &mut self.settings
+    }
+
+    fn input_mut(&mut self) -> &mut Input {
+        &mut self.input
+    }
+
+    fn prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.find_prop_isometry(prop_name)
+    }
+
+    fn previous_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.find_previous_prop_isometry(prop_name)
+    }
+
+    fn poi(&self, poi_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.find_poi(poi_name)
+    }
+
+    fn camera_global_isometry(&self) -> Isometry3<f32> {
+        Sim::camera_global_isometry(self)
+    }
+
+    fn previous_camera_global_isometry(&self) -> Isometry3<f32> {
+        Sim::previous_camera_global_isometry(self)
+    }
+
+    fn stop_ghost_recording(&mut self) {
+        self.finish_ghost_recording();
+    }
+
+    fn ghost_recording_bytes(&self) -> &[u8] {
+        &self.ghost_recording_bytes
+    }
+
+    fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        Sim::ghost_import_buffer(self, recording_len)
+    }
+
+    fn load_ghost(&mut self) -> Result<(), FfiError> {
+        self.load_imported_ghost()
+    }
+
+    fn has_ghost(&self) -> bool {
+        self.ghost_playback.is_some()
+    }
+
+    fn elapsed_seconds(&self) -> f32 {
+        self.input_log.elapsed_seconds()
+    }
+
+    fn state_checksum(&self) -> u32 {
+        self.state_checksum
+    }
+
+    fn input_log_bytes(&self) -> &[u8] {
+        &self.input_log_bytes
+    }
+
+    fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        Sim::replay_import_buffer(self, input_log_len)
+    }
+
+    fn verify_replay(&self, claimed_completion_seconds: f32) -> Result<(), FfiError> {
+        self.verify_imported_replay(claimed_completion_seconds)
+    }
+
+    fn vehicle(&self) -> Option<&dyn Vehicle> {
+        Some(self)
+    }
+
+    fn vehicle_mut(&mut self) -> Option<&mut dyn Vehicle> {
+        Some(self)
+    }
+}
+
+impl Vehicle for Sim<'_> {
+    fn camera_fov_kick(&self) -> f32 {
+        Sim::camera_fov_kick(self)
+    }
+
+    fn cabin_isometry(&self) -> Isometry3<f32> {
+        self.car_cabin_isometry()
+    }
+
+    fn previous_cabin_isometry(&self) -> Isometry3<f32> {
+        self.previous_car_cabin_isometry()
+    }
+
+    fn wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        self.find_wheel_isometry(wheel_idx)
+    }
+
+    fn previous_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        self.find_previous_wheel_isometry(wheel_idx)
+    }
+
+    fn cycle_camera_mode(&mut self) {
+        Sim::cycle_camera_mode(self);
+    }
+
+    fn damage(&self) -> f32 {
+        self.car_damage()
+    }
+
+    fn reset(&mut self) {
+        self.reset_car();
+    }
+
+    fn opponent_count(&self) -> u32 {
+        Sim::opponent_count(self)
+    }
+
+    fn opponent_cabin_isometry(&self, opponent_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        self.find_opponent_cabin_isometry(opponent_idx)
+    }
+
+    fn opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        self.find_opponent_wheel_isometry(opponent_idx, wheel_idx)
+    }
+
+    fn previous_opponent_cabin_isometry(
+        &self,
+        opponent_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        self.find_previous_opponent_cabin_isometry(opponent_idx)
+    }
+
+    fn previous_opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError> {
+        self.find_previous_opponent_wheel_isometry(opponent_idx, wheel_idx)
+    }
+
+    fn ghost_cabin_isometry(&self) -> Result<Isometry3<f32>, FfiError> {
+        self.ghost_isometry(0)
+    }
+
+    fn ghost_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError> {
+        self.find_ghost_wheel_isometry(wheel_idx)
     }
 }
 
+#[cfg(not(feature = "all_levels"))]
 #[no_mangle]
 pub extern "C" fn destroy_sim(sim_ptr: *mut Sim) {
     // Box will deallocate the memory on drop
     unsafe { Box::from_raw(sim_ptr) };
 }
 
+#[cfg(not(feature = "all_levels"))]
 #[no_mangle]
 pub extern "C" fn create_sim() -> *mut Sim<'static> {
     init_perigee_logger();
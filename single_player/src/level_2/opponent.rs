@@ -0,0 +1,82 @@
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Level2Config;
+use crate::shared::controllers::racing_driver::RacingDriver;
+use crate::shared::input::Input;
+use crate::shared::prefabs::Sedan;
+use crate::shared::spline::Spline;
+
+/// An AI-driven car racing against the player.
+#[derive(Serialize, Deserialize)]
+pub struct Opponent<'a> {
+    pub car: Sedan<'a>,
+    driver: RacingDriver,
+    #[serde(skip)]
+    input: Input,
+}
+
+impl<'a> Opponent<'a> {
+    pub fn new(config: &Level2Config) -> Self {
+        Self {
+            car: Sedan::from_config(&config.car),
+            driver: RacingDriver::default(),
+            input: Input::default(),
+        }
+    }
+
+    pub fn initialize(
+        &mut self,
+        config: &Level2Config,
+        physics: &mut PhysicsWorld,
+        racing_line: Option<&Spline>,
+        initial_isometry: Isometry3<f32>,
+        name: String,
+    ) {
+        self.car
+            .initialize(&config.car, physics, Some(initial_isometry), Some(name));
+        if let Some(racing_line) = racing_line {
+            self.driver
+                .start_from(racing_line, &initial_isometry.translation.vector);
+        }
+    }
+
+    /// Put the opponent back at the provided isometry, ready to race again.
+    pub fn restart(
+        &mut self,
+        config: &Level2Config,
+        physics: &mut PhysicsWorld,
+        racing_line: Option<&Spline>,
+        isometry: Isometry3<f32>,
+    ) {
+        self.car.respawn(&config.car, physics, isometry);
+        if let Some(racing_line) = racing_line {
+            self.driver
+                .start_from(racing_line, &isometry.translation.vector);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        config: &Level2Config,
+        racing_line: Option<&Spline>,
+        physics: &mut PhysicsWorld,
+        delta_seconds: f32,
+    ) {
+        // Without a racing line to follow the opponent just sits on the grid
+        if let Some(racing_line) = racing_line {
+            self.driver.drive(
+                &config.racing_driver,
+                config.opponent_difficulty,
+                racing_line,
+                self.car.controller.cabin_isometry(),
+                self.car.controller.forward_speed(&physics.rigid_body_set),
+                &mut self.input,
+            );
+        }
+        // Nobody sees an opponent's camera, so it's left where it is
+        self.car
+            .drive(&config.car, &self.input, physics, delta_seconds);
+        self.input.wipe();
+    }
+}
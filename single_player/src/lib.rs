@@ -1,12 +1,23 @@
 mod config;
+pub mod registry;
 mod shared;
 
 #[cfg(feature = "level_1")]
 pub mod level_1;
-#[cfg(feature = "level_1")]
+#[cfg(all(feature = "level_1", not(feature = "all_levels")))]
 pub use level_1::*;
 
 #[cfg(feature = "level_2")]
 pub mod level_2;
-#[cfg(feature = "level_2")]
+#[cfg(all(feature = "level_2", not(feature = "all_levels")))]
 pub use level_2::*;
+
+#[cfg(feature = "custom_level")]
+pub mod custom_level;
+#[cfg(all(feature = "custom_level", not(feature = "all_levels")))]
+pub use custom_level::*;
+
+#[cfg(feature = "all_levels")]
+pub mod all_levels;
+#[cfg(feature = "all_levels")]
+pub use all_levels::*;
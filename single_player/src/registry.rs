@@ -0,0 +1,156 @@
+use crate::shared::{
+    ffi_status::FfiError,
+    input::Input,
+    level_requirements::{LevelInitError, LevelRequirements},
+    settings::GameSettings,
+};
+use perigee::prelude::*;
+
+#[cfg(feature = "level_1")]
+use crate::config::Level1Config;
+#[cfg(feature = "level_2")]
+use crate::config::Level2Config;
+#[cfg(feature = "custom_level")]
+use crate::config::LevelManifest;
+
+/// What every level's simulation can do, no matter what's in the level.
+/// An interface can drive any level through this without knowing which one it is.
+pub trait Level {
+    fn scene_gltf_bytes(&self) -> &[u8];
+    fn requirements(&self) -> LevelRequirements;
+    fn initialize(&mut self) -> Result<(), LevelInitError>;
+    fn is_initialized(&self) -> bool;
+    fn restart(&mut self) -> Result<(), FfiError>;
+    fn step(&mut self, delta_seconds: f32) -> Result<(), FfiError>;
+    fn desired_fps(&self) -> f32;
+    fn interpolation_alpha(&self) -> f32;
+    fn time_scale(&self) -> f32;
+    fn set_time_scale(&mut self, new_time_scale: f32);
+    fn is_paused(&self) -> bool;
+    fn pause(&mut self);
+    fn resume(&mut self);
+    fn settings(&self) -> &GameSettings;
+    fn settings_mut(&mut self) -> &mut GameSettings;
+    fn input_mut(&mut self) -> &mut Input;
+    fn prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError>;
+    fn previous_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError>;
+    fn poi(&self, poi_name: &str) -> Result<Isometry3<f32>, FfiError>;
+    fn camera_global_isometry(&self) -> Isometry3<f32>;
+    fn previous_camera_global_isometry(&self) -> Isometry3<f32>;
+    fn stop_ghost_recording(&mut self);
+    fn ghost_recording_bytes(&self) -> &[u8];
+    fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8;
+    fn load_ghost(&mut self) -> Result<(), FfiError>;
+    fn has_ghost(&self) -> bool;
+    fn elapsed_seconds(&self) -> f32;
+    fn state_checksum(&self) -> u32;
+    fn input_log_bytes(&self) -> &[u8];
+    fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8;
+    fn verify_replay(&self, claimed_completion_seconds: f32) -> Result<(), FfiError>;
+
+    /// The player character, if the level is played as one.
+    fn player(&self) -> Option<&dyn PlayerCharacter> {
+        None
+    }
+
+    /// The car, if the level is played driving one.
+    fn vehicle(&self) -> Option<&dyn Vehicle> {
+        None
+    }
+
+    fn vehicle_mut(&mut self) -> Option<&mut dyn Vehicle> {
+        None
+    }
+
+    /// Where the interface copies the level in to, if it's built outside of this crate.
+    fn level_import_mut(&mut self) -> Option<&mut dyn LevelImport> {
+        None
+    }
+}
+
+/// What a level played as the player character can say about them.
+pub trait PlayerCharacter {
+    fn player_gltf_bytes(&self) -> &[u8];
+    fn body_isometry(&self) -> Isometry3<f32>;
+    fn previous_body_isometry(&self) -> Isometry3<f32>;
+    fn ghost_body_isometry(&self) -> Result<Isometry3<f32>, FfiError>;
+}
+
+/// What a level played driving a car can say about it and the cars racing it.
+pub trait Vehicle {
+    fn camera_fov_kick(&self) -> f32;
+    fn cabin_isometry(&self) -> Isometry3<f32>;
+    fn previous_cabin_isometry(&self) -> Isometry3<f32>;
+    fn wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError>;
+    fn previous_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError>;
+    fn cycle_camera_mode(&mut self);
+    fn damage(&self) -> f32;
+    fn reset(&mut self);
+    fn opponent_count(&self) -> u32;
+    fn opponent_cabin_isometry(&self, opponent_idx: u32) -> Result<Isometry3<f32>, FfiError>;
+    fn opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError>;
+    fn previous_opponent_cabin_isometry(
+        &self,
+        opponent_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError>;
+    fn previous_opponent_wheel_isometry(
+        &self,
+        opponent_idx: u32,
+        wheel_idx: u32,
+    ) -> Result<Isometry3<f32>, FfiError>;
+    fn ghost_cabin_isometry(&self) -> Result<Isometry3<f32>, FfiError>;
+    fn ghost_wheel_isometry(&self, wheel_idx: u32) -> Result<Isometry3<f32>, FfiError>;
+}
+
+/// How the interface hands over a level built outside of this crate.
+pub trait LevelImport {
+    fn scene_gltf_import_buffer(&mut self, scene_gltf_len: usize) -> *mut u8;
+    fn manifest_import_buffer(&mut self, manifest_len: usize) -> *mut u8;
+    fn load_manifest(&mut self) -> Result<(), FfiError>;
+}
+
+/// A level that can be created by its ID.
+pub struct RegisteredLevel {
+    pub id: u32,
+    pub name: &'static str,
+    create: fn() -> Box<dyn Level>,
+}
+
+/// Every level built into this module, in the order they're played.
+pub static LEVELS: &[RegisteredLevel] = &[
+    #[cfg(feature = "level_1")]
+    RegisteredLevel {
+        id: 1,
+        name: "Level 1",
+        create: || Box::new(crate::level_1::Sim::from_config(Level1Config::default())),
+    },
+    #[cfg(feature = "level_2")]
+    RegisteredLevel {
+        id: 2,
+        name: "Level 2",
+        create: || Box::new(crate::level_2::Sim::from_config(Level2Config::default())),
+    },
+    // The interface provides this level's glTF and manifest after creating it
+    #[cfg(feature = "custom_level")]
+    RegisteredLevel {
+        id: 0,
+        name: "Custom Level",
+        create: || {
+            Box::new(crate::custom_level::Sim::from_config(
+                LevelManifest::default(),
+            ))
+        },
+    },
+];
+
+/// Create the level with the provided ID, if it's been built into this module.
+pub fn create_level(level_id: u32) -> Option<Box<dyn Level>> {
+    LEVELS
+        .iter()
+        .find(|registered_level| registered_level.id == level_id)
+        .map(|registered_level| (registered_level.create)())
+}
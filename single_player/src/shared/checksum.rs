@@ -0,0 +1,106 @@
+use perigee::prelude::*;
+use std::hash::{Hash, Hasher};
+use std::mem::discriminant;
+
+static FNV_OFFSET_BASIS: u32 = 0x811c9dc5;
+static FNV_PRIME: u32 = 0x01000193;
+
+/// A cheap FNV-1a hash of simulation state. Two runs that hash their state
+/// in the same order come out with the same checksum only if they haven't diverged.
+#[derive(Debug, Clone, Copy)]
+pub struct StateChecksum(u32);
+
+impl Default for StateChecksum {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl Hasher for StateChecksum {
+    fn finish(&self) -> u64 {
+        self.0 as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u32;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // Pointer-sized integers are written as 64 bits so that
+    // checksums match between native and WebAssembly builds
+    fn write_usize(&mut self, n: usize) {
+        self.write(&(n as u64).to_le_bytes());
+    }
+
+    fn write_isize(&mut self, n: isize) {
+        self.write(&(n as i64).to_le_bytes());
+    }
+}
+
+impl StateChecksum {
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn write_f32(&mut self, n: f32) {
+        self.write(&n.to_bits().to_le_bytes());
+    }
+
+    pub fn write_vector(&mut self, vector: &Vector3<f32>) {
+        for component in vector.iter() {
+            self.write_f32(*component);
+        }
+    }
+
+    pub fn write_isometry(&mut self, isometry: &Isometry3<f32>) {
+        self.write_vector(&isometry.translation.vector);
+        for component in isometry.rotation.coords.iter() {
+            self.write_f32(*component);
+        }
+    }
+
+    /// Hash the position and velocity of every rigid body in the physics world.
+    pub fn write_rigid_bodies(&mut self, rigid_body_set: &RigidBodySet) {
+        for (_, rigid_body) in rigid_body_set.iter() {
+            self.write_isometry(rigid_body.position());
+            self.write_vector(rigid_body.linvel());
+            self.write_vector(rigid_body.angvel());
+        }
+    }
+
+    /// Hash which state a state machine is in. Data carried by the state isn't hashed.
+    pub fn write_state<T>(&mut self, state_machine: &StateMachine<T>) {
+        discriminant(state_machine.current_state()).hash(self);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksum_changes_with_state() {
+        let mut rigid_body_set = RigidBodySet::new();
+        let body_handle = rigid_body_set.insert(RigidBodyBuilder::dynamic().build());
+        let checksum_of = |rigid_body_set: &RigidBodySet| {
+            let mut checksum = StateChecksum::default();
+            checksum.write_rigid_bodies(rigid_body_set);
+            checksum.value()
+        };
+
+        let initial_checksum = checksum_of(&rigid_body_set);
+        assert_eq!(initial_checksum, checksum_of(&rigid_body_set));
+        rigid_body_set[body_handle].set_linvel(Vector3::new(0.0, 0.0, 1e-6), false);
+        assert_ne!(initial_checksum, checksum_of(&rigid_body_set));
+
+        let mut state_machine = StateMachine::new(Some(1.0));
+        let mut some_checksum = StateChecksum::default();
+        some_checksum.write_state(&state_machine);
+        state_machine.transition_to(None);
+        let mut none_checksum = StateChecksum::default();
+        none_checksum.write_state(&state_machine);
+        assert_ne!(some_checksum.value(), none_checksum.value());
+    }
+}
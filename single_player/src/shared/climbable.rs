@@ -0,0 +1,26 @@
+use crate::shared::descriptor::tagged_colliders;
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The tags that mark an object in a level's glTF as something that can be climbed.
+pub static CLIMBABLE_TAGS: [&str; 2] = ["ladder", "climbable"];
+
+/// The colliders in a level that can be climbed, like ladders.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ClimbableSurfaces {
+    collider_handles: Vec<ColliderHandle>,
+}
+
+impl ClimbableSurfaces {
+    /// Find the colliders of the objects tagged `ladder` or `climbable` in a level's glTF,
+    /// e.g. `Tower Ladder.ladder`.
+    pub fn from_gltf(gltf: &Gltf, physics: &PhysicsWorld) -> Self {
+        Self {
+            collider_handles: tagged_colliders(gltf, physics, &CLIMBABLE_TAGS),
+        }
+    }
+
+    pub fn contains(&self, collider_handle: &ColliderHandle) -> bool {
+        self.collider_handles.contains(collider_handle)
+    }
+}
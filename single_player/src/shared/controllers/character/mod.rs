@@ -1,12 +1,16 @@
 use crate::config::character_controller::{CharacterControllerConfig, MovementMode};
 use crate::shared::boom::Boom;
+use crate::shared::checksum::StateChecksum;
+use crate::shared::climbable::ClimbableSurfaces;
 use crate::shared::controllers::character::utils::*;
-use crate::shared::events::CharacterControllerEvent;
+use crate::shared::events::{CharacterControllerEvent, SlideKind, WallSide};
 use crate::shared::input::Input;
 use crate::shared::settings::GameSettings;
 use crate::shared::vectors::*;
+use crate::shared::water::WaterVolumes;
 use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
 use std::time::Duration;
 
 pub mod utils;
@@ -28,11 +32,47 @@ pub struct CharacterController {
     rigid_body_handle: RigidBodyHandle,
     collider_handle: ColliderHandle,
     pub wallrunning_state: StateMachine<WallRunning>,
+    /// How long the character controller has been on its current wallrun
+    wallrunning_timer: PassiveClock,
+    /// The world space normal of the wall being run on
+    wallrunning_wall_normal: Vector3<f32>,
+    /// The normal of the wall the last wallrun ran out of time on, if the character
+    /// controller hasn't landed since
+    spent_wall_normal: Option<Vector3<f32>>,
+    /// The normal of the wall last jumped off of, and how many jumps in a row have been off of it
+    last_kicked_wall: Option<(Vector3<f32>, u8)>,
+    pub wall_climb_state: StateMachine<WallClimbing>,
+    wall_climb_timer: PassiveClock,
+    /// Whether the character controller has climbed a wall since it last landed
+    wall_climb_spent: bool,
+    pub climbing_state: StateMachine<ClimbingState>,
+    /// How long since the character controller last let go of a ladder
+    ladder_timer: PassiveClock,
     pub crouch_state: StateMachine<CrouchState>,
     ground_normal: Option<Vector3<f32>>,
     coyote_timer: PassiveClock,
     jump_cooldown_timer: PassiveClock,
+    air_dash_cooldown_timer: PassiveClock,
+    /// How many air jumps have been used since the character controller last
+    /// landed, touched a wall or grabbed a ledge
+    air_jumps_used: u8,
+    /// How many air dashes have been used since the character controller last
+    /// landed, touched a wall or grabbed a ledge
+    air_dashes_used: u8,
+    /// Whether jump was held last tick, so air jumps need a fresh press
+    jump_was_held: bool,
+    /// Whether dash was held last tick, so air dashes need a fresh press
+    dash_was_held: bool,
     pub sliding_state: StateMachine<SlidingState>,
+    pub ledge_state: StateMachine<LedgeState>,
+    /// How long the character controller has been hanging or mantling
+    ledge_timer: PassiveClock,
+    /// How long since the character controller last let go of a ledge, if it has
+    /// since it was last moved somewhere new
+    ledge_let_go_clock: Option<PassiveClock>,
+    pub swimming_state: StateMachine<SwimmingState>,
+    water_volumes: WaterVolumes,
+    climbable_surfaces: ClimbableSurfaces,
     #[serde(skip)]
     event_channel: EventChannel<CharacterControllerEvent>,
 }
@@ -69,11 +109,31 @@ impl FromConfig for CharacterController {
             rigid_body_handle: RigidBodyHandle::default(),
             collider_handle: ColliderHandle::default(),
             wallrunning_state: StateMachine::new(WallRunning::None),
+            wallrunning_timer: PassiveClock::default(),
+            wallrunning_wall_normal: Vector3::zeros(),
+            spent_wall_normal: None,
+            last_kicked_wall: None,
+            wall_climb_state: StateMachine::new(WallClimbing::None),
+            wall_climb_timer: PassiveClock::default(),
+            wall_climb_spent: false,
+            climbing_state: StateMachine::new(ClimbingState::None),
+            ladder_timer: PassiveClock::default(),
             crouch_state: StateMachine::new(CrouchState::Upright),
             ground_normal: Some(Vector::y()),
             coyote_timer: PassiveClock::default(),
             jump_cooldown_timer: PassiveClock::default(),
+            air_dash_cooldown_timer: PassiveClock::default(),
+            air_jumps_used: 0,
+            air_dashes_used: 0,
+            jump_was_held: false,
+            dash_was_held: false,
             sliding_state: StateMachine::new(SlidingState::None),
+            ledge_state: StateMachine::new(LedgeState::None),
+            ledge_timer: PassiveClock::default(),
+            ledge_let_go_clock: None,
+            swimming_state: StateMachine::new(SwimmingState::None),
+            water_volumes: WaterVolumes::default(),
+            climbable_surfaces: ClimbableSurfaces::default(),
             event_channel: EventChannel::with_capacity(config.event_queue_capacity),
         }
     }
@@ -88,6 +148,20 @@ impl CharacterController {
         self.event_channel.get_message()
     }
 
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        checksum.write_state(&self.perspective_mode);
+        checksum.write_state(&self.movement_mode);
+        checksum.write_state(&self.wallrunning_state);
+        checksum.write_state(&self.wall_climb_state);
+        checksum.write_state(&self.climbing_state);
+        checksum.write_state(&self.crouch_state);
+        checksum.write_state(&self.sliding_state);
+        checksum.write_state(&self.ledge_state);
+        checksum.write_state(&self.swimming_state);
+        checksum.write_u8(self.air_jumps_used);
+        checksum.write_u8(self.air_dashes_used);
+    }
+
     fn build_collider(
         &self,
         config: &CharacterControllerConfig,
@@ -167,6 +241,10 @@ impl CharacterController {
     ) {
         self.update_body_isometry(&mut physics.rigid_body_set);
         self.update_head_isometry(config, delta_seconds);
+        let jump_pressed = input.jump() && !self.jump_was_held;
+        self.jump_was_held = input.jump();
+        let dash_pressed = input.dash() && !self.dash_was_held;
+        self.dash_was_held = input.dash();
 
         if self.perspective_mode.is_third_person() {
             self.update_boom_isometry(
@@ -214,13 +292,19 @@ impl CharacterController {
         let (_capsule_half_height, capsule_radius) = self.capsule_values(config);
 
         let previous_tick_grounded_state = self.is_grounded();
+        let previous_tick_linear_velocity = self.body_linear_velocity;
         self.determine_grounded_state(
             config,
             &mut physics.rigid_body_set,
             &mut physics.query_pipeline,
             &mut physics.collider_set,
         );
+        let previous_tick_swimming_state = *self.swimming_state.current_state();
+        self.determine_swimming_state(config, &physics.collider_set);
         let previous_tick_wallrunning_state = *self.wallrunning_state.current_state();
+        if self.wallrunning_state != WallRunning::None {
+            self.wallrunning_timer.tick(delta_seconds);
+        }
         self.determine_wallrunning_state(
             config,
             &mut physics.rigid_body_set,
@@ -228,6 +312,27 @@ impl CharacterController {
             &mut physics.query_pipeline,
             &mut physics.collider_set,
         );
+        self.ladder_timer.tick(delta_seconds);
+        self.determine_climbing_state(
+            config,
+            input.move_forward(),
+            &mut physics.rigid_body_set,
+            capsule_radius,
+            &mut physics.query_pipeline,
+            &mut physics.collider_set,
+        );
+        let previous_tick_wall_climb_state = *self.wall_climb_state.current_state();
+        if self.wall_climb_state != WallClimbing::None {
+            self.wall_climb_timer.tick(delta_seconds);
+        }
+        self.determine_wall_climb_state(
+            config,
+            input.move_forward(),
+            &mut physics.rigid_body_set,
+            capsule_radius,
+            &mut physics.query_pipeline,
+            &mut physics.collider_set,
+        );
         self.determine_linear_velocity(&mut physics.rigid_body_set);
         let previous_tick_sliding_state = *self.sliding_state.current_state();
         self.determine_sliding_state(config);
@@ -237,19 +342,60 @@ impl CharacterController {
             if self.is_grounded() {
                 // We can't be grounded and wallrunning at the same time
                 self.wallrunning_state.transition_to(WallRunning::None);
-                self.event_channel.send(CharacterControllerEvent::Landed);
+                self.event_channel.send(CharacterControllerEvent::Landed {
+                    impact_speed: (-previous_tick_linear_velocity.y).max(0.0),
+                });
                 self.coyote_timer.reset();
+                self.restore_air_moves();
+                self.restore_wall_moves();
             } else {
                 // We've just taken off
             }
         }
+        if previous_tick_swimming_state != *self.swimming_state.current_state() {
+            match (
+                previous_tick_swimming_state,
+                self.swimming_state.current_state(),
+            ) {
+                (SwimmingState::None, _) => {
+                    self.event_channel
+                        .send(CharacterControllerEvent::StartedSwimming);
+                    self.restore_air_moves();
+                    self.restore_wall_moves();
+                }
+                (_, SwimmingState::None) => {
+                    self.event_channel
+                        .send(CharacterControllerEvent::StoppedSwimming);
+                }
+                (SwimmingState::Surface(_), SwimmingState::Underwater(_)) => {
+                    self.event_channel.send(CharacterControllerEvent::Dived);
+                }
+                _ => {
+                    self.event_channel.send(CharacterControllerEvent::Surfaced);
+                }
+            }
+        }
         if previous_tick_wallrunning_state != *self.wallrunning_state.current_state() {
             // We're entered a new wallrun
             if self.wallrunning_state != WallRunning::None && !self.is_grounded() {
                 self.start_wallrunning(config, &mut physics.rigid_body_set);
-                self.event_channel
-                    .send(CharacterControllerEvent::StartedWallRunning);
+                let wall = match self.wallrunning_state.current_state() {
+                    WallRunning::OnLeft(wall_normal) => Some((WallSide::Left, *wall_normal)),
+                    WallRunning::OnRight(wall_normal) => Some((WallSide::Right, *wall_normal)),
+                    WallRunning::None => None,
+                };
+                if let Some((side, local_wall_normal)) = wall {
+                    self.event_channel
+                        .send(CharacterControllerEvent::StartedWallRunning {
+                            side,
+                            wall_normal: self
+                                .body_isometry()
+                                .transform_vector(&local_wall_normal)
+                                .into(),
+                        });
+                }
                 self.coyote_timer.reset();
+                self.restore_air_moves();
             } else {
                 // We've exited a wallrun
                 self.stop_wallrunning(&mut physics.rigid_body_set);
@@ -257,13 +403,35 @@ impl CharacterController {
                     .send(CharacterControllerEvent::StoppedWallRunning);
             }
         }
+        if self.wallrunning_state != WallRunning::None {
+            self.ramp_wallrunning_gravity(config, &mut physics.rigid_body_set);
+        }
+        if previous_tick_wall_climb_state != *self.wall_climb_state.current_state() {
+            if self.wall_climb_state != WallClimbing::None {
+                self.wall_climb_timer.reset();
+                self.event_channel
+                    .send(CharacterControllerEvent::StartedWallClimbing);
+                self.restore_air_moves();
+            } else {
+                self.wall_climb_spent = true;
+                self.event_channel
+                    .send(CharacterControllerEvent::StoppedWallClimbing);
+            }
+        }
+        if self.wall_climb_state != WallClimbing::None {
+            self.climb_wall(config, &mut physics.rigid_body_set);
+        }
         self.tilt_head(config, delta_seconds);
 
         if previous_tick_sliding_state != *self.sliding_state.current_state() {
             if self.sliding_state.current_state() != &SlidingState::None {
                 self.start_sliding(config, &mut physics.rigid_body_set);
+                let kind = match self.sliding_state.current_state() {
+                    SlidingState::Downhill => SlideKind::Downhill,
+                    _ => SlideKind::Normal,
+                };
                 self.event_channel
-                    .send(CharacterControllerEvent::StartedSliding);
+                    .send(CharacterControllerEvent::StartedSliding { kind });
             } else {
                 self.stop_sliding(&mut physics.rigid_body_set);
                 self.event_channel
@@ -275,7 +443,47 @@ impl CharacterController {
             }
         }
 
+        self.ledge_timer.tick(delta_seconds);
+        if let Some(ledge_let_go_clock) = &mut self.ledge_let_go_clock {
+            ledge_let_go_clock.tick(delta_seconds);
+        }
+        if self.ledge_state == LedgeState::None {
+            let can_grab_ledge = !self.is_grounded()
+                && !self.is_climbing()
+                && self.wallrunning_state == WallRunning::None
+                && self.crouch_state.current_state() == &CrouchState::Upright
+                // Ledges are caught on the way down so they don't cut jumps short
+                && self.body_linear_velocity().y <= 0.0
+                && self.ledge_let_go_clock.as_ref().is_none_or(|ledge_let_go_clock| {
+                    ledge_let_go_clock.elapsed()
+                        > Duration::from_secs_f32(config.min_ledge_regrab_cooldown_duration)
+                });
+            if can_grab_ledge {
+                if let Some(grip) = self.find_ledge(
+                    config,
+                    &mut physics.rigid_body_set,
+                    &mut physics.query_pipeline,
+                    &mut physics.collider_set,
+                ) {
+                    self.grab_ledge(config, grip, &mut physics.rigid_body_set);
+                }
+            }
+        }
+        if self.ledge_state != LedgeState::None {
+            self.hang_or_mantle(config, input, &mut physics.rigid_body_set);
+            // The body is held by the ledge, so it can't move, jump or crouch on its own
+            self.update_camera(config, input, physics, delta_seconds);
+            return;
+        }
+        if self.is_climbing() {
+            self.climb_ladder(config, input, jump_pressed, physics);
+            // The body is held by the ladder, so it can only move along it
+            self.update_camera(config, input, physics, delta_seconds);
+            return;
+        }
+
         self.jump_cooldown_timer.tick(delta_seconds);
+        self.air_dash_cooldown_timer.tick(delta_seconds);
         let max_jump_cooldown_timer_duration = match self.crouch_state.current_state() {
             CrouchState::Upright => config.min_jump_standing_cooldown_duration,
             CrouchState::Crouched => config.min_jump_crouched_cooldown_duration,
@@ -284,6 +492,26 @@ impl CharacterController {
             self.coyote_timer.tick(delta_seconds);
         }
 
+        if self.is_swimming() {
+            let jump_has_cooled_down = self.jump_cooldown_timer.elapsed()
+                > Duration::from_secs_f32(max_jump_cooldown_timer_duration);
+            // Jumping at the surface leaps out of the water, anywhere else it swims up
+            if input.jump()
+                && jump_has_cooled_down
+                && matches!(
+                    self.swimming_state.current_state(),
+                    SwimmingState::Surface(_)
+                )
+            {
+                self.jump(config, &mut physics.rigid_body_set);
+            } else {
+                self.swim(config, input, &mut physics.rigid_body_set, delta_seconds);
+            }
+            // The water holds the body up, so it can't crouch, dash or move on the ground
+            self.update_camera(config, input, physics, delta_seconds);
+            return;
+        }
+
         if self.is_grounded()
             && !input.jump()
             && self.sliding_state.current_state() == &SlidingState::None
@@ -309,19 +537,32 @@ impl CharacterController {
         if input.jump() {
             let jump_has_cooled_down = self.jump_cooldown_timer.elapsed()
                 > Duration::from_secs_f32(max_jump_cooldown_timer_duration);
-            let is_grounded_or_wallrunning =
-                self.wallrunning_state != WallRunning::None || self.is_grounded();
+            let is_on_wall = self.wallrunning_state != WallRunning::None
+                || self.wall_climb_state != WallClimbing::None;
+            let is_grounded_or_wallrunning = is_on_wall || self.is_grounded();
             let can_coyote_jump = self.coyote_timer.elapsed()
                 < Duration::from_secs_f32(config.max_jump_coyote_duration);
 
             if jump_has_cooled_down
                 && (is_grounded_or_wallrunning || can_coyote_jump)
+                && (!is_on_wall || self.can_wall_kick(config))
                 && self.sliding_state.current_state() != &SlidingState::Normal
             {
                 self.jump(config, &mut physics.rigid_body_set);
+            } else if jump_pressed && self.can_air_jump(config) {
+                self.air_jump(config, &mut physics.rigid_body_set);
             }
         }
 
+        if dash_pressed && self.can_air_dash(config) {
+            self.air_dash(
+                config,
+                input.move_right(),
+                input.move_forward(),
+                &mut physics.rigid_body_set,
+            );
+        }
+
         match (input.crouch(), self.crouch_state.current_state()) {
             (true, &CrouchState::Upright) => {
                 self.change_crouch_state(
@@ -359,6 +600,16 @@ impl CharacterController {
             _ => {}
         }
 
+        self.update_camera(config, input, physics, delta_seconds);
+    }
+
+    fn update_camera(
+        &mut self,
+        config: &CharacterControllerConfig,
+        input: &Input,
+        physics: &mut PhysicsWorld,
+        delta_seconds: f32,
+    ) {
         self.aim(config, input.aim(), delta_seconds);
 
         if self.perspective_mode.is_third_person() {
@@ -376,6 +627,14 @@ impl CharacterController {
         }
     }
 
+    /// Catch the cached body isometry and the camera up with a body that was just moved.
+    /// Anywhere new is fair game for grabbing ledges, even ones just let go of.
+    pub fn snap_to_body(&mut self, rigid_body_set: &mut RigidBodySet) {
+        self.update_body_isometry(rigid_body_set);
+        self.boom.translation = self.body_isometry().translation;
+        self.ledge_let_go_clock = None;
+    }
+
     fn update_body_isometry(&mut self, rigid_body_set: &mut RigidBodySet) {
         let mut body_isometry: Isometry3<f32> = Isometry::identity();
         let body_handle = self.body_handle();
@@ -626,6 +885,29 @@ impl CharacterController {
         &self.body_isometry
     }
 
+    /// Whether the character controller is hanging from or mantling onto a ledge.
+    pub fn is_on_ledge(&self) -> bool {
+        self.ledge_state != LedgeState::None
+    }
+
+    pub fn is_swimming(&self) -> bool {
+        self.swimming_state != SwimmingState::None
+    }
+
+    /// Let the character controller swim in a level's water.
+    pub fn set_water_volumes(&mut self, water_volumes: WaterVolumes) {
+        self.water_volumes = water_volumes;
+    }
+
+    pub fn is_climbing(&self) -> bool {
+        self.climbing_state != ClimbingState::None
+    }
+
+    /// Let the character controller climb a level's ladders.
+    pub fn set_climbable_surfaces(&mut self, climbable_surfaces: ClimbableSurfaces) {
+        self.climbable_surfaces = climbable_surfaces;
+    }
+
     pub fn is_grounded(&self) -> bool {
         self.ground_normal().is_some()
     }
@@ -798,6 +1080,129 @@ impl CharacterController {
         self.jump_body(config, jump_acceleration, rigid_body_set);
         self.event_channel.send(CharacterControllerEvent::Jump);
         self.jump_cooldown_timer.reset();
+        if let Some(wall_normal) = self.wall_normal() {
+            self.last_kicked_wall = match self.last_kicked_wall {
+                Some((last_wall_normal, kicks))
+                    if is_same_wall(&last_wall_normal, &wall_normal) =>
+                {
+                    Some((wall_normal, kicks + 1))
+                }
+                _ => Some((wall_normal, 1)),
+            };
+        }
+        if self.wall_climb_state != WallClimbing::None {
+            // Kicking off of a wall ends the climb
+            self.wall_climb_state.transition_to(WallClimbing::None);
+            self.wall_climb_spent = true;
+            self.event_channel
+                .send(CharacterControllerEvent::StoppedWallClimbing);
+        }
+        if self.is_climbing() {
+            // Kicking off of a ladder lets go of it
+            self.let_go_of_ladder(rigid_body_set);
+        }
+    }
+
+    /// The world space normal of the wall the character controller is running on or climbing, if any.
+    fn wall_normal(&self) -> Option<Vector3<f32>> {
+        match (
+            self.wallrunning_state.current_state(),
+            self.wall_climb_state.current_state(),
+        ) {
+            (WallRunning::OnLeft(_) | WallRunning::OnRight(_), _) => {
+                Some(self.wallrunning_wall_normal)
+            }
+            (WallRunning::None, WallClimbing::Climbing(wall_normal)) => Some(*wall_normal),
+            (WallRunning::None, WallClimbing::None) => None,
+        }
+    }
+
+    /// Whether the character controller hasn't jumped off of the wall it's on too many times in a row.
+    fn can_wall_kick(&self, config: &CharacterControllerConfig) -> bool {
+        match (self.wall_normal(), self.last_kicked_wall) {
+            (Some(wall_normal), Some((last_wall_normal, kicks))) => {
+                !is_same_wall(&wall_normal, &last_wall_normal)
+                    || kicks < config.max_consecutive_wall_kicks
+            }
+            _ => true,
+        }
+    }
+
+    fn can_air_jump(&self, config: &CharacterControllerConfig) -> bool {
+        !self.is_grounded()
+            && self.wallrunning_state == WallRunning::None
+            && self.wall_climb_state == WallClimbing::None
+            && self.air_jumps_used < config.max_air_jumps
+            && self.jump_cooldown_timer.elapsed()
+                > Duration::from_secs_f32(config.min_air_jump_cooldown_duration)
+    }
+
+    fn air_jump(&mut self, config: &CharacterControllerConfig, rigid_body_set: &mut RigidBodySet) {
+        self.jump_body(config, config.air_jump_acceleration, rigid_body_set);
+        self.air_jumps_used += 1;
+        self.event_channel
+            .send(CharacterControllerEvent::AirJumped {
+                air_jumps_left: config.max_air_jumps - self.air_jumps_used,
+            });
+        self.jump_cooldown_timer.reset();
+    }
+
+    fn can_air_dash(&self, config: &CharacterControllerConfig) -> bool {
+        !self.is_grounded()
+            && self.wallrunning_state == WallRunning::None
+            && self.wall_climb_state == WallClimbing::None
+            && self.air_dashes_used < config.max_air_dashes
+            && self.air_dash_cooldown_timer.elapsed()
+                > Duration::from_secs_f32(config.min_air_dash_cooldown_duration)
+    }
+
+    /// Push the character controller's rigid body horizontally in the direction it's being moved,
+    /// or straight ahead if it isn't being moved. Falling is canceled so the dash stays level.
+    fn air_dash(
+        &mut self,
+        config: &CharacterControllerConfig,
+        left_right_magnitude: f32,
+        forward_back_magnitude: f32,
+        rigid_body_set: &mut RigidBodySet,
+    ) {
+        let movement_vector = Vector3::new(left_right_magnitude, 0.0, forward_back_magnitude);
+        let untransformed_dash_direction = if movement_vector.magnitude() > 0.0 {
+            movement_vector.normalize()
+        } else {
+            FORWARD_VECTOR
+        };
+        let dash_direction = self
+            .pivot_isometry()
+            .transform_vector(&untransformed_dash_direction);
+        let current_velocity = self.body_linear_velocity();
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.reset_forces(true);
+            body.set_linvel(
+                Vector3::new(current_velocity.x, 0.0, current_velocity.z),
+                true,
+            );
+            body.apply_impulse(dash_direction * config.air_dash_impulse * body.mass(), true);
+        }
+        self.air_dashes_used += 1;
+        self.event_channel
+            .send(CharacterControllerEvent::AirDashed {
+                direction: dash_direction.into(),
+                air_dashes_left: config.max_air_dashes - self.air_dashes_used,
+            });
+        self.air_dash_cooldown_timer.reset();
+    }
+
+    /// Let the character controller jump and dash in the air again.
+    fn restore_air_moves(&mut self) {
+        self.air_jumps_used = 0;
+        self.air_dashes_used = 0;
+    }
+
+    /// Let the character controller run on, climb and kick off of any wall again.
+    fn restore_wall_moves(&mut self) {
+        self.spent_wall_normal = None;
+        self.last_kicked_wall = None;
+        self.wall_climb_spent = false;
     }
 
     /// Make the character controller's rigid body jump. If the character controller is wallrunning, it will jump on the
@@ -822,7 +1227,23 @@ impl CharacterController {
                 untransformed_wall_normal * config.jump_wallrunning_normal_scale
                     + untransformed_jump_direction_vector * config.jump_wallrunning_scale
             }
-            WallRunning::None => UP_VECTOR,
+            WallRunning::None => match self.wall_climb_state.current_state() {
+                // Kick back off of the wall being climbed
+                WallClimbing::Climbing(wall_normal) => {
+                    body_isometry.inverse_transform_vector(wall_normal)
+                        * config.jump_wallrunning_normal_scale
+                        + UP_VECTOR * config.jump_wallrunning_scale
+                }
+                WallClimbing::None => match self.climbing_state.current_state() {
+                    // Kick back off of the ladder
+                    ClimbingState::Climbing(ladder_normal) => {
+                        body_isometry.inverse_transform_vector(ladder_normal)
+                            * config.jump_wallrunning_normal_scale
+                            + UP_VECTOR * config.jump_wallrunning_scale
+                    }
+                    ClimbingState::None => UP_VECTOR,
+                },
+            },
         } * jump_acceleration;
         let current_velocity = self.body_linear_velocity();
         if let Some(body) = rigid_body_set.get_mut(body_handle) {
@@ -889,7 +1310,20 @@ impl CharacterController {
         query_pipeline: &mut QueryPipeline,
         collider_set: &mut ColliderSet,
     ) {
-        if self.crouch_state.current_state() == &CrouchState::Crouched {
+        if self.crouch_state.current_state() == &CrouchState::Crouched
+            || self.is_on_ledge()
+            || self.is_swimming()
+            || self.is_climbing()
+        {
+            self.wallrunning_state.transition_to(WallRunning::None);
+            return;
+        }
+        if self.wallrunning_state != WallRunning::None
+            && self.wallrunning_timer.elapsed()
+                >= Duration::from_secs_f32(config.max_wallrunning_duration)
+        {
+            // The character controller falls off and can't run on this wall until it lands
+            self.spent_wall_normal = Some(self.wallrunning_wall_normal);
             self.wallrunning_state.transition_to(WallRunning::None);
             return;
         }
@@ -921,11 +1355,15 @@ impl CharacterController {
                 Self::query_filter().exclude_collider(self.collider_handle()),
             ) {
                 let ray_normal = ray_intersection.normal;
-                let transformed_wall_normal = (-right_wall_ray.dir + ray_normal).normalize();
-                let wall_normal = body_isometry.inverse_transform_vector(&transformed_wall_normal);
-                self.wallrunning_state
-                    .transition_to(WallRunning::OnRight(wall_normal));
-                return;
+                if !self.is_spent_wall(&ray_normal) {
+                    let transformed_wall_normal = (-right_wall_ray.dir + ray_normal).normalize();
+                    let wall_normal =
+                        body_isometry.inverse_transform_vector(&transformed_wall_normal);
+                    self.wallrunning_wall_normal = ray_normal;
+                    self.wallrunning_state
+                        .transition_to(WallRunning::OnRight(wall_normal));
+                    return;
+                }
             }
 
             let left_wall_ray =
@@ -939,16 +1377,164 @@ impl CharacterController {
                 Self::query_filter().exclude_collider(self.collider_handle()),
             ) {
                 let ray_normal = ray_intersection.normal;
-                let transformed_wall_normal = (-left_wall_ray.dir + ray_normal).normalize();
-                let wall_normal = body_isometry.inverse_transform_vector(&transformed_wall_normal);
-                self.wallrunning_state
-                    .transition_to(WallRunning::OnLeft(wall_normal));
-                return;
+                if !self.is_spent_wall(&ray_normal) {
+                    let transformed_wall_normal = (-left_wall_ray.dir + ray_normal).normalize();
+                    let wall_normal =
+                        body_isometry.inverse_transform_vector(&transformed_wall_normal);
+                    self.wallrunning_wall_normal = ray_normal;
+                    self.wallrunning_state
+                        .transition_to(WallRunning::OnLeft(wall_normal));
+                    return;
+                }
             }
         }
         self.wallrunning_state.transition_to(WallRunning::None);
     }
 
+    fn is_spent_wall(&self, wall_normal: &Vector3<f32>) -> bool {
+        self.spent_wall_normal
+            .map(|spent_wall_normal| is_same_wall(&spent_wall_normal, wall_normal))
+            .unwrap_or(false)
+    }
+
+    /// Determine whether the character controller is climbing a wall by firing a ray straight ahead. It
+    /// climbs when it's in the air, moving forward and facing the wall head-on.
+    fn determine_wall_climb_state(
+        &mut self,
+        config: &CharacterControllerConfig,
+        forward_back_magnitude: f32,
+        rigid_body_set: &mut RigidBodySet,
+        player_radius: f32,
+        query_pipeline: &mut QueryPipeline,
+        collider_set: &mut ColliderSet,
+    ) {
+        let is_climbing = self.wall_climb_state != WallClimbing::None;
+        let can_climb = !self.is_grounded()
+            && !self.is_on_ledge()
+            && !self.is_swimming()
+            && !self.is_climbing()
+            && self.wallrunning_state == WallRunning::None
+            && self.crouch_state.current_state() == &CrouchState::Upright
+            // Forward is negative
+            && forward_back_magnitude < 0.0
+            && if is_climbing {
+                self.wall_climb_timer.elapsed()
+                    < Duration::from_secs_f32(config.max_wall_climb_duration)
+            } else {
+                !self.wall_climb_spent
+            };
+        if !can_climb {
+            self.wall_climb_state.transition_to(WallClimbing::None);
+            return;
+        }
+
+        let forward_wall_ray =
+            Ray::new(point![0.0, 0.0, 0.0], FORWARD_VECTOR).transform_by(self.body_isometry());
+        if let Some((_handle, ray_intersection)) = query_pipeline.cast_ray_and_get_normal(
+            rigid_body_set,
+            collider_set,
+            &forward_wall_ray,
+            (player_radius - COLLIDER_RAYCAST_OFFSET) + config.wallrunning_ray_length,
+            false,
+            Self::query_filter().exclude_collider(self.collider_handle()),
+        ) {
+            let facing_angle = (-forward_wall_ray.dir)
+                .angle(&ray_intersection.normal)
+                .to_degrees();
+            if facing_angle <= config.max_wall_climb_facing_angle {
+                self.wall_climb_state
+                    .transition_to(WallClimbing::Climbing(ray_intersection.normal));
+                return;
+            }
+        }
+        self.wall_climb_state.transition_to(WallClimbing::None);
+    }
+
+    fn determine_climbing_state(
+        &mut self,
+        config: &CharacterControllerConfig,
+        forward_back_magnitude: f32,
+        rigid_body_set: &mut RigidBodySet,
+        player_radius: f32,
+        query_pipeline: &mut QueryPipeline,
+        collider_set: &mut ColliderSet,
+    ) {
+        let is_climbing = self.is_climbing();
+        let can_climb = !self.is_on_ledge()
+            && self.crouch_state.current_state() == &CrouchState::Upright
+            && if is_climbing {
+                // Climbing down onto the ground steps off of the ladder
+                !(self.is_grounded() && forward_back_magnitude >= 0.0)
+            } else {
+                // Forward is negative
+                forward_back_magnitude < 0.0
+                    && self.ladder_timer.elapsed()
+                        > Duration::from_secs_f32(config.min_ladder_regrab_cooldown_duration)
+            };
+
+        let mut ladder_normal = None;
+        if can_climb {
+            let forward_ray =
+                Ray::new(point![0.0, 0.0, 0.0], FORWARD_VECTOR).transform_by(self.body_isometry());
+            if let Some((collider_handle, ray_intersection)) = query_pipeline
+                .cast_ray_and_get_normal(
+                    rigid_body_set,
+                    collider_set,
+                    &forward_ray,
+                    (player_radius - COLLIDER_RAYCAST_OFFSET) + config.ladder_grab_reach,
+                    false,
+                    Self::query_filter().exclude_collider(self.collider_handle()),
+                )
+            {
+                let facing_angle = (-forward_ray.dir)
+                    .angle(&ray_intersection.normal)
+                    .to_degrees();
+                if self.climbable_surfaces.contains(&collider_handle)
+                    && (is_climbing || facing_angle <= config.max_ladder_facing_angle)
+                {
+                    ladder_normal = Some(ray_intersection.normal);
+                }
+            }
+        }
+
+        match (is_climbing, ladder_normal) {
+            (false, Some(ladder_normal)) => self.grab_ladder(ladder_normal, rigid_body_set),
+            (true, Some(ladder_normal)) => self
+                .climbing_state
+                .transition_to(ClimbingState::Climbing(ladder_normal)),
+            (true, None) => self.let_go_of_ladder(rigid_body_set),
+            (false, None) => {}
+        }
+    }
+
+    fn determine_swimming_state(
+        &mut self,
+        config: &CharacterControllerConfig,
+        collider_set: &ColliderSet,
+    ) {
+        let body_translation = self.body_isometry().translation.vector;
+        // The character controller swims once its middle is in water
+        match self
+            .water_volumes
+            .surface_height_at(&body_translation.into(), collider_set)
+        {
+            Some(surface_height) => {
+                let head_height = (self.body_isometry() * self.head_isometry())
+                    .translation
+                    .vector
+                    .y;
+                if head_height >= surface_height - config.max_swim_surface_depth {
+                    self.swimming_state
+                        .transition_to(SwimmingState::Surface(surface_height));
+                } else {
+                    self.swimming_state
+                        .transition_to(SwimmingState::Underwater(surface_height));
+                }
+            }
+            None => self.swimming_state.transition_to(SwimmingState::None),
+        }
+    }
+
     fn determine_sliding_state(&mut self, config: &CharacterControllerConfig) {
         let body_isometry = self.body_isometry();
         if let Some(ground_normal) = self.ground_normal() {
@@ -1147,6 +1733,7 @@ impl CharacterController {
         config: &CharacterControllerConfig,
         rigid_body_set: &mut RigidBodySet,
     ) {
+        self.wallrunning_timer.reset();
         let current_velocity = self.body_linear_velocity();
         if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
             body.reset_forces(true);
@@ -1160,6 +1747,166 @@ impl CharacterController {
         }
     }
 
+    /// Pull the character controller down the wall harder the longer it wallruns.
+    fn ramp_wallrunning_gravity(
+        &mut self,
+        config: &CharacterControllerConfig,
+        rigid_body_set: &mut RigidBodySet,
+    ) {
+        let wallrunning_progress = (self.wallrunning_timer.elapsed().as_secs_f32()
+            / config.max_wallrunning_duration)
+            .min(1.0);
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.set_gravity_scale(
+                config.start_wallrunning_gravity_scale
+                    + (config.end_wallrunning_gravity_scale
+                        - config.start_wallrunning_gravity_scale)
+                        * wallrunning_progress,
+                true,
+            );
+        }
+    }
+
+    fn climb_wall(
+        &mut self,
+        config: &CharacterControllerConfig,
+        rigid_body_set: &mut RigidBodySet,
+    ) {
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.set_linvel(Vector3::new(0.0, config.wall_climb_speed, 0.0), true);
+        }
+    }
+
+    fn grab_ladder(&mut self, ladder_normal: Vector3<f32>, rigid_body_set: &mut RigidBodySet) {
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.reset_forces(true);
+            body.set_gravity_scale(0.0, true);
+            body.set_linvel(Vector3::zeros(), true);
+        }
+        self.climbing_state
+            .transition_to(ClimbingState::Climbing(ladder_normal));
+        self.restore_air_moves();
+        self.restore_wall_moves();
+        self.event_channel
+            .send(CharacterControllerEvent::StartedClimbing);
+    }
+
+    /// Move the body up or down the ladder it's on. Jumping kicks off of the ladder, crouching
+    /// lets go of it, and climbing off the top mantles onto whatever the ladder leads up to.
+    fn climb_ladder(
+        &mut self,
+        config: &CharacterControllerConfig,
+        input: &Input,
+        jump_pressed: bool,
+        physics: &mut PhysicsWorld,
+    ) {
+        if jump_pressed {
+            self.jump(config, &mut physics.rigid_body_set);
+            return;
+        }
+        if input.crouch() {
+            self.let_go_of_ladder(&mut physics.rigid_body_set);
+            return;
+        }
+
+        // Forward is negative
+        let climb_velocity =
+            UP_VECTOR * -input.move_forward().clamp(-1.0, 1.0) * config.ladder_climb_speed;
+        if climb_velocity.y > 0.0 {
+            if let Some(grip) = self.find_ledge(
+                config,
+                &mut physics.rigid_body_set,
+                &mut physics.query_pipeline,
+                &mut physics.collider_set,
+            ) {
+                self.let_go_of_ladder(&mut physics.rigid_body_set);
+                self.grab_ledge(config, grip, &mut physics.rigid_body_set);
+                self.start_mantling(grip);
+                return;
+            }
+        }
+        if let Some(body) = physics.rigid_body_set.get_mut(self.body_handle()) {
+            // Ending a wallrun restores gravity, so make sure it stays off
+            body.set_gravity_scale(0.0, true);
+            body.set_linvel(climb_velocity, true);
+        }
+    }
+
+    /// Let go of the ladder the character controller is climbing, if any.
+    pub fn let_go_of_ladder(&mut self, rigid_body_set: &mut RigidBodySet) {
+        if !self.is_climbing() {
+            return;
+        }
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.set_gravity_scale(1.0, true);
+        }
+        self.climbing_state.transition_to(ClimbingState::None);
+        self.ladder_timer.reset();
+        self.event_channel
+            .send(CharacterControllerEvent::StoppedClimbing);
+    }
+
+    /// Float the body in the water it's in, slow it down with drag, and swim it toward
+    /// the movement input. Holding jump swims up and holding crouch dives.
+    fn swim(
+        &mut self,
+        config: &CharacterControllerConfig,
+        input: &Input,
+        rigid_body_set: &mut RigidBodySet,
+        delta_seconds: f32,
+    ) {
+        let surface_height = match self.swimming_state.current_state() {
+            SwimmingState::Surface(surface_height) | SwimmingState::Underwater(surface_height) => {
+                *surface_height
+            }
+            SwimmingState::None => return,
+        };
+        let (capsule_half_height, capsule_radius) = self.capsule_values(config);
+        let body_half_height = capsule_half_height + capsule_radius;
+        let body_bottom_height = self.body_isometry().translation.vector.y - body_half_height;
+        // How much of the body is under the surface, from 0 to 1
+        let submersion =
+            ((surface_height - body_bottom_height) / (2.0 * body_half_height)).clamp(0.0, 1.0);
+
+        let movement_vector = Vector3::new(input.move_right(), 0.0, input.move_forward());
+        let movement_vector = if movement_vector.magnitude() > 1.0 {
+            movement_vector.normalize()
+        } else {
+            movement_vector
+        };
+        let target_velocity = self
+            .pivot_isometry()
+            .transform_vector(&(movement_vector * config.max_swim_speed));
+        let vertical_target_speed = if input.jump() {
+            Some(config.swim_vertical_speed)
+        } else if input.crouch() {
+            Some(-config.swim_vertical_speed)
+        } else {
+            None
+        };
+
+        let current_velocity = *self.body_linear_velocity();
+        let body_handle = self.body_handle();
+        if let Some(body) = rigid_body_set.get_mut(body_handle) {
+            let buoyancy = UP_VECTOR * config.swim_buoyancy_acceleration * submersion;
+            let drag = -current_velocity * config.swim_drag;
+            let mut frame_goal_velocity = current_velocity + (buoyancy + drag) * delta_seconds;
+            let planar_goal_velocity = move_towards(
+                &Vector3::new(frame_goal_velocity.x, 0.0, frame_goal_velocity.z),
+                &Vector3::new(target_velocity.x, 0.0, target_velocity.z),
+                config.swim_acceleration * delta_seconds,
+            );
+            frame_goal_velocity.x = planar_goal_velocity.x;
+            frame_goal_velocity.z = planar_goal_velocity.z;
+            if let Some(vertical_target_speed) = vertical_target_speed {
+                let max_vertical_change = config.swim_acceleration * delta_seconds;
+                frame_goal_velocity.y += (vertical_target_speed - frame_goal_velocity.y)
+                    .clamp(-max_vertical_change, max_vertical_change);
+            }
+            body.apply_impulse((frame_goal_velocity - current_velocity) * body.mass(), true);
+        }
+    }
+
     fn stop_wallrunning(&mut self, rigid_body_set: &mut RigidBodySet) {
         if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
             body.set_gravity_scale(1.0, true);
@@ -1213,4 +1960,577 @@ impl CharacterController {
             }
         }
     }
+
+    /// Look for a ledge in front of the character controller that it could pull itself onto. A small sphere
+    /// is cast forward from the shoulders to find a wall, then down onto the wall from above to find its top.
+    fn find_ledge(
+        &self,
+        config: &CharacterControllerConfig,
+        rigid_body_set: &mut RigidBodySet,
+        query_pipeline: &mut QueryPipeline,
+        collider_set: &mut ColliderSet,
+    ) -> Option<LedgeGrip> {
+        let (cap_halfheight, cap_radius) = self.capsule_values(config);
+        let body_isometry = self.body_isometry();
+        let probe = Ball::new(LEDGE_PROBE_RADIUS);
+        let shoulder_isometry: Isometry3<f32> =
+            Isometry::from(body_isometry.translation.vector + UP_VECTOR * cap_halfheight);
+
+        let (_, wall_hit) = query_pipeline.cast_shape(
+            rigid_body_set,
+            collider_set,
+            &shoulder_isometry,
+            &body_isometry.transform_vector(&FORWARD_VECTOR),
+            &probe,
+            cap_radius + config.ledge_grab_forward_reach,
+            true,
+            Self::query_filter().exclude_collider(self.collider_handle()),
+        )?;
+        let wall_normal = Vector3::new(wall_hit.normal1.x, 0.0, wall_hit.normal1.z);
+        // Anything tilted more than 60 degrees from upright is a floor or ceiling, not a wall
+        if wall_normal.magnitude() < 0.5 {
+            return None;
+        }
+        let wall_normal = wall_normal.normalize();
+
+        // Start just past the face of the wall, as high as can be reached
+        let above_ledge_isometry: Isometry3<f32> = Isometry::from(
+            wall_hit.witness1.coords - wall_normal * LEDGE_PROBE_RADIUS * 2.0
+                + UP_VECTOR * config.ledge_grab_upward_reach,
+        );
+        let (_, top_hit) = query_pipeline.cast_shape(
+            rigid_body_set,
+            collider_set,
+            &above_ledge_isometry,
+            &DOWN_VECTOR,
+            &probe,
+            config.ledge_grab_upward_reach,
+            true,
+            Self::query_filter().exclude_collider(self.collider_handle()),
+        )?;
+        // If the probe starts inside the wall, the wall is too tall to reach the top of
+        if top_hit.toi <= 0.0
+            || top_hit.normal1.angle(&UP_VECTOR).to_degrees() > config.max_ledge_slope_angle
+        {
+            return None;
+        }
+        let grip = LedgeGrip {
+            // The probe came down past the face of the wall, so step back out onto the edge
+            ledge_point: top_hit.witness1.coords + wall_normal * LEDGE_PROBE_RADIUS * 2.0,
+            wall_normal,
+        };
+
+        // There has to be room to stand on top of the ledge
+        let standing_isometry: Isometry3<f32> =
+            Isometry::from(self.mantle_translation(config, &grip));
+        if query_pipeline
+            .intersection_with_shape(
+                rigid_body_set,
+                collider_set,
+                &standing_isometry,
+                &Capsule::new_y(
+                    config.capsule_standing_half_height(),
+                    config.capsule_standing_radius,
+                ),
+                Self::query_filter().exclude_collider(self.collider_handle()),
+            )
+            .is_some()
+        {
+            return None;
+        }
+        Some(grip)
+    }
+
+    /// Where the body hangs from a ledge, with the ledge at its shoulders and the capsule just off the wall.
+    fn hang_translation(
+        &self,
+        config: &CharacterControllerConfig,
+        grip: &LedgeGrip,
+    ) -> Vector3<f32> {
+        let (cap_halfheight, cap_radius) = self.capsule_values(config);
+        grip.ledge_point - UP_VECTOR * cap_halfheight
+            + grip.wall_normal * (cap_radius + COLLIDER_RAYCAST_OFFSET)
+    }
+
+    /// Where the body stands once it has mantled onto a ledge.
+    fn mantle_translation(
+        &self,
+        config: &CharacterControllerConfig,
+        grip: &LedgeGrip,
+    ) -> Vector3<f32> {
+        grip.ledge_point
+            + UP_VECTOR * (config.capsule_standing_total_height / 2.0 + COLLIDER_RAYCAST_OFFSET)
+            - grip.wall_normal * config.capsule_standing_radius
+    }
+
+    fn grab_ledge(
+        &mut self,
+        config: &CharacterControllerConfig,
+        grip: LedgeGrip,
+        rigid_body_set: &mut RigidBodySet,
+    ) {
+        let hang_translation = self.hang_translation(config, &grip);
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.reset_forces(true);
+            body.set_gravity_scale(0.0, true);
+            body.set_linvel(Vector3::zeros(), true);
+            // Face the wall
+            body.set_position(
+                Isometry::from_parts(
+                    hang_translation.into(),
+                    UnitQuaternion::face_towards(&grip.wall_normal, &Vector3::y_axis()),
+                ),
+                true,
+            );
+        }
+        self.ledge_state.transition_to(LedgeState::Hanging(grip));
+        self.ledge_timer.reset();
+        self.restore_air_moves();
+        self.restore_wall_moves();
+        self.event_channel
+            .send(CharacterControllerEvent::GrabbedLedge {
+                ledge_point: grip.ledge_point.into(),
+                wall_normal: grip.wall_normal.into(),
+            });
+    }
+
+    /// Hold onto the ledge until jumping mantles onto it or crouching lets go of it. Mantling
+    /// raises the body above the ledge, then moves it over the ledge.
+    fn hang_or_mantle(
+        &mut self,
+        config: &CharacterControllerConfig,
+        input: &Input,
+        rigid_body_set: &mut RigidBodySet,
+    ) {
+        match *self.ledge_state.current_state() {
+            LedgeState::Hanging(grip) => {
+                if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+                    body.set_linvel(Vector3::zeros(), true);
+                }
+                if input.jump() {
+                    self.start_mantling(grip);
+                } else if input.crouch() {
+                    self.let_go_of_ledge(rigid_body_set);
+                }
+            }
+            LedgeState::Mantling(grip) => {
+                let mantle_progress = (self.ledge_timer.elapsed().as_secs_f32()
+                    / config.ledge_mantle_duration)
+                    .min(1.0);
+                let rise_progress = (mantle_progress * 2.0).min(1.0);
+                let over_progress = (mantle_progress * 2.0 - 1.0).max(0.0);
+                let hang_translation = self.hang_translation(config, &grip);
+                let mantle_translation = self.mantle_translation(config, &grip);
+                let mantle_offset = mantle_translation - hang_translation;
+                let next_translation = hang_translation
+                    + Vector3::new(
+                        mantle_offset.x * over_progress,
+                        mantle_offset.y * rise_progress,
+                        mantle_offset.z * over_progress,
+                    );
+                if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+                    body.set_linvel(Vector3::zeros(), true);
+                    body.set_position(
+                        Isometry::from_parts(next_translation.into(), body.position().rotation),
+                        true,
+                    );
+                    if mantle_progress >= 1.0 {
+                        body.set_gravity_scale(1.0, true);
+                    }
+                }
+                if mantle_progress >= 1.0 {
+                    self.ledge_state.transition_to(LedgeState::None);
+                    self.event_channel
+                        .send(CharacterControllerEvent::FinishedMantling);
+                }
+            }
+            LedgeState::None => {}
+        }
+    }
+
+    fn start_mantling(&mut self, grip: LedgeGrip) {
+        self.ledge_state.transition_to(LedgeState::Mantling(grip));
+        self.ledge_timer.reset();
+        self.event_channel
+            .send(CharacterControllerEvent::StartedMantling);
+    }
+
+    /// Drop from the ledge the character controller is hanging from or mantling onto, if any.
+    pub fn let_go_of_ledge(&mut self, rigid_body_set: &mut RigidBodySet) {
+        if !self.is_on_ledge() {
+            return;
+        }
+        if let Some(body) = rigid_body_set.get_mut(self.body_handle()) {
+            body.set_gravity_scale(1.0, true);
+        }
+        self.ledge_state.transition_to(LedgeState::None);
+        self.ledge_let_go_clock = Some(PassiveClock::default());
+        self.event_channel
+            .send(CharacterControllerEvent::LetGoOfLedge);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+    /// A character controller alone in a headless physics world, so its moves can be
+    /// played out without any level's glTF.
+    struct HeadlessCharacter {
+        config: CharacterControllerConfig,
+        controller: CharacterController,
+        physics: PhysicsWorld,
+        input: Input,
+        /// Every event the character controller has sent so far
+        events: Vec<CharacterControllerEvent>,
+    }
+
+    impl HeadlessCharacter {
+        /// Put a character controller facing forward at the provided spot in a world that's already been built.
+        fn new(
+            config: CharacterControllerConfig,
+            mut physics: PhysicsWorld,
+            translation: Vector3<f32>,
+        ) -> Self {
+            let mut controller = CharacterController::from_config(&config);
+            controller.add_to_physics_world(
+                &config,
+                &mut physics.rigid_body_set,
+                &mut physics.collider_set,
+                Some(Isometry::translation(
+                    translation.x,
+                    translation.y,
+                    translation.z,
+                )),
+            );
+            // Let the first tick see the world before physics has stepped
+            physics
+                .query_pipeline
+                .update(&physics.rigid_body_set, &physics.collider_set);
+            Self {
+                config,
+                controller,
+                physics,
+                input: Input::default(),
+                events: Vec::new(),
+            }
+        }
+
+        fn tick(&mut self) {
+            self.controller.update(
+                &self.config,
+                &GameSettings::default(),
+                &mut self.physics,
+                &self.input,
+                TICK_SECONDS,
+            );
+            self.physics.step(TICK_SECONDS);
+            // The event channel is bounded, so it has to be drained like a level would
+            while let Ok(event) = self.controller.get_event() {
+                self.events.push(event);
+            }
+        }
+
+        /// Tick until the condition holds, giving up after a few seconds. Returns whether it held.
+        fn tick_until(&mut self, condition: impl Fn(&Self) -> bool) -> bool {
+            for _ in 0..(5.0 / TICK_SECONDS) as u32 {
+                self.tick();
+                if condition(self) {
+                    return true;
+                }
+            }
+            false
+        }
+
+        fn tick_for(&mut self, seconds: f32) {
+            for _ in 0..(seconds / TICK_SECONDS).round() as u32 {
+                self.tick();
+            }
+        }
+
+        /// Press jump for a tick, then let go of it.
+        fn tap_jump(&mut self) {
+            self.input.set_jump(true);
+            self.tick();
+            self.input.set_jump(false);
+            self.tick();
+        }
+
+        fn set_linear_velocity(&mut self, linear_velocity: Vector3<f32>) {
+            if let Some(body) = self
+                .physics
+                .rigid_body_set
+                .get_mut(self.controller.body_handle())
+            {
+                body.set_linvel(linear_velocity, true);
+            }
+        }
+
+        fn translation(&self) -> Vector3<f32> {
+            self.controller.body_isometry().translation.vector
+        }
+
+        fn has_sent(&self, is_event: impl Fn(&CharacterControllerEvent) -> bool) -> bool {
+            self.events.iter().any(is_event)
+        }
+    }
+
+    /// A physics world with an endless floor at the provided height.
+    fn world_with_floor(floor_height: f32) -> PhysicsWorld {
+        let mut physics = PhysicsWorld::from_config(&PhysicsConfig::default());
+        physics.collider_set.insert(
+            ColliderBuilder::cuboid(500.0, 0.5, 500.0)
+                .translation(Vector3::new(0.0, floor_height - 0.5, 0.0))
+                .build(),
+        );
+        physics
+    }
+
+    #[test]
+    fn grabs_a_ledge_and_mantles_onto_it() {
+        let mut physics = world_with_floor(0.0);
+        // A 2 meter tall block just in front of the character controller
+        physics.collider_set.insert(
+            ColliderBuilder::cuboid(2.0, 1.0, 1.0)
+                .translation(Vector3::new(0.0, 1.0, -1.5))
+                .build(),
+        );
+        let mut character = HeadlessCharacter::new(
+            CharacterControllerConfig::default(),
+            physics,
+            Vector3::new(0.0, 2.5, 0.0),
+        );
+
+        // Falling past the top of the block catches it
+        assert!(character.tick_until(|character| character.controller.is_on_ledge()));
+        assert!(character
+            .has_sent(|event| matches!(event, CharacterControllerEvent::GrabbedLedge { .. })));
+        // Hanging holds the body still
+        character.tick();
+        let hang_translation = character.translation();
+        // The shoulders are at the top of the block, with the body just off of its face
+        assert!((hang_translation.y - (2.0 - 1.83 / 2.0 + 0.4)).abs() < 0.01);
+        assert!((hang_translation.z - (-0.5 + 0.4)).abs() < 0.01);
+        character.tick_for(0.5);
+        assert!((character.translation() - hang_translation).magnitude() < 0.01);
+        assert!(!character.controller.is_grounded());
+
+        character.input.set_jump(true);
+        assert!(character.tick_until(|character| !character.controller.is_on_ledge()));
+        character.input.set_jump(false);
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::StartedMantling))
+        );
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::FinishedMantling))
+        );
+
+        // The body ends up standing on top of the block, not hanging off of it
+        character.tick_for(0.5);
+        assert!(character.controller.is_grounded());
+        assert!(character.translation().z < -0.5);
+        assert!((character.translation().y - (2.0 + 1.83 / 2.0)).abs() < 0.1);
+    }
+
+    fn air_jumps_sent(character: &HeadlessCharacter) -> usize {
+        character
+            .events
+            .iter()
+            .filter(|event| matches!(event, CharacterControllerEvent::AirJumped { .. }))
+            .count()
+    }
+
+    #[test]
+    fn gets_air_jumps_back_on_landing() {
+        let mut character = HeadlessCharacter::new(
+            CharacterControllerConfig {
+                max_air_jumps: 2,
+                ..Default::default()
+            },
+            world_with_floor(0.0),
+            Vector3::new(0.0, 1.83 / 2.0, 0.0),
+        );
+        character.tick_for(0.5);
+        assert!(character.controller.is_grounded());
+
+        character.tap_jump();
+        character.tick_for(0.3);
+        assert!(!character.controller.is_grounded());
+        // Only fresh presses jump in the air, and only as many times as allowed
+        for _ in 0..3 {
+            character.tap_jump();
+            character.tick_for(0.25);
+        }
+        assert_eq!(character.controller.air_jumps_used, 2);
+        assert_eq!(air_jumps_sent(&character), 2);
+
+        assert!(character.tick_until(|character| character.controller.is_grounded()));
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::Landed { .. }))
+        );
+        assert_eq!(character.controller.air_jumps_used, 0);
+
+        character.tick_for(0.5);
+        character.tap_jump();
+        character.tick_for(0.3);
+        character.tap_jump();
+        assert_eq!(character.controller.air_jumps_used, 1);
+        assert!(matches!(
+            character.events.last(),
+            Some(CharacterControllerEvent::AirJumped { air_jumps_left: 1 })
+        ));
+    }
+
+    #[test]
+    fn falls_off_a_wall_when_the_wallrun_runs_out() {
+        let mut physics = PhysicsWorld::from_config(&PhysicsConfig::default());
+        // A long, tall wall just to the right, with nothing underneath to land on
+        physics.collider_set.insert(
+            ColliderBuilder::cuboid(0.5, 100.0, 200.0)
+                .translation(Vector3::new(1.1, 0.0, 0.0))
+                .build(),
+        );
+        let config = CharacterControllerConfig::default();
+        let max_wallrunning_duration = config.max_wallrunning_duration;
+        let mut character = HeadlessCharacter::new(config, physics, Vector3::new(0.0, 50.0, 0.0));
+        character.set_linear_velocity(Vector3::new(0.0, 0.0, -8.0));
+
+        character.tick();
+        assert!(matches!(
+            character.controller.wallrunning_state.current_state(),
+            WallRunning::OnRight(_)
+        ));
+        assert!(character.has_sent(|event| matches!(
+            event,
+            CharacterControllerEvent::StartedWallRunning {
+                side: WallSide::Right,
+                ..
+            }
+        )));
+
+        character.tick_for(max_wallrunning_duration - 0.2);
+        assert!(character.controller.wallrunning_state != WallRunning::None);
+        character.tick_for(0.3);
+        assert!(character.controller.wallrunning_state == WallRunning::None);
+        assert!(character
+            .has_sent(|event| matches!(event, CharacterControllerEvent::StoppedWallRunning)));
+
+        // The same wall can't be run on again until landing
+        character.tick_for(0.5);
+        assert!(character.controller.wallrunning_state == WallRunning::None);
+        assert!(character.controller.spent_wall_normal.is_some());
+    }
+
+    #[test]
+    fn swims_in_water_and_surfaces_from_under_it() {
+        let mut physics = world_with_floor(0.0);
+        // A 3 meter deep pool covering the floor
+        let pool_handle = physics.collider_set.insert(
+            ColliderBuilder::cuboid(50.0, 1.5, 50.0)
+                .translation(Vector3::new(0.0, 1.5, 0.0))
+                .sensor(true)
+                .build(),
+        );
+        physics.named_sensors.insert("Pool.water", pool_handle);
+        let water_volumes = WaterVolumes::from_gltf(
+            &Gltf::from_slice(br#"{"asset":{"version":"2.0"},"nodes":[{"name":"Pool.water"}]}"#)
+                .unwrap(),
+            &physics,
+        );
+        let mut character = HeadlessCharacter::new(
+            CharacterControllerConfig::default(),
+            physics,
+            Vector3::new(0.0, 6.0, 0.0),
+        );
+        character.controller.set_water_volumes(water_volumes);
+
+        assert!(character.tick_until(|character| character.controller.is_swimming()));
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::StartedSwimming))
+        );
+        // The water holds the body up at its surface
+        character.tick_for(3.0);
+        assert!(matches!(
+            character.controller.swimming_state.current_state(),
+            SwimmingState::Surface(_)
+        ));
+        assert!(!character.controller.is_grounded());
+
+        character.input.set_crouch(true);
+        assert!(character.tick_until(|character| matches!(
+            character.controller.swimming_state.current_state(),
+            SwimmingState::Underwater(_)
+        )));
+        character.input.set_crouch(false);
+        assert!(character.has_sent(|event| matches!(event, CharacterControllerEvent::Dived)));
+
+        character.input.set_jump(true);
+        assert!(character.tick_until(|character| matches!(
+            character.controller.swimming_state.current_state(),
+            SwimmingState::Surface(_)
+        )));
+        assert!(character.has_sent(|event| matches!(event, CharacterControllerEvent::Surfaced)));
+        assert!(character.controller.is_swimming());
+    }
+
+    #[test]
+    fn climbs_off_the_top_of_a_ladder() {
+        let mut physics = world_with_floor(0.0);
+        // A 3 meter tall tower just in front of the character controller, with a ladder up its face
+        let tower_handle = physics
+            .rigid_body_set
+            .insert(RigidBodyBuilder::fixed().build());
+        physics.collider_set.insert_with_parent(
+            ColliderBuilder::cuboid(2.0, 1.5, 1.0)
+                .translation(Vector3::new(0.0, 1.5, -1.5))
+                .build(),
+            tower_handle,
+            &mut physics.rigid_body_set,
+        );
+        physics
+            .named_rigid_bodies
+            .insert("Tower Ladder.ladder", tower_handle);
+        let climbable_surfaces = ClimbableSurfaces::from_gltf(
+            &Gltf::from_slice(
+                br#"{"asset":{"version":"2.0"},"nodes":[{"name":"Tower Ladder.ladder"}]}"#,
+            )
+            .unwrap(),
+            &physics,
+        );
+        let mut character = HeadlessCharacter::new(
+            CharacterControllerConfig::default(),
+            physics,
+            Vector3::new(0.0, 1.83 / 2.0, 0.0),
+        );
+        character
+            .controller
+            .set_climbable_surfaces(climbable_surfaces);
+
+        // Forward is negative
+        character.input.set_move_forward(-1.0);
+        assert!(character.tick_until(|character| character.controller.is_climbing()));
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::StartedClimbing))
+        );
+
+        // Climbing past the top lets go of the ladder and mantles onto the tower
+        assert!(character.tick_until(|character| !character.controller.is_climbing()));
+        assert!(character.controller.is_on_ledge());
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::StoppedClimbing))
+        );
+        assert!(
+            character.has_sent(|event| matches!(event, CharacterControllerEvent::StartedMantling))
+        );
+        assert!(character.tick_until(|character| !character.controller.is_on_ledge()));
+        character.input.set_move_forward(0.0);
+
+        character.tick_for(0.5);
+        assert!(character.controller.is_grounded());
+        assert!(!character.controller.is_climbing());
+        assert!(character.translation().z < -0.5);
+        assert!((character.translation().y - (3.0 + 1.83 / 2.0)).abs() < 0.1);
+    }
 }
@@ -2,6 +2,15 @@ use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
 
 pub const COLLIDER_RAYCAST_OFFSET: f32 = 0.001;
+/// The radius of the spheres cast to look for ledges.
+pub const LEDGE_PROBE_RADIUS: f32 = 0.05;
+/// How far apart (in degrees) two walls' normals can be for them to be treated as the same wall.
+pub const SAME_WALL_MAX_NORMAL_ANGLE: f32 = 10.0;
+
+/// Whether two wall normals belong to the same wall, or at least walls facing the same way.
+pub fn is_same_wall(wall_normal: &Vector3<f32>, other_wall_normal: &Vector3<f32>) -> bool {
+    wall_normal.angle(other_wall_normal).to_degrees() <= SAME_WALL_MAX_NORMAL_ANGLE
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SlidingState {
@@ -30,6 +39,97 @@ impl PartialEq for WallRunning {
 
 impl Eq for WallRunning {}
 
+/// Whether the character controller is climbing straight up a wall it's facing. Holds the
+/// wall's normal in world space.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum WallClimbing {
+    Climbing(Vector3<f32>),
+    None,
+}
+
+impl PartialEq for WallClimbing {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (WallClimbing::Climbing(_), WallClimbing::Climbing(_))
+                | (WallClimbing::None, WallClimbing::None)
+        )
+    }
+}
+
+impl Eq for WallClimbing {}
+
+/// Whether the character controller is on a ladder or other climbable surface. Holds the
+/// surface's normal in world space.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum ClimbingState {
+    Climbing(Vector3<f32>),
+    None,
+}
+
+impl PartialEq for ClimbingState {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (ClimbingState::Climbing(_), ClimbingState::Climbing(_))
+                | (ClimbingState::None, ClimbingState::None)
+        )
+    }
+}
+
+impl Eq for ClimbingState {}
+
+/// Where the character controller is holding onto a ledge, in world space.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LedgeGrip {
+    /// The point on top of the ledge that's being held.
+    pub ledge_point: Vector3<f32>,
+    /// The horizontal normal of the wall beneath the ledge.
+    pub wall_normal: Vector3<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum LedgeState {
+    Hanging(LedgeGrip),
+    Mantling(LedgeGrip),
+    None,
+}
+
+impl PartialEq for LedgeState {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (LedgeState::Hanging(_), LedgeState::Hanging(_))
+                | (LedgeState::Mantling(_), LedgeState::Mantling(_))
+                | (LedgeState::None, LedgeState::None)
+        )
+    }
+}
+
+impl Eq for LedgeState {}
+
+/// Whether the character controller is swimming, and where. Holds the height of the
+/// water's surface.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum SwimmingState {
+    Surface(f32),
+    Underwater(f32),
+    None,
+}
+
+impl PartialEq for SwimmingState {
+    fn eq(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (SwimmingState::Surface(_), SwimmingState::Surface(_))
+                | (SwimmingState::Underwater(_), SwimmingState::Underwater(_))
+                | (SwimmingState::None, SwimmingState::None)
+        )
+    }
+}
+
+impl Eq for SwimmingState {}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CrouchState {
     Upright,
@@ -0,0 +1,273 @@
+use crate::config::GrappleConfig;
+use crate::shared::checksum::StateChecksum;
+use crate::shared::descriptor::tagged_colliders;
+use crate::shared::events::CharacterControllerEvent;
+use crate::shared::input::Input;
+use crate::shared::vectors::FORWARD_VECTOR;
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
+
+/// The tag that marks an object in a level's glTF as something that can be grappled onto.
+pub static GRAPPLE_TAG: &str = "grapple";
+
+/// Swings a body from a rope shot at whatever the camera is looking at, as long as it's
+/// tagged `grapple`. The rope is a rope joint, so the body swings and keeps its momentum
+/// when it lets go.
+#[derive(Serialize, Deserialize, Default)]
+pub struct GrappleController {
+    target_collider_handles: Vec<ColliderHandle>,
+    rope_joint_handle: Option<ImpulseJointHandle>,
+    rope_length: f32,
+    /// Whether grapple was held last tick, so the grapple is only shot on a fresh press
+    grapple_was_held: bool,
+    #[serde(skip)]
+    event_channel: EventChannel<CharacterControllerEvent>,
+}
+
+impl GrappleController {
+    pub fn get_event(&self) -> Result<CharacterControllerEvent, TryRecvError> {
+        self.event_channel.get_message()
+    }
+
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        checksum.write_u8(u8::from(self.is_grappling()));
+    }
+
+    pub fn is_grappling(&self) -> bool {
+        self.rope_joint_handle.is_some()
+    }
+
+    /// Find the objects tagged `grapple` in a level's glTF, e.g. `Crane Hook.grapple`.
+    pub fn find_targets(&mut self, gltf: &Gltf, physics: &PhysicsWorld) {
+        self.target_collider_handles = tagged_colliders(gltf, physics, &[GRAPPLE_TAG]);
+    }
+
+    /// Shoot the grapple when it's pressed, let go when it's released,
+    /// and reel the rope in or out while it's held.
+    pub fn update(
+        &mut self,
+        config: &GrappleConfig,
+        input: &Input,
+        camera_isometry: &Isometry3<f32>,
+        body_handle: RigidBodyHandle,
+        physics: &mut PhysicsWorld,
+        delta_seconds: f32,
+    ) {
+        let grapple_pressed = input.grapple() && !self.grapple_was_held;
+        self.grapple_was_held = input.grapple();
+
+        if !input.grapple() {
+            self.release(physics);
+            return;
+        }
+        if grapple_pressed && !self.is_grappling() {
+            self.shoot(config, camera_isometry, body_handle, physics);
+        }
+
+        if let Some(rope_joint_handle) = self.rope_joint_handle {
+            self.rope_length = (self.rope_length
+                - input.reel() * config.reel_speed * delta_seconds)
+                .clamp(config.min_rope_length, config.max_grapple_distance);
+            if let Some(rope_joint) = physics.impulse_joint_set.get_mut(rope_joint_handle) {
+                let rope_limits = rope_axis_limits(self.rope_length);
+                rope_joint.data.set_limits(JointAxis::X, rope_limits);
+                rope_joint.data.set_limits(JointAxis::Y, rope_limits);
+                rope_joint.data.set_limits(JointAxis::Z, rope_limits);
+            }
+        }
+    }
+
+    /// Attach a rope between the body and whatever grapple target the camera is looking at, if any.
+    fn shoot(
+        &mut self,
+        config: &GrappleConfig,
+        camera_isometry: &Isometry3<f32>,
+        body_handle: RigidBodyHandle,
+        physics: &mut PhysicsWorld,
+    ) {
+        let aim_ray = Ray::new(
+            camera_isometry.translation.vector.into(),
+            camera_isometry.transform_vector(&FORWARD_VECTOR),
+        );
+        let (hit_collider_handle, toi) = match physics.query_pipeline.cast_ray(
+            &physics.rigid_body_set,
+            &physics.collider_set,
+            &aim_ray,
+            config.max_grapple_distance,
+            true,
+            QueryFilter::new()
+                .exclude_sensors()
+                .exclude_rigid_body(body_handle),
+        ) {
+            Some(hit) => hit,
+            None => return,
+        };
+        if !self.target_collider_handles.contains(&hit_collider_handle) {
+            return;
+        }
+        let target_body_handle = match physics
+            .collider_set
+            .get(hit_collider_handle)
+            .and_then(|target_collider| target_collider.parent())
+        {
+            Some(target_body_handle) => target_body_handle,
+            None => return,
+        };
+        let (target_body, body) = match (
+            physics.rigid_body_set.get(target_body_handle),
+            physics.rigid_body_set.get(body_handle),
+        ) {
+            (Some(target_body), Some(body)) => (target_body, body),
+            _ => return,
+        };
+
+        let anchor_point = aim_ray.point_at(toi);
+        self.rope_length = (body.translation() - anchor_point.coords)
+            .magnitude()
+            .clamp(config.min_rope_length, config.max_grapple_distance);
+        let rope_joint = RopeJointBuilder::new()
+            .local_anchor1(
+                target_body
+                    .position()
+                    .inverse_transform_point(&anchor_point),
+            )
+            .local_anchor2(Point::origin())
+            .limits(rope_axis_limits(self.rope_length))
+            .build();
+        self.rope_joint_handle = Some(physics.impulse_joint_set.insert(
+            target_body_handle,
+            body_handle,
+            rope_joint,
+            true,
+        ));
+        self.event_channel.send(CharacterControllerEvent::Grappled {
+            anchor_point: anchor_point.coords.into(),
+        });
+    }
+
+    /// Let go of the rope, if there is one. The body keeps whatever momentum it had.
+    pub fn release(&mut self, physics: &mut PhysicsWorld) {
+        if let Some(rope_joint_handle) = self.rope_joint_handle.take() {
+            physics.impulse_joint_set.remove(rope_joint_handle, true);
+            self.event_channel
+                .send(CharacterControllerEvent::ReleasedGrapple);
+        }
+    }
+}
+
+/// The limits to give each of a rope joint's axes so its anchors stay within the provided length.
+/// A rope joint is only as long as the length of the vector of its axes' limits, so each gets an even share.
+fn rope_axis_limits(rope_length: f32) -> [f32; 2] {
+    [0.0, rope_length / 3.0_f32.sqrt()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TICK_SECONDS: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn swings_from_a_target_and_keeps_going_when_let_go() {
+        let mut physics = PhysicsWorld::from_config(&PhysicsConfig::default());
+        let hook_handle = physics.rigid_body_set.insert(
+            RigidBodyBuilder::fixed()
+                .translation(Vector3::new(0.0, 10.0, 0.0))
+                .build(),
+        );
+        physics.collider_set.insert_with_parent(
+            ColliderBuilder::ball(0.5).build(),
+            hook_handle,
+            &mut physics.rigid_body_set,
+        );
+        physics
+            .named_rigid_bodies
+            .insert("Crane Hook.grapple", hook_handle);
+        let body_handle = physics.rigid_body_set.insert(
+            RigidBodyBuilder::dynamic()
+                .translation(Vector3::new(6.0, 0.0, 0.0))
+                .build(),
+        );
+        physics.collider_set.insert_with_parent(
+            ColliderBuilder::ball(0.4).build(),
+            body_handle,
+            &mut physics.rigid_body_set,
+        );
+        physics
+            .query_pipeline
+            .update(&physics.rigid_body_set, &physics.collider_set);
+
+        let config = GrappleConfig::default();
+        let mut grapple = GrappleController::default();
+        grapple.find_targets(
+            &Gltf::from_slice(
+                br#"{"asset":{"version":"2.0"},"nodes":[{"name":"Crane Hook.grapple"}]}"#,
+            )
+            .unwrap(),
+            &physics,
+        );
+        // Look straight at the hook
+        let camera_isometry = Isometry::from_parts(
+            Translation::new(6.0, 0.0, 0.0),
+            UnitQuaternion::face_towards(&Vector3::new(6.0, -10.0, 0.0), &Vector3::y_axis()),
+        );
+        let mut input = Input::default();
+        input.set_grapple(true);
+        grapple.update(
+            &config,
+            &input,
+            &camera_isometry,
+            body_handle,
+            &mut physics,
+            TICK_SECONDS,
+        );
+        assert!(grapple.is_grappling());
+        assert!(matches!(
+            grapple.get_event(),
+            Ok(CharacterControllerEvent::Grappled { .. })
+        ));
+
+        // The rope holds the body up as it swings under the hook
+        let rope_length = grapple.rope_length;
+        let anchor_point =
+            Vector3::new(0.0, 10.0, 0.0) + Vector3::new(6.0, -10.0, 0.0).normalize() * 0.5;
+        for _ in 0..60 {
+            physics.step(TICK_SECONDS);
+            grapple.update(
+                &config,
+                &input,
+                &camera_isometry,
+                body_handle,
+                &mut physics,
+                TICK_SECONDS,
+            );
+            let body_translation = physics.rigid_body_set[body_handle].translation();
+            assert!((body_translation - anchor_point).magnitude() < rope_length + 0.05);
+        }
+        let swing_velocity = *physics.rigid_body_set[body_handle].linvel();
+        assert!(swing_velocity.x < -1.0);
+
+        input.set_grapple(false);
+        grapple.update(
+            &config,
+            &input,
+            &camera_isometry,
+            body_handle,
+            &mut physics,
+            TICK_SECONDS,
+        );
+        assert!(!grapple.is_grappling());
+        assert!(matches!(
+            grapple.get_event(),
+            Ok(CharacterControllerEvent::ReleasedGrapple)
+        ));
+        // Letting go keeps the swing's momentum, with only gravity acting on it
+        physics.step(TICK_SECONDS);
+        let released_velocity = *physics.rigid_body_set[body_handle].linvel();
+        assert!(
+            (released_velocity - (swing_velocity + physics.gravity * TICK_SECONDS)).magnitude()
+                < 0.01
+        );
+    }
+}
@@ -1,7 +1,10 @@
 pub mod character;
+pub mod grapple;
 pub mod plane;
+pub mod racing_driver;
 pub mod raycast_vehicle;
 
 pub use character::CharacterController;
+pub use grapple::GrappleController;
 pub use plane::PlaneController;
 pub use raycast_vehicle::RaycastVehicleController;
@@ -0,0 +1,94 @@
+use crate::config::{racing_driver::DriverDifficulty, RacingDriverConfig};
+use crate::shared::input::Input;
+use crate::shared::spline::Spline;
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How far apart (in meters) the driver checks the racing line for corners to brake for.
+static CORNER_SCAN_STEP: f32 = 2.0;
+
+/// Drives a vehicle around a racing line by synthesizing
+/// the same steer, throttle, and brake input a player would give.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RacingDriver {
+    /// How far along the racing line the driver is
+    progress_distance: f32,
+}
+
+impl RacingDriver {
+    /// Start from the point on the racing line closest to the provided position.
+    pub fn start_from(&mut self, racing_line: &Spline, position: &Vector3<f32>) {
+        self.progress_distance = racing_line.closest_distance(position);
+    }
+
+    /// Decide how to steer, throttle, and brake in order to follow the racing line.
+    pub fn drive(
+        &mut self,
+        config: &RacingDriverConfig,
+        difficulty: DriverDifficulty,
+        racing_line: &Spline,
+        cabin_isometry: &Isometry3<f32>,
+        forward_speed: f32,
+        input: &mut Input,
+    ) {
+        let position = cabin_isometry.translation.vector;
+        // Only look for progress near where the driver last was so that
+        // parts of the track running alongside each other aren't confused
+        self.progress_distance = racing_line.closest_distance_near(
+            &position,
+            self.progress_distance,
+            config.braking_lookahead_distance,
+        );
+
+        // Steer toward a point further down the line the faster we go
+        let lookahead_distance =
+            config.min_lookahead_distance + forward_speed.max(0.0) * config.lookahead_time;
+        let lookahead_point =
+            racing_line.point_at_distance(self.progress_distance + lookahead_distance);
+        let cabin_relative_lookahead = cabin_isometry
+            .rotation
+            .inverse_transform_vector(&(lookahead_point - position));
+        // Forward is -Z, so a positive heading error means the point is to the right
+        let heading_error = cabin_relative_lookahead
+            .x
+            .atan2(-cabin_relative_lookahead.z);
+        input.set_steer((heading_error * config.steering_gain).clamp(-1.0, 1.0));
+
+        let speed_error = self.target_speed(config, difficulty, racing_line) - forward_speed;
+        if speed_error > 0.0 {
+            input.set_throttle((speed_error * config.throttle_gain).clamp(0.0, 1.0));
+            input.set_brake(0.0);
+        } else {
+            input.set_throttle(0.0);
+            input.set_brake((-speed_error * config.brake_gain).clamp(0.0, 1.0));
+        }
+    }
+
+    /// The fastest the car can go right now and still be able to slow
+    /// down in time for every corner within the braking lookahead distance.
+    fn target_speed(
+        &self,
+        config: &RacingDriverConfig,
+        difficulty: DriverDifficulty,
+        racing_line: &Spline,
+    ) -> f32 {
+        let pace_scale = difficulty.pace_scale();
+        let max_lateral_acceleration = config.max_lateral_acceleration * pace_scale;
+        let mut target_speed = config.max_speed * pace_scale;
+
+        let mut distance_to_corner = 0.0;
+        while distance_to_corner <= config.braking_lookahead_distance {
+            let curvature =
+                racing_line.curvature_at_distance(self.progress_distance + distance_to_corner);
+            if curvature > f32::EPSILON {
+                let corner_speed = (max_lateral_acceleration / curvature).sqrt();
+                let approach_speed = (corner_speed * corner_speed
+                    + 2.0 * config.braking_deceleration * distance_to_corner)
+                    .sqrt();
+                target_speed = target_speed.min(approach_speed);
+            }
+            distance_to_corner += CORNER_SCAN_STEP;
+        }
+        target_speed
+    }
+}
@@ -1,8 +1,12 @@
-use crate::config::raycast_vehicle::RaycastVehicleConfig;
+use crate::config::raycast_vehicle::{RaycastVehicleConfig, WheelConfig};
+use crate::shared::checksum::StateChecksum;
 use crate::shared::input::Input;
+use crate::shared::vectors::{DOWN_VECTOR, FORWARD_VECTOR, UP_VECTOR};
+use crate::shared::water::WaterVolumes;
 use perigee::rapier3d::control::DynamicRayCastVehicleController;
 use perigee::{prelude::*, rapier3d::control::WheelTuning};
 use serde::{Deserialize, Serialize};
+use std::hash::Hasher;
 
 fn default_rapier_vehicle() -> DynamicRayCastVehicleController {
     DynamicRayCastVehicleController::new(RigidBodyHandle::default())
@@ -11,7 +15,20 @@ fn default_rapier_vehicle() -> DynamicRayCastVehicleController {
 #[derive(Serialize, Deserialize)]
 pub struct RaycastVehicleController {
     cabin_body_handle: RigidBodyHandle,
+    cabin_collider_handle: ColliderHandle,
     cabin_isometry: Isometry3<f32>,
+    /// The current angle (in degrees) of the steered wheels before
+    /// any Ackermann correction is applied.
+    steer_angle: f32,
+    /// How wrecked the vehicle is, from 0 (pristine) to 1 (fully wrecked).
+    damage: f32,
+    /// How far off center (in degrees) impacts have knocked the steered wheels.
+    steering_misalignment: f32,
+    /// Whether the cabin has been in water. A drowned engine has no power until it's repaired.
+    engine_drowned: bool,
+    water_volumes: WaterVolumes,
+    /// Where each wheel's center is relative to the cabin, so the wheels move along with it
+    wheel_centers: Vec<Point<f32>>,
     #[serde(skip, default = "default_rapier_vehicle")]
     rapier_vehicle: DynamicRayCastVehicleController,
 }
@@ -23,13 +40,27 @@ impl FromConfig for RaycastVehicleController {
         let rapier_vehicle = DynamicRayCastVehicleController::new(cabin_body_handle);
         Self {
             cabin_body_handle,
+            cabin_collider_handle: ColliderHandle::default(),
             rapier_vehicle,
             cabin_isometry: Isometry::default(),
+            steer_angle: 0.0,
+            damage: 0.0,
+            steering_misalignment: 0.0,
+            engine_drowned: false,
+            water_volumes: WaterVolumes::default(),
+            wheel_centers: Vec::new(),
         }
     }
 }
 
 impl RaycastVehicleController {
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        checksum.write_f32(self.steer_angle);
+        checksum.write_f32(self.damage);
+        checksum.write_f32(self.steering_misalignment);
+        checksum.write_u8(u8::from(self.engine_drowned));
+    }
+
     pub fn add_to_physics_world(
         &mut self,
         config: &RaycastVehicleConfig,
@@ -57,14 +88,16 @@ impl RaycastVehicleController {
             config.cabin_half_length,
         )
         // Listen for *all* collision and intersection events with
-        // this collider
-        .active_events(ActiveEvents::COLLISION_EVENTS)
+        // this collider, along with contact forces strong enough to be impacts
+        .active_events(ActiveEvents::COLLISION_EVENTS | ActiveEvents::CONTACT_FORCE_EVENTS)
+        .contact_force_event_threshold(config.impact_force_threshold)
         // Set the mass (in kg, I think) of the collider
         .density(config.mass)
         .build();
 
         let cabin_body_handle = rigid_body_set.insert(rigid_body);
-        collider_set.insert_with_parent(cabin_collider, cabin_body_handle, rigid_body_set);
+        self.cabin_collider_handle =
+            collider_set.insert_with_parent(cabin_collider, cabin_body_handle, rigid_body_set);
         self.cabin_body_handle = cabin_body_handle;
 
         self.rapier_vehicle = DynamicRayCastVehicleController::new(self.cabin_body_handle);
@@ -81,25 +114,210 @@ impl RaycastVehicleController {
                 &wheel_tuning,
             );
         }
+        self.cabin_isometry = initial_isometry;
+        self.wheel_centers = self.resting_wheel_centers();
+    }
+
+    /// Put the cabin at the provided isometry and bring it to a stop, with its
+    /// wheels straightened out and resting on their suspension.
+    pub fn respawn(&mut self, rigid_body_set: &mut RigidBodySet, isometry: Isometry3<f32>) {
+        if let Some(cabin_body) = rigid_body_set.get_mut(self.cabin_body_handle) {
+            cabin_body.set_position(isometry, true);
+            cabin_body.set_linvel(Vector3::zeros(), true);
+            cabin_body.set_angvel(Vector3::zeros(), true);
+        }
+        self.cabin_isometry = isometry;
+        self.steer_angle = 0.0;
+        for wheel in self.rapier_vehicle.wheels_mut() {
+            wheel.steering = 0.0;
+        }
+        self.wheel_centers = self.resting_wheel_centers();
+        self.repair();
+    }
+
+    fn resting_wheel_centers(&self) -> Vec<Point<f32>> {
+        self.rapier_vehicle
+            .wheels()
+            .iter()
+            .map(|wheel| {
+                wheel.chassis_connection_point_cs
+                    + wheel.direction_cs * wheel.suspension_rest_length
+            })
+            .collect()
     }
 
     pub fn cabin_body_handle(&self) -> RigidBodyHandle {
         self.cabin_body_handle
     }
 
+    pub fn cabin_collider_handle(&self) -> ColliderHandle {
+        self.cabin_collider_handle
+    }
+
     pub fn cabin_isometry(&self) -> &Isometry3<f32> {
         &self.cabin_isometry
     }
 
+    pub fn wheel_count(&self) -> usize {
+        self.rapier_vehicle.wheels().len()
+    }
+
     pub fn wheel_isometry(&self, wheel_idx: usize) -> Isometry3<f32> {
         let wheel = self.rapier_vehicle.wheels()[wheel_idx];
+        let wheel_center = self
+            .wheel_centers
+            .get(wheel_idx)
+            .map(|wheel_center| self.cabin_isometry * wheel_center)
+            .unwrap_or_else(|| wheel.center());
         Isometry::from_parts(
-            wheel.center().into(),
+            wheel_center.into(),
             self.cabin_isometry().rotation
                 * UnitQuaternion::from_euler_angles(0.0, wheel.steering, 0.0),
         )
     }
 
+    /// How wrecked the vehicle is, from 0 (pristine) to 1 (fully wrecked).
+    pub fn damage(&self) -> f32 {
+        self.damage
+    }
+
+    pub fn is_engine_drowned(&self) -> bool {
+        self.engine_drowned
+    }
+
+    /// Let the vehicle's engine drown in a level's water.
+    pub fn set_water_volumes(&mut self, water_volumes: WaterVolumes) {
+        self.water_volumes = water_volumes;
+    }
+
+    /// Wear the vehicle down based on the force of an impact and the direction (relative to
+    /// the cabin) pointing toward whatever was hit. Returns how much damage was taken.
+    pub fn apply_impact(
+        &mut self,
+        config: &RaycastVehicleConfig,
+        impact_force: f32,
+        cabin_relative_impact_direction: &Vector3<f32>,
+    ) -> f32 {
+        let damage_config = match config.damage {
+            Some(damage_config) => damage_config,
+            None => return 0.0,
+        };
+        if impact_force <= damage_config.min_damaging_force {
+            return 0.0;
+        }
+
+        let previous_damage = self.damage;
+        self.damage = (self.damage
+            + (impact_force - damage_config.min_damaging_force) * damage_config.damage_per_force)
+            .min(1.0);
+        let damage_taken = self.damage - previous_damage;
+        // Hits from the right knock the wheels to the left and vice versa
+        let misalignment_direction = if cabin_relative_impact_direction.x > 0.0 {
+            1.0
+        } else {
+            -1.0
+        };
+        self.steering_misalignment = (self.steering_misalignment
+            + misalignment_direction * damage_taken * damage_config.max_steering_misalignment)
+            .clamp(
+                -damage_config.max_steering_misalignment,
+                damage_config.max_steering_misalignment,
+            );
+        damage_taken
+    }
+
+    /// Undo all damage taken by the vehicle, including a drowned engine.
+    pub fn repair(&mut self) {
+        self.damage = 0.0;
+        self.steering_misalignment = 0.0;
+        self.engine_drowned = false;
+    }
+
+    /// The speed of the cabin along its forward axis. Negative when reversing.
+    pub fn forward_speed(&self, rigid_body_set: &RigidBodySet) -> f32 {
+        if let Some(cabin_body) = rigid_body_set.get(self.cabin_body_handle) {
+            cabin_body.linvel().dot(
+                &cabin_body
+                    .position()
+                    .rotation
+                    .transform_vector(&FORWARD_VECTOR),
+            )
+        } else {
+            0.0
+        }
+    }
+
+    /// Move the steering angle toward the one requested by the steering input. The
+    /// max angle shrinks as the vehicle speeds up and the wheels drift back to
+    /// center when there's no steering input.
+    fn update_steer_angle(
+        &mut self,
+        config: &RaycastVehicleConfig,
+        steer_input: f32,
+        forward_speed: f32,
+        delta_seconds: f32,
+    ) {
+        let speed_t = if config.steering_reduction_speed > 0.0 {
+            (forward_speed.abs() / config.steering_reduction_speed).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let angle_scale = lerp(1.0, config.min_steering_angle_scale, speed_t);
+        let target_angle = lerp(
+            config.wheel_left_turn_angle,
+            config.wheel_right_turn_angle,
+            remap(steer_input.clamp(-1.0, 1.0), -1.0, 1.0, 0.0, 1.0),
+        ) * angle_scale;
+
+        let steering_rate = if steer_input == 0.0 {
+            config.steering_return_rate
+        } else {
+            config.steering_rate
+        };
+        let max_angle_delta = steering_rate * delta_seconds;
+        let angle_difference = target_angle - self.steer_angle;
+        self.steer_angle = if steering_rate <= 0.0 || angle_difference.abs() <= max_angle_delta {
+            target_angle
+        } else {
+            self.steer_angle + angle_difference.signum() * max_angle_delta
+        };
+    }
+
+    /// The steering angle (in degrees) of a single steered wheel, corrected so that
+    /// the inner wheel turns tighter than the outer one when `ackermann_factor` is above 0.
+    fn wheel_steer_angle(&self, config: &RaycastVehicleConfig, wheel_index: usize) -> f32 {
+        let unsteered_wheels: Vec<&WheelConfig> = config
+            .wheels
+            .iter()
+            .filter(|wheel_config| !wheel_config.steers_on_input)
+            .collect();
+        if config.ackermann_factor <= 0.0 || unsteered_wheels.is_empty() || self.steer_angle == 0.0
+        {
+            return self.steer_angle;
+        }
+
+        let rear_axle_z = unsteered_wheels
+            .iter()
+            .map(|wheel_config| wheel_config.center_cabin_relative_position[2])
+            .sum::<f32>()
+            / unsteered_wheels.len() as f32;
+        let [wheel_x, _, wheel_z] = config.wheels[wheel_index].center_cabin_relative_position;
+        let wheelbase = (rear_axle_z - wheel_z).abs();
+        if wheelbase == 0.0 {
+            return self.steer_angle;
+        }
+        let tan_angle = self.steer_angle.to_radians().tan();
+        // Turning left puts the turning center to the left (-X), so wheels on the left are
+        // closer to it and need a sharper angle. Once the turning center is at or past a
+        // wheel (sharp turns or wide tracks), that wheel turns all the way to 90 degrees
+        // rather than flipping to the other side
+        let ackermann_angle = (wheelbase * tan_angle)
+            .atan2((wheelbase + wheel_x * tan_angle).max(0.0))
+            .to_degrees();
+
+        lerp(self.steer_angle, ackermann_angle, config.ackermann_factor)
+    }
+
     pub fn update(
         &mut self,
         config: &RaycastVehicleConfig,
@@ -107,20 +325,39 @@ impl RaycastVehicleController {
         physics: &mut PhysicsWorld,
         delta_seconds: f32,
     ) {
-        let steer_angle = lerp(
-            config.wheel_left_turn_angle,
-            config.wheel_right_turn_angle,
-            remap(input.steer(), -1.0, 1.0, 0.0, 1.0),
-        );
+        let forward_speed = self.forward_speed(&physics.rigid_body_set);
+        self.update_steer_angle(config, input.steer(), forward_speed, delta_seconds);
+        let wheel_steer_angles: Vec<f32> = (0..config.wheels.len())
+            .map(|wheel_index| {
+                self.wheel_steer_angle(config, wheel_index) + self.steering_misalignment
+            })
+            .collect();
+        if !self.engine_drowned {
+            self.engine_drowned = self
+                .water_volumes
+                .surface_height_at(
+                    &self.cabin_isometry.translation.vector.into(),
+                    &physics.collider_set,
+                )
+                .is_some();
+        }
+        let engine_power_scale = if self.engine_drowned {
+            0.0
+        } else {
+            match config.damage {
+                Some(damage_config) => 1.0 - self.damage * damage_config.max_engine_power_loss,
+                None => 1.0,
+            }
+        };
         for (wheel_index, wheel) in self.rapier_vehicle.wheels_mut().iter_mut().enumerate() {
             let wheel_config = config.wheels[wheel_index];
             wheel.engine_force = 0.0;
             // if wheel_config.receives_power {
-            wheel.engine_force += config.throttle_force * input.throttle();
+            wheel.engine_force += config.throttle_force * engine_power_scale * input.throttle();
             // }
             wheel.engine_force -= config.brake_force * input.brake();
             if wheel_config.steers_on_input {
-                wheel.steering = steer_angle.to_radians();
+                wheel.steering = wheel_steer_angles[wheel_index].to_radians();
             }
         }
 
@@ -131,9 +368,142 @@ impl RaycastVehicleController {
             &physics.query_pipeline,
             QueryFilter::new().exclude_rigid_body(self.cabin_body_handle),
         );
+        self.apply_anti_roll_forces(config, &mut physics.rigid_body_set, delta_seconds);
+        self.apply_aerodynamic_forces(config, &mut physics.rigid_body_set, delta_seconds);
 
         if let Some(cabin_body) = physics.rigid_body_set.get(self.cabin_body_handle) {
             self.cabin_isometry = *cabin_body.position();
         }
+        self.wheel_centers = self
+            .rapier_vehicle
+            .wheels()
+            .iter()
+            .map(|wheel| self.cabin_isometry.inverse_transform_point(&wheel.center()))
+            .collect();
+    }
+
+    /// How far the suspension of the wheel is compressed past its rest length. This
+    /// is 0 when the wheel isn't touching the ground.
+    fn suspension_compression(&self, wheel_index: usize, cabin_isometry: &Isometry3<f32>) -> f32 {
+        let wheel = &self.rapier_vehicle.wheels()[wheel_index];
+        if wheel.wheel_suspension_force <= 0.0 {
+            return 0.0;
+        }
+        let hard_point = cabin_isometry * wheel.chassis_connection_point_cs;
+        let suspension_length = (wheel.center() - hard_point).dot(&wheel.suspension());
+        wheel.suspension_rest_length - suspension_length
+    }
+
+    /// Push the more compressed side of each axle up and the other side down so that
+    /// the cabin resists rolling over in turns.
+    fn apply_anti_roll_forces(
+        &self,
+        config: &RaycastVehicleConfig,
+        rigid_body_set: &mut RigidBodySet,
+        delta_seconds: f32,
+    ) {
+        if let Some(cabin_body) = rigid_body_set.get_mut(self.cabin_body_handle) {
+            let cabin_isometry = *cabin_body.position();
+            let cabin_up = cabin_isometry.rotation.transform_vector(&UP_VECTOR);
+            let cabin_mass = cabin_body.mass();
+            for (anti_roll_stiffness, is_front_axle) in [
+                (config.front_anti_roll_stiffness, true),
+                (config.rear_anti_roll_stiffness, false),
+            ] {
+                if anti_roll_stiffness == 0.0 {
+                    continue;
+                }
+                let axle_wheel_index = |on_left: bool| {
+                    config.wheels.iter().position(|wheel_config| {
+                        let [wheel_x, _, wheel_z] = wheel_config.center_cabin_relative_position;
+                        (wheel_z < 0.0) == is_front_axle && (wheel_x < 0.0) == on_left
+                    })
+                };
+                if let (Some(left_wheel_index), Some(right_wheel_index)) =
+                    (axle_wheel_index(true), axle_wheel_index(false))
+                {
+                    let anti_roll_force = (self
+                        .suspension_compression(left_wheel_index, &cabin_isometry)
+                        - self.suspension_compression(right_wheel_index, &cabin_isometry))
+                        * anti_roll_stiffness
+                        * cabin_mass;
+                    for (wheel_index, direction) in
+                        [(left_wheel_index, 1.0), (right_wheel_index, -1.0)]
+                    {
+                        let wheel = &self.rapier_vehicle.wheels()[wheel_index];
+                        cabin_body.apply_impulse_at_point(
+                            cabin_up * anti_roll_force * direction * delta_seconds,
+                            cabin_isometry * wheel.chassis_connection_point_cs,
+                            true,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Press the cabin toward the ground and slow it down, both
+    /// proportionally to the square of its speed.
+    fn apply_aerodynamic_forces(
+        &self,
+        config: &RaycastVehicleConfig,
+        rigid_body_set: &mut RigidBodySet,
+        delta_seconds: f32,
+    ) {
+        if let Some(cabin_body) = rigid_body_set.get_mut(self.cabin_body_handle) {
+            let linvel = *cabin_body.linvel();
+            let cabin_rotation = cabin_body.position().rotation;
+            let forward_speed = linvel.dot(&cabin_rotation.transform_vector(&FORWARD_VECTOR));
+            let downforce = cabin_rotation.transform_vector(&DOWN_VECTOR)
+                * config.downforce_coefficient
+                * forward_speed.powi(2);
+            let drag = if linvel.magnitude() > 0.0 {
+                // Drag should only ever slow the cabin down, never push it backwards
+                (-linvel.normalize() * config.drag_coefficient * linvel.magnitude_squared())
+                    .cap_magnitude(linvel.magnitude() * cabin_body.mass() / delta_seconds)
+            } else {
+                Vector3::zeros()
+            };
+            cabin_body.apply_impulse((downforce + drag) * delta_seconds, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wheel_steer_angles(config: &RaycastVehicleConfig, steer_angle: f32) -> Vec<f32> {
+        let mut controller = RaycastVehicleController::from_config(config);
+        controller.steer_angle = steer_angle;
+        (0..config.wheels.len())
+            .filter(|wheel_index| config.wheels[*wheel_index].steers_on_input)
+            .map(|wheel_index| controller.wheel_steer_angle(config, wheel_index))
+            .collect()
+    }
+
+    #[test]
+    fn ackermann_keeps_the_steering_direction_at_max_angle() {
+        let mut config = RaycastVehicleConfig {
+            ackermann_factor: 1.0,
+            ..Default::default()
+        };
+        // Wide enough that the turning center is past the inner wheel at full lock
+        for wheel in config.wheels.iter_mut() {
+            wheel.center_cabin_relative_position[0] *= 10.0;
+        }
+
+        for max_angle in [config.wheel_left_turn_angle, config.wheel_right_turn_angle] {
+            let angles = wheel_steer_angles(&config, max_angle);
+            assert_eq!(angles.len(), 2);
+            for angle in angles {
+                assert_eq!(angle.signum(), max_angle.signum());
+                assert!(angle.abs() <= 90.0);
+            }
+        }
+
+        // The inner (left) wheel turns tighter than the outer one when turning left
+        let angles = wheel_steer_angles(&config, config.wheel_left_turn_angle);
+        assert!(angles[0] > angles[1]);
     }
 }
@@ -1,3 +1,4 @@
+use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::char;
@@ -30,17 +31,11 @@ impl<'a> Descriptor<'a> {
     }
 
     pub fn has_tag(&self, tag_name: &str) -> bool {
-        if let Some(tag_start_idx) = self.inner.find(tag_name) {
-            if tag_start_idx >= 1
-                && self.inner.chars().nth(tag_start_idx - 1).unwrap() == SEPARATOR_CHAR
-            {
-                true
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+        // Everything after the object name is a tag
+        self.inner
+            .split(SEPARATOR_CHAR)
+            .skip(1)
+            .any(|tag| tag == tag_name)
     }
 
     pub fn add_tag(&mut self, tag_name: &str) {
@@ -57,6 +52,20 @@ impl<'a> Descriptor<'a> {
     }
 }
 
+/// The colliders of the objects in a glTF whose names are tagged with any of the tags.
+pub fn tagged_colliders(gltf: &Gltf, physics: &PhysicsWorld, tags: &[&str]) -> Vec<ColliderHandle> {
+    gltf.nodes()
+        .filter_map(|node| node.name())
+        .filter(|object_name| {
+            let descriptor = Descriptor::from_name(*object_name);
+            tags.iter().any(|tag| descriptor.has_tag(tag))
+        })
+        .filter_map(|object_name| physics.named_rigid_bodies.handle_with_name(object_name))
+        .filter_map(|body_handle| physics.rigid_body_set.get(*body_handle))
+        .flat_map(|body| body.colliders().iter().copied())
+        .collect()
+}
+
 impl<'a> AsRef<str> for Descriptor<'a> {
     fn as_ref(&self) -> &str {
         self.inner.as_ref()
@@ -94,4 +103,13 @@ mod test {
 
         assert_eq!(d.object_name(), "Player");
     }
+
+    #[test]
+    fn tags_are_matched_whole() {
+        let d = Descriptor::from_name("ladder 2.ladder.tallest");
+        assert!(d.has_tag("ladder"));
+        assert!(d.has_tag("tallest"));
+        assert!(!d.has_tag("tall"));
+        assert!(!d.has_tag("ladder 2"));
+    }
 }
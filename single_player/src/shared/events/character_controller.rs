@@ -1,20 +1,123 @@
+use perigee::prelude::*;
 use serde::{Deserialize, Serialize};
 
+extern "C" {
+    fn character_event_hook(event_json_ptr: *const u8, event_json_len: usize);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WallSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SlideKind {
+    /// Sliding along after crouching at speed
+    Normal,
+    /// Sliding down a slope, which doesn't slow down
+    Downhill,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum CharacterControllerEvent {
     /// CharacterController just jumped
     Jump,
+    /// CharacterController just jumped in the air
+    AirJumped {
+        air_jumps_left: u8,
+    },
+    /// CharacterController just dashed in the air
+    AirDashed {
+        /// The direction of the dash, in world space
+        direction: [f32; 3],
+        air_dashes_left: u8,
+    },
     /// CharacterController just landed on a surface
-    Landed,
+    Landed {
+        /// How fast the CharacterController was falling when it landed
+        impact_speed: f32,
+    },
     Moving,
     Stopped,
     Crouched,
     StoodUpright,
     /// CharacterController just took a footstep
     Stepped,
-    StartedWallRunning,
+    StartedWallRunning {
+        side: WallSide,
+        /// The normal of the wall being run on, in world space
+        wall_normal: [f32; 3],
+    },
     StoppedWallRunning,
+    StartedWallClimbing,
+    StoppedWallClimbing,
     /// CharacterController started sliding on the ground
-    StartedSliding,
+    StartedSliding {
+        kind: SlideKind,
+    },
     StoppedSliding,
+    /// CharacterController caught a ledge and is hanging from it
+    GrabbedLedge {
+        /// The point on top of the ledge being held, in world space
+        ledge_point: [f32; 3],
+        /// The normal of the wall beneath the ledge, in world space
+        wall_normal: [f32; 3],
+    },
+    LetGoOfLedge,
+    StartedMantling,
+    FinishedMantling,
+    /// CharacterController entered water
+    StartedSwimming,
+    /// CharacterController left the water
+    StoppedSwimming,
+    /// CharacterController went under the water's surface
+    Dived,
+    /// CharacterController came back up to the water's surface
+    Surfaced,
+    /// CharacterController grabbed onto a ladder or other climbable surface
+    StartedClimbing,
+    StoppedClimbing,
+    /// CharacterController shot its grapple and the rope caught
+    Grappled {
+        /// Where the rope is attached, in world space
+        anchor_point: [f32; 3],
+    },
+    ReleasedGrapple,
+}
+
+impl CharacterControllerEvent {
+    /// Let the interface know the event happened. It's sent
+    /// as JSON, with its variant's name in `type`.
+    pub fn send_to_interface(&self) {
+        match serde_json::to_string(self) {
+            Ok(character_event) => unsafe {
+                character_event_hook(character_event.as_ptr(), character_event.len())
+            },
+            Err(json_err) => warn!(
+                "Couldn't serialize character event {:?}: {}",
+                self, json_err
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn serializes_with_its_data() {
+        let wallrun_started = CharacterControllerEvent::StartedWallRunning {
+            side: WallSide::Left,
+            wall_normal: [1.0, 0.0, 0.0],
+        };
+        assert_eq!(
+            serde_json::to_string(&wallrun_started).unwrap(),
+            r#"{"type":"STARTED_WALL_RUNNING","side":"LEFT","wall_normal":[1.0,0.0,0.0]}"#
+        );
+    }
 }
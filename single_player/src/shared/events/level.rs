@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// Why the player was put back somewhere in the level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ResetReason {
+    /// The player went somewhere they can't come back from
+    OutOfBounds,
+    /// The player asked to be put back
+    Requested,
+}
+
+/// Something that happened in a level played as the player character that the
+/// interface should know about. It's sent to the interface as JSON, with its variant's name in `type`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlayerLevelEvent {
+    LevelCompleted {
+        completion_seconds: f32,
+    },
+    PlayerReset {
+        reason: ResetReason,
+        /// Where the player was put back
+        position: [f32; 3],
+        run_seconds: f32,
+    },
+    CheckpointReached {
+        /// Checkpoints are numbered from 1 in the order they're reached
+        checkpoint_index: u8,
+        run_seconds: f32,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn serializes_with_its_type() {
+        let checkpoint_event = PlayerLevelEvent::CheckpointReached {
+            checkpoint_index: 2,
+            run_seconds: 12.5,
+        };
+        assert_eq!(
+            serde_json::to_string(&checkpoint_event).unwrap(),
+            r#"{"type":"CHECKPOINT_REACHED","checkpoint_index":2,"run_seconds":12.5}"#
+        );
+
+        let reset_event = PlayerLevelEvent::PlayerReset {
+            reason: ResetReason::OutOfBounds,
+            position: [1.0, 2.0, 3.0],
+            run_seconds: 4.0,
+        };
+        assert_eq!(
+            serde_json::to_string(&reset_event).unwrap(),
+            r#"{"type":"PLAYER_RESET","reason":"OUT_OF_BOUNDS","position":[1.0,2.0,3.0],"run_seconds":4.0}"#
+        );
+    }
+}
@@ -1,3 +1,6 @@
 pub mod character_controller;
+pub mod level;
 
 pub use character_controller::*;
+#[cfg(any(feature = "level_1", feature = "level_2", feature = "custom_level"))]
+pub use level::*;
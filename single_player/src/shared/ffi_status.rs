@@ -0,0 +1,113 @@
+use perigee::prelude::*;
+use std::ffi::{CStr, CString};
+use std::fmt;
+
+/// What happened when the interface called into a simulation. Anything
+/// other than `Ok` means the simulation's last error says what went wrong.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiStatus {
+    Ok = 0,
+    /// A name or index didn't refer to anything in the level.
+    NotFound = 1,
+    /// A string or buffer from the interface couldn't be used.
+    InvalidInput = 2,
+    /// The call doesn't make sense for the level or what it's doing right now.
+    InvalidState = 3,
+}
+
+/// Why an FFI call couldn't be completed.
+#[derive(Debug, PartialEq)]
+pub struct FfiError {
+    pub status: FfiStatus,
+    pub message: String,
+}
+
+impl FfiError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self {
+            status: FfiStatus::NotFound,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self {
+            status: FfiStatus::InvalidInput,
+            message: message.into(),
+        }
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self {
+            status: FfiStatus::InvalidState,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for FfiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Read a string the interface copied into memory from `alloc_string`.
+pub(crate) fn str_from_ffi<'a>(str_ptr: *const CString) -> Result<&'a str, FfiError> {
+    if str_ptr.is_null() {
+        return Err(FfiError::invalid_input(
+            "Expected a string but received null",
+        ));
+    }
+    unsafe { CStr::from_ptr(str_ptr as *const _) }
+        .to_str()
+        .map_err(|utf8_err| FfiError::invalid_input(format!("String isn't UTF-8: {}", utf8_err)))
+}
+
+/// Write a value into a slot the interface allocated for it.
+pub(crate) fn write_to_slot<T>(slot: *mut T, value: T) -> Result<(), FfiError> {
+    if slot.is_null() {
+        return Err(FfiError::invalid_input("Expected a slot but received null"));
+    }
+    unsafe { *slot = value };
+    Ok(())
+}
+
+/// Turn the result of an FFI call into a status, keeping
+/// the reason it failed around for the interface to read.
+pub fn report_status(last_error: &mut String, result: Result<(), FfiError>) -> FfiStatus {
+    match result {
+        Ok(()) => FfiStatus::Ok,
+        Err(ffi_err) => {
+            warn!("{}", ffi_err);
+            *last_error = ffi_err.message;
+            ffi_err.status
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keeps_the_last_error() {
+        let mut last_error = String::new();
+        assert_eq!(report_status(&mut last_error, Ok(())), FfiStatus::Ok);
+        assert!(last_error.is_empty());
+
+        let status = report_status(
+            &mut last_error,
+            Err(FfiError::not_found("No prop is named \"Crate\"")),
+        );
+        assert_eq!(status, FfiStatus::NotFound);
+        assert_eq!(last_error, "No prop is named \"Crate\"");
+
+        assert_eq!(
+            str_from_ffi(std::ptr::null()),
+            Err(FfiError::invalid_input(
+                "Expected a string but received null"
+            ))
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+static GHOST_MAGIC: &[u8; 4] = b"PGST";
+static GHOST_FORMAT_VERSION: u8 = 1;
+/// Magic, version, kind, isometries per frame, sample rate, and frame count
+static GHOST_HEADER_LEN: usize = 4 + 1 + 1 + 1 + 4 + 4;
+/// A full precision translation and a quantized rotation
+static ENCODED_ISOMETRY_LEN: usize = 3 * 4 + 4 * 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GhostKind {
+    /// A car's cabin followed by each of its wheels
+    Sedan,
+    /// A player's body
+    Player,
+}
+
+impl From<GhostKind> for u8 {
+    fn from(kind: GhostKind) -> Self {
+        match kind {
+            GhostKind::Sedan => 0,
+            GhostKind::Player => 1,
+        }
+    }
+}
+
+impl TryFrom<u8> for GhostKind {
+    type Error = String;
+    fn try_from(kind_code: u8) -> Result<Self, Self::Error> {
+        match kind_code {
+            0 => Ok(Self::Sedan),
+            1 => Ok(Self::Player),
+            _ => Err(format!("Unrecognized ghost kind {}", kind_code)),
+        }
+    }
+}
+
+/// A run sampled at a fixed rate, with a set of isometries and an animation state for each frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostRecording {
+    kind: GhostKind,
+    sample_rate: f32,
+    isometries_per_frame: usize,
+    /// Every frame's isometries laid end to end
+    isometries: Vec<Isometry3<f32>>,
+    animation_codes: Vec<u8>,
+}
+
+impl GhostRecording {
+    pub fn new(kind: GhostKind, sample_rate: f32, isometries_per_frame: usize) -> Self {
+        Self {
+            kind,
+            sample_rate,
+            isometries_per_frame,
+            isometries: Vec::new(),
+            animation_codes: Vec::new(),
+        }
+    }
+
+    pub fn kind(&self) -> GhostKind {
+        self.kind
+    }
+
+    pub fn isometries_per_frame(&self) -> usize {
+        self.isometries_per_frame
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.animation_codes.len()
+    }
+
+    /// How many seconds the recording spans.
+    pub fn duration(&self) -> f32 {
+        self.frame_count().saturating_sub(1) as f32 / self.sample_rate
+    }
+
+    fn push_frame(&mut self, isometries: &[Isometry3<f32>], animation_code: u8) {
+        self.isometries
+            .extend_from_slice(&isometries[..self.isometries_per_frame]);
+        self.animation_codes.push(animation_code);
+    }
+
+    fn clear(&mut self) {
+        self.isometries.clear();
+        self.animation_codes.clear();
+    }
+
+    fn frame_isometry(&self, frame_index: usize, isometry_index: usize) -> &Isometry3<f32> {
+        &self.isometries[frame_index * self.isometries_per_frame + isometry_index]
+    }
+
+    /// Pack the recording into a compact binary format. Translations are kept at full
+    /// precision, but rotations are quantized to 16 bits per quaternion component.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(
+            GHOST_HEADER_LEN
+                + self.frame_count() * (1 + self.isometries_per_frame * ENCODED_ISOMETRY_LEN),
+        );
+        bytes.extend_from_slice(GHOST_MAGIC);
+        bytes.push(GHOST_FORMAT_VERSION);
+        bytes.push(u8::from(self.kind));
+        bytes.push(self.isometries_per_frame as u8);
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.frame_count() as u32).to_le_bytes());
+        for (frame_index, animation_code) in self.animation_codes.iter().enumerate() {
+            bytes.push(*animation_code);
+            for isometry_index in 0..self.isometries_per_frame {
+                let isometry = self.frame_isometry(frame_index, isometry_index);
+                for component in isometry.translation.vector.iter() {
+                    bytes.extend_from_slice(&component.to_le_bytes());
+                }
+                for component in isometry.rotation.coords.iter() {
+                    let quantized = (component.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                    bytes.extend_from_slice(&quantized.to_le_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Unpack a recording made with `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < GHOST_HEADER_LEN || &bytes[0..4] != GHOST_MAGIC {
+            return Err(String::from("Data isn't a ghost recording"));
+        }
+        if bytes[4] != GHOST_FORMAT_VERSION {
+            return Err(format!("Unsupported ghost format version {}", bytes[4]));
+        }
+        let kind = GhostKind::try_from(bytes[5])?;
+        let isometries_per_frame = bytes[6] as usize;
+        let sample_rate = f32::from_le_bytes([bytes[7], bytes[8], bytes[9], bytes[10]]);
+        let frame_count = u32::from_le_bytes([bytes[11], bytes[12], bytes[13], bytes[14]]) as usize;
+        if isometries_per_frame == 0 || sample_rate <= 0.0 {
+            return Err(String::from("Ghost recording header is invalid"));
+        }
+        let frame_len = 1 + isometries_per_frame * ENCODED_ISOMETRY_LEN;
+        let expected_len = frame_count
+            .checked_mul(frame_len)
+            .and_then(|frames_len| frames_len.checked_add(GHOST_HEADER_LEN));
+        if expected_len != Some(bytes.len()) {
+            return Err(String::from("Ghost recording is truncated"));
+        }
+
+        let read_f32 = |offset: usize| {
+            f32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let read_quantized = |offset: usize| {
+            i16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as f32 / i16::MAX as f32
+        };
+        let mut recording = Self::new(kind, sample_rate, isometries_per_frame);
+        for frame_index in 0..frame_count {
+            let frame_offset = GHOST_HEADER_LEN + frame_index * frame_len;
+            recording.animation_codes.push(bytes[frame_offset]);
+            for isometry_index in 0..isometries_per_frame {
+                let offset = frame_offset + 1 + isometry_index * ENCODED_ISOMETRY_LEN;
+                let translation =
+                    Vector3::new(read_f32(offset), read_f32(offset + 4), read_f32(offset + 8));
+                // Quaternion coordinates are stored as i, j, k, w
+                let rotation = UnitQuaternion::from_quaternion(Quaternion::new(
+                    read_quantized(offset + 18),
+                    read_quantized(offset + 12),
+                    read_quantized(offset + 14),
+                    read_quantized(offset + 16),
+                ));
+                recording
+                    .isometries
+                    .push(Isometry3::from_parts(translation.into(), rotation));
+            }
+        }
+        Ok(recording)
+    }
+}
+
+/// Samples a live run into a ghost recording at a fixed rate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhostRecorder {
+    recording: GhostRecording,
+    /// The most frames a recording can hold before it's given up on
+    max_frame_count: usize,
+    seconds_since_last_sample: f32,
+    is_recording: bool,
+}
+
+impl GhostRecorder {
+    pub fn new(
+        kind: GhostKind,
+        sample_rate: f32,
+        max_seconds: f32,
+        isometries_per_frame: usize,
+    ) -> Self {
+        Self {
+            recording: GhostRecording::new(kind, sample_rate, isometries_per_frame),
+            max_frame_count: (max_seconds.max(0.0) * sample_rate).floor() as usize + 1,
+            seconds_since_last_sample: 0.0,
+            is_recording: false,
+        }
+    }
+
+    /// Throw away anything recorded so far and start recording again.
+    pub fn start(&mut self) {
+        self.recording.clear();
+        self.seconds_since_last_sample = 0.0;
+        self.is_recording = true;
+    }
+
+    /// Stop recording, keeping what's been recorded so far.
+    pub fn stop(&mut self) -> &GhostRecording {
+        self.is_recording = false;
+        &self.recording
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.is_recording
+    }
+
+    /// Record a frame if it's time for a new sample. Runs that go on longer than
+    /// the recorder's max duration are thrown away and recording stops.
+    pub fn update(
+        &mut self,
+        isometries: &[Isometry3<f32>],
+        animation_code: u8,
+        delta_seconds: f32,
+    ) {
+        if !self.is_recording {
+            return;
+        }
+        let sample_interval = 1.0 / self.recording.sample_rate;
+        // Always record the first frame so that the ghost starts where the run did
+        if self.recording.frame_count() > 0 {
+            self.seconds_since_last_sample += delta_seconds;
+            if self.seconds_since_last_sample < sample_interval {
+                return;
+            }
+            self.seconds_since_last_sample -= sample_interval;
+        }
+        if self.recording.frame_count() >= self.max_frame_count {
+            self.recording.clear();
+            self.is_recording = false;
+            return;
+        }
+        self.recording.push_frame(isometries, animation_code);
+    }
+}
+
+/// Plays a ghost recording back in step with a live run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhostPlayback {
+    recording: GhostRecording,
+    elapsed_seconds: f32,
+}
+
+impl GhostPlayback {
+    pub fn new(recording: GhostRecording) -> Self {
+        Self {
+            recording,
+            elapsed_seconds: 0.0,
+        }
+    }
+
+    /// Unpack a recording to play back, making sure it's the kind of ghost expected.
+    pub fn from_bytes(
+        bytes: &[u8],
+        expected_kind: GhostKind,
+        expected_isometries_per_frame: usize,
+    ) -> Result<Self, String> {
+        let recording = GhostRecording::from_bytes(bytes)?;
+        if recording.kind() != expected_kind
+            || recording.isometries_per_frame() != expected_isometries_per_frame
+        {
+            return Err(String::from("Ghost recording doesn't fit this level"));
+        }
+        Ok(Self::new(recording))
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed_seconds = 0.0;
+    }
+
+    pub fn advance(&mut self, delta_seconds: f32) {
+        self.elapsed_seconds =
+            (self.elapsed_seconds + delta_seconds).min(self.recording.duration());
+    }
+
+    /// The frame currently being played and how far (from 0 to 1) it is to the next one.
+    fn frame_position(&self) -> (usize, f32) {
+        let frame_position = self.elapsed_seconds * self.recording.sample_rate;
+        let last_frame_index = self.recording.frame_count().saturating_sub(1);
+        let frame_index = (frame_position.floor() as usize).min(last_frame_index);
+        (frame_index, frame_position - frame_index as f32)
+    }
+
+    /// The isometry at the provided index, interpolated between recorded frames.
+    pub fn isometry(&self, isometry_index: usize) -> Isometry3<f32> {
+        if self.recording.frame_count() == 0
+            || isometry_index >= self.recording.isometries_per_frame()
+        {
+            return Isometry3::identity();
+        }
+        let (frame_index, t) = self.frame_position();
+        let next_frame_index = (frame_index + 1).min(self.recording.frame_count() - 1);
+        self.recording
+            .frame_isometry(frame_index, isometry_index)
+            .lerp_slerp(
+                self.recording
+                    .frame_isometry(next_frame_index, isometry_index),
+                t.clamp(0.0, 1.0),
+            )
+    }
+
+    /// The animation state of the frame currently being played.
+    pub fn animation_code(&self) -> u8 {
+        if self.recording.frame_count() == 0 {
+            return 0;
+        }
+        self.recording.animation_codes[self.frame_position().0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recordings_survive_serialization() {
+        let mut recorder = GhostRecorder::new(GhostKind::Player, 8.0, 60.0, 1);
+        recorder.start();
+        for frame in 0..30 {
+            let isometry = Isometry3::from_parts(
+                Vector3::new(frame as f32, 1.5, -2.0).into(),
+                UnitQuaternion::from_euler_angles(0.1, frame as f32 * 0.05, 0.0),
+            );
+            recorder.update(&[isometry], (frame % 4) as u8, 1.0 / 32.0);
+        }
+        let recording = recorder.stop().clone();
+        assert_eq!(recording.frame_count(), 8);
+
+        let decoded = GhostRecording::from_bytes(&recording.to_bytes()).unwrap();
+        assert_eq!(decoded.kind(), GhostKind::Player);
+        assert_eq!(decoded.frame_count(), recording.frame_count());
+        for frame_index in 0..recording.frame_count() {
+            let original = recording.frame_isometry(frame_index, 0);
+            let round_tripped = decoded.frame_isometry(frame_index, 0);
+            assert_eq!(original.translation, round_tripped.translation);
+            assert!(original.rotation.angle_to(&round_tripped.rotation) < 0.001);
+        }
+
+        let mut playback = GhostPlayback::new(decoded);
+        playback.advance(2.5 / 8.0);
+        assert!((playback.isometry(0).translation.vector.x - 10.0).abs() < 0.001);
+        assert!(GhostRecording::from_bytes(b"not a ghost").is_err());
+    }
+
+    #[test]
+    fn long_runs_stop_recording() {
+        let mut recorder = GhostRecorder::new(GhostKind::Player, 10.0, 1.0, 1);
+        recorder.start();
+        for _ in 0..11 {
+            recorder.update(&[Isometry3::identity()], 0, 0.1);
+        }
+        assert!(recorder.is_recording());
+        assert_eq!(recorder.recording.frame_count(), 11);
+
+        recorder.update(&[Isometry3::identity()], 0, 0.1);
+        assert!(!recorder.is_recording());
+        assert_eq!(recorder.recording.frame_count(), 0);
+    }
+}
@@ -30,9 +30,33 @@ pub struct Input {
     /// The crouch status of the character controller (true is intention to crouch, false is not)
     #[getset(get_copy = "pub")]
     crouch: bool,
+    /// The air dash status of the character controller (true is intention to dash, false is not)
+    // Replays recorded before dashing existed don't have it
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    dash: bool,
+    /// The grapple status of the character controller (true is intention to grapple, false is not)
+    // Replays recorded before grappling existed don't have it
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    grapple: bool,
+    /// The reel magnitude of the grapple's rope (in is positive, out is negative)
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    reel: f32,
     /// The third person aim mode of the character controller
     #[getset(get_copy = "pub")]
     aim: bool,
+    /// The look back status of the vehicle camera (true is intention to look back, false is not)
+    // Replays recorded before looking back existed don't have it
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    look_back: bool,
+    /// The reset status of the object controlled (true is intention to reset, false is not)
+    // Replays recorded before resetting went through input don't have it
+    #[serde(default)]
+    #[getset(get_copy = "pub")]
+    reset: bool,
     #[getset(get_copy = "pub")]
     steer: f32,
     #[getset(get_copy = "pub")]
@@ -50,7 +74,12 @@ impl Default for Input {
             rotate_right: 0.0,
             jump: false,
             crouch: false,
+            dash: false,
+            grapple: false,
+            reel: 0.0,
             aim: false,
+            look_back: false,
+            reset: false,
             steer: 0.0,
             brake: 0.0,
             throttle: 0.0,
@@ -97,16 +126,42 @@ impl Input {
         self.steer = new_magnitude;
     }
 
-    /// Sets the look-up magnitude of the object
+    /// Adds to the look-up magnitude of the object
     /// controlled by the character controller (up is positive, down is negative).
-    pub fn set_rotate_up(&mut self, new_magnitude: f32) {
-        self.rotate_up = new_magnitude;
+    /// Looking is a change since the last frame, so frames that don't tick add up
+    /// until a tick gets to use them.
+    pub fn add_rotate_up(&mut self, magnitude_delta: f32) {
+        self.rotate_up += magnitude_delta;
     }
 
-    /// Sets the right turn magnitude of the object
+    /// Adds to the right turn magnitude of the object
     /// controlled by the character controller (right is positive, left is negative).
-    pub fn set_rotate_right(&mut self, new_magnitude: f32) {
-        self.rotate_right = new_magnitude;
+    /// Looking is a change since the last frame, so frames that don't tick add up
+    /// until a tick gets to use them.
+    pub fn add_rotate_right(&mut self, magnitude_delta: f32) {
+        self.rotate_right += magnitude_delta;
+    }
+
+    /// Forget how far to look once a tick has looked that far, so
+    /// the rest of the ticks in a step don't look that far again.
+    pub fn consume_look(&mut self) {
+        self.rotate_up = 0.0;
+        self.rotate_right = 0.0;
+    }
+
+    /// Sets how hard a vehicle should steer (right is positive, left is negative).
+    pub fn set_steer(&mut self, new_magnitude: f32) {
+        self.steer = new_magnitude;
+    }
+
+    /// Sets how much throttle a vehicle should apply, from 0 to 1.
+    pub fn set_throttle(&mut self, new_magnitude: f32) {
+        self.throttle = new_magnitude;
+    }
+
+    /// Sets how much brake a vehicle should apply, from 0 to 1.
+    pub fn set_brake(&mut self, new_magnitude: f32) {
+        self.brake = new_magnitude;
     }
 
     /// Sets the jump status of the object controlled
@@ -120,8 +175,33 @@ impl Input {
         self.crouch = crouch_state;
     }
 
+    /// Sets the air dash status of the character controller (true is intention to dash, false is not)
+    pub fn set_dash(&mut self, dash_state: bool) {
+        self.dash = dash_state;
+    }
+
+    /// Sets the grapple status of the character controller (true is intention to grapple, false is not)
+    pub fn set_grapple(&mut self, grapple_state: bool) {
+        self.grapple = grapple_state;
+    }
+
+    /// Sets the reel magnitude of the grapple's rope (in is positive, out is negative).
+    pub fn set_reel(&mut self, new_magnitude: f32) {
+        self.reel = new_magnitude;
+    }
+
     /// Sets the aim status of the character controller (true is intention to aim, false is not)
     pub fn set_aim(&mut self, aim_state: bool) {
         self.aim = aim_state;
     }
+
+    /// Sets the look back status of the vehicle camera (true is intention to look back, false is not)
+    pub fn set_look_back(&mut self, look_back_state: bool) {
+        self.look_back = look_back_state;
+    }
+
+    /// Sets the reset status of the object controlled (true is intention to reset, false is not)
+    pub fn set_reset(&mut self, reset_state: bool) {
+        self.reset = reset_state;
+    }
 }
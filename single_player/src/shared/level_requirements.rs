@@ -0,0 +1,182 @@
+use crate::shared::ffi_status::FfiError;
+use perigee::config::PhysicsConfig;
+use perigee::prelude::*;
+use std::fmt;
+
+/// Why a level couldn't be loaded from its glTF.
+#[derive(Debug, PartialEq)]
+pub enum LevelInitError {
+    /// The glTF couldn't be parsed at all.
+    InvalidGltf(String),
+    /// The glTF's physics objects couldn't be loaded.
+    InvalidPhysics(String),
+    /// The glTF's points of interest couldn't be loaded.
+    InvalidPointsOfInterest(String),
+    /// The glTF loaded, but is missing objects the level needs.
+    MissingObjects(LevelRequirements),
+    /// The level was already loaded, and loading it again would load everything twice.
+    AlreadyInitialized,
+}
+
+impl fmt::Display for LevelInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidGltf(gltf_err) => write!(f, "Level glTF couldn't be read: {}", gltf_err),
+            Self::InvalidPhysics(physics_err) => {
+                write!(f, "Level physics couldn't be loaded: {}", physics_err)
+            }
+            Self::InvalidPointsOfInterest(poi_err) => {
+                write!(
+                    f,
+                    "Level points of interest couldn't be loaded: {}",
+                    poi_err
+                )
+            }
+            Self::MissingObjects(missing) => {
+                write!(f, "Level glTF is missing ")?;
+                let missing_lists = [
+                    ("points of interest", &missing.pois),
+                    ("sensors", &missing.sensors),
+                    ("rigid bodies", &missing.rigid_bodies),
+                ];
+                let mut first_list = true;
+                for (kind, names) in missing_lists {
+                    if names.is_empty() {
+                        continue;
+                    }
+                    if !first_list {
+                        write!(f, "; ")?;
+                    }
+                    let quoted_names: Vec<String> =
+                        names.iter().map(|name| format!("\"{}\"", name)).collect();
+                    write!(f, "{} {}", kind, quoted_names.join(", "))?;
+                    first_list = false;
+                }
+                Ok(())
+            }
+            Self::AlreadyInitialized => write!(f, "Level is already initialized"),
+        }
+    }
+}
+
+impl From<LevelInitError> for FfiError {
+    fn from(init_err: LevelInitError) -> Self {
+        match init_err {
+            LevelInitError::AlreadyInitialized => FfiError::invalid_state(init_err.to_string()),
+            _ => FfiError::invalid_input(init_err.to_string()),
+        }
+    }
+}
+
+/// The names of everything a level expects to find in its glTF.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LevelRequirements {
+    pub pois: Vec<String>,
+    pub sensors: Vec<String>,
+    pub rigid_bodies: Vec<String>,
+}
+
+impl LevelRequirements {
+    pub fn is_empty(&self) -> bool {
+        self.pois.is_empty() && self.sensors.is_empty() && self.rigid_bodies.is_empty()
+    }
+
+    /// Make sure everything required was loaded, listing everything that wasn't.
+    pub fn check(
+        &self,
+        physics: &PhysicsWorld,
+        pois: &PointsOfInterest,
+    ) -> Result<(), LevelInitError> {
+        let missing = LevelRequirements {
+            pois: self
+                .pois
+                .iter()
+                .filter(|poi_name| pois.point_with_name(poi_name).is_none())
+                .cloned()
+                .collect(),
+            sensors: self
+                .sensors
+                .iter()
+                .filter(|sensor_name| {
+                    physics
+                        .named_sensors
+                        .handle_with_name(sensor_name.as_str())
+                        .is_none()
+                })
+                .cloned()
+                .collect(),
+            rigid_bodies: self
+                .rigid_bodies
+                .iter()
+                .filter(|body_name| {
+                    physics
+                        .named_rigid_bodies
+                        .handle_with_name(body_name.as_str())
+                        .is_none()
+                })
+                .cloned()
+                .collect(),
+        };
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(LevelInitError::MissingObjects(missing))
+        }
+    }
+
+    /// Load a level's glTF into the provided physics world and points of interest,
+    /// then make sure it has everything required.
+    pub fn load_gltf(
+        &self,
+        gltf_bytes: &[u8],
+        physics: &mut PhysicsWorld,
+        pois: &mut PointsOfInterest,
+    ) -> Result<Gltf, LevelInitError> {
+        let gltf = Gltf::from_slice(gltf_bytes)
+            .map_err(|gltf_err| LevelInitError::InvalidGltf(gltf_err.to_string()))?;
+        physics
+            .load_from_gltf(&gltf, None)
+            .map_err(|physics_err| LevelInitError::InvalidPhysics(physics_err.to_string()))?;
+        pois.load_from_gltf(&gltf)
+            .map_err(|poi_err| LevelInitError::InvalidPointsOfInterest(poi_err.to_string()))?;
+        self.check(physics, pois)?;
+        Ok(gltf)
+    }
+
+    /// Check a level's glTF without loading it into a simulation.
+    pub fn validate_gltf(&self, gltf_bytes: &[u8]) -> Result<(), LevelInitError> {
+        let mut physics = PhysicsWorld::from_config(&PhysicsConfig::default());
+        let mut pois = PointsOfInterest::default();
+        self.load_gltf(gltf_bytes, &mut physics, &mut pois)
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lists_every_missing_object() {
+        let requirements = LevelRequirements {
+            pois: vec![String::from("Player Start")],
+            sensors: vec![String::from("Finish Sensor"), String::from("Launch Sensor")],
+            rigid_bodies: Vec::new(),
+        };
+        let physics = PhysicsWorld::from_config(&PhysicsConfig::default());
+        let pois = PointsOfInterest::default();
+
+        let init_err = requirements.check(&physics, &pois).unwrap_err();
+        assert_eq!(init_err, LevelInitError::MissingObjects(requirements));
+        assert_eq!(
+            init_err.to_string(),
+            "Level glTF is missing points of interest \"Player Start\"; \
+             sensors \"Finish Sensor\", \"Launch Sensor\""
+        );
+
+        assert!(matches!(
+            LevelRequirements::default().validate_gltf(b"not a glTF"),
+            Err(LevelInitError::InvalidGltf(_))
+        ));
+    }
+}
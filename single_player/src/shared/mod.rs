@@ -1,10 +1,25 @@
 pub mod boom;
+pub mod checksum;
+pub mod climbable;
 pub mod controllers;
 pub mod descriptor;
 pub mod events;
+pub mod ffi_status;
+pub mod ghost;
 pub mod input;
+pub mod level_requirements;
+// Only levels played as the player character are built on this
+#[cfg(any(feature = "level_1", feature = "custom_level"))]
+pub mod player_level;
 pub mod prefabs;
+pub mod replay;
 pub mod settings;
+pub mod spline;
+#[cfg(test)]
+mod test_hooks;
+pub mod time_scale;
+pub mod timestep;
+pub mod water;
 
 pub mod vectors {
     use perigee::rapier3d::na::Vector3;
@@ -0,0 +1,1307 @@
+use crate::config::PlayerLevelConfig;
+use crate::registry::{Level, LevelImport, PlayerCharacter};
+use crate::shared::{
+    checksum::StateChecksum,
+    climbable::ClimbableSurfaces,
+    descriptor::Descriptor,
+    events::{CharacterControllerEvent, PlayerLevelEvent, ResetReason},
+    ffi_status::FfiError,
+    ghost::{GhostKind, GhostPlayback, GhostRecorder},
+    input::Input,
+    level_requirements::{LevelInitError, LevelRequirements},
+    prefabs::{MovementState, Player},
+    replay::{InputLog, ReplayedFrame},
+    settings::GameSettings,
+    time_scale::TimeScale,
+    timestep::{FixedTimestep, RigidBodySnapshot},
+    water::WaterVolumes,
+};
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Each level played as the player character is built into a module of its own,
+// and they all export the same FFI
+#[cfg(all(feature = "custom_level", not(feature = "all_levels")))]
+use crate::custom_level::Sim;
+#[cfg(all(feature = "level_1", not(feature = "all_levels")))]
+use crate::level_1::Sim;
+#[cfg(all(
+    any(feature = "level_1", feature = "custom_level"),
+    not(feature = "all_levels")
+))]
+use crate::shared::ffi_status::{report_status, str_from_ffi, write_to_slot, FfiStatus};
+#[cfg(all(
+    any(feature = "level_1", feature = "custom_level"),
+    not(feature = "all_levels")
+))]
+use std::ffi::CString;
+
+extern "C" {
+    fn level_event_hook(event_json_ptr: *const u8, event_json_len: usize);
+}
+
+/// What a level played as the player character has on top of what every such level has.
+pub trait PlayerLevelContent<'a> {
+    fn config(&self) -> &PlayerLevelConfig;
+    fn scene_gltf_bytes(&self) -> &[u8];
+    /// The point of interest the player starts at and is reset to.
+    fn spawn_poi(&self) -> &str;
+    /// The sensor that completes the level once the player enters it.
+    fn finish_sensor(&self) -> &str;
+    /// The audio looped on the player while the level is being played and its volume, if any.
+    fn music(&self) -> Option<(&str, f32)>;
+    /// The content as it was before the level was initialized, for checking replays with.
+    fn replay_copy(&self) -> Self;
+
+    /// Add everything else the content expects to find in the level's glTF.
+    fn add_requirements(&self, _requirements: &mut LevelRequirements) {}
+
+    /// Set the content up once the level's glTF has been loaded.
+    fn initialize(&mut self, _level: &mut PlayerLevel<'a>) {}
+
+    /// Put the content back the way it was when the level started.
+    fn restart(&mut self, _level: &mut PlayerLevel<'a>) {}
+
+    /// Move the content along before the physics world is stepped.
+    fn update(&mut self, _level: &mut PlayerLevel<'a>, _delta_seconds: f32) {}
+
+    /// React to whatever ran into the content during the physics step.
+    fn handle_events(&mut self, _level: &mut PlayerLevel<'a>, _run_seconds: f32) {}
+
+    /// Hash everything about the content that decides how the level plays out from here on.
+    fn hash_state(&self, _checksum: &mut StateChecksum) {}
+
+    /// Where the interface copies the level in to, if it's built outside of this crate.
+    fn level_import_mut<'s>(
+        _sim: &'s mut PlayerLevelSim<'a, Self>,
+    ) -> Option<&'s mut dyn LevelImport>
+    where
+        Self: Sized,
+    {
+        None
+    }
+}
+
+/// Everything every level played as the player character has. What else
+/// is in a particular level is up to its [`PlayerLevelContent`].
+#[derive(Serialize, Deserialize)]
+pub struct PlayerLevel<'a> {
+    pub settings: GameSettings,
+    pub physics: PhysicsWorld,
+    pub pois: PointsOfInterest,
+    pub player: Player<'a>,
+    player_gltf_bytes: &'a [u8],
+    /// Whether the level's glTF was successfully loaded
+    initialized: bool,
+    spawn_iso: Isometry3<f32>,
+    /// Where the player is put back when they go out of bounds
+    pub checkpoint_iso: Isometry3<f32>,
+    level_completed: bool,
+    /// Whether the interface is kept out of what the sim does, as when checking a replay
+    headless: bool,
+    ghost_recorder: GhostRecorder,
+    ghost_playback: Option<GhostPlayback>,
+    /// The movement state the ghost was last animated with
+    ghost_movement_state: Option<MovementState>,
+    /// The most recently finished recording of the player's run
+    ghost_recording_bytes: Vec<u8>,
+    /// Where the interface copies a recording to before loading it as the ghost
+    ghost_import_bytes: Vec<u8>,
+    /// Everything the player did up until the level was completed
+    input_log: InputLog,
+    /// The input log of the completed run, ready for the interface to save
+    input_log_bytes: Vec<u8>,
+    /// Where the interface copies an input log to before verifying it
+    replay_import_bytes: Vec<u8>,
+    /// Where the interface copies a glTF to before validating it
+    gltf_validation_bytes: Vec<u8>,
+    /// Why the most recent FFI call that failed couldn't be completed
+    pub last_error: String,
+    /// The checksum of the simulation's state as of the latest tick
+    state_checksum: u32,
+    timestep: FixedTimestep,
+    pub time_scale: TimeScale,
+    previous_player_body_isometry: Isometry3<f32>,
+    previous_camera_isometry: Isometry3<f32>,
+    #[serde(skip)]
+    previous_rigid_body_isometries: RigidBodySnapshot,
+    #[serde(skip)]
+    animation_manager: AnimationManager,
+    #[serde(skip)]
+    player_event_channel: ColliderEventChannel,
+    #[serde(skip)]
+    finish_sensor_event_channel: ColliderEventChannel,
+    #[serde(skip)]
+    pub input: Input,
+}
+
+impl<'a> PlayerLevel<'a> {
+    pub fn new(config: &PlayerLevelConfig) -> Self {
+        Self {
+            settings: GameSettings::default(),
+            physics: PhysicsWorld::from_config(&config.physics),
+            pois: PointsOfInterest::default(),
+            player: Player::from_config(&config.player),
+            player_gltf_bytes: include_bytes!("../../../assets/gltf/shared/player-character.glb"),
+            initialized: false,
+            spawn_iso: Isometry::identity(),
+            checkpoint_iso: Isometry::identity(),
+            level_completed: false,
+            headless: false,
+            ghost_recorder: GhostRecorder::new(
+                GhostKind::Player,
+                config.ghost_sample_rate,
+                config.max_ghost_seconds,
+                1,
+            ),
+            ghost_playback: None,
+            ghost_movement_state: None,
+            ghost_recording_bytes: Vec::new(),
+            ghost_import_bytes: Vec::new(),
+            input_log: InputLog::default(),
+            input_log_bytes: Vec::new(),
+            replay_import_bytes: Vec::new(),
+            gltf_validation_bytes: Vec::new(),
+            last_error: String::new(),
+            state_checksum: 0,
+            timestep: FixedTimestep::new(config.tick_rate),
+            time_scale: TimeScale::default(),
+            previous_player_body_isometry: Isometry::identity(),
+            previous_camera_isometry: Isometry::identity(),
+            previous_rigid_body_isometries: RigidBodySnapshot::default(),
+            animation_manager: AnimationManager::default(),
+            player_event_channel: ColliderEventChannel::default(),
+            finish_sensor_event_channel: ColliderEventChannel::default(),
+            input: Input::default(),
+        }
+    }
+
+    /// Rebuild everything the provided config decides. This has to be done before the level is initialized.
+    #[cfg(feature = "custom_level")]
+    pub fn reconfigure(&mut self, config: &PlayerLevelConfig) {
+        self.physics = PhysicsWorld::from_config(&config.physics);
+        self.player = Player::from_config(&config.player);
+        self.ghost_recorder = GhostRecorder::new(
+            GhostKind::Player,
+            config.ghost_sample_rate,
+            config.max_ghost_seconds,
+            1,
+        );
+        self.timestep = FixedTimestep::new(config.tick_rate);
+    }
+
+    pub fn player_gltf_bytes(&self) -> &[u8] {
+        self.player_gltf_bytes
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Where the player's body was as of the previous tick.
+    pub fn previous_player_body_isometry(&self) -> Isometry3<f32> {
+        self.previous_player_body_isometry
+    }
+
+    pub fn send_level_event(&self, evt: PlayerLevelEvent) {
+        if self.headless {
+            return;
+        }
+        match serde_json::to_string(&evt) {
+            Ok(level_event) => unsafe { level_event_hook(level_event.as_ptr(), level_event.len()) },
+            Err(json_err) => warn!("Couldn't serialize level event {:?}: {}", evt, json_err),
+        }
+    }
+
+    /// Everything the level expects to find in its glTF.
+    pub fn requirements(&self, content: &impl PlayerLevelContent<'a>) -> LevelRequirements {
+        let mut requirements = LevelRequirements {
+            pois: vec![String::from(content.spawn_poi())],
+            sensors: vec![String::from(content.finish_sensor())],
+            rigid_bodies: Vec::new(),
+        };
+        content.add_requirements(&mut requirements);
+        requirements
+    }
+
+    /// Load the level's glTF and set the player and the content up in it.
+    pub fn initialize(
+        &mut self,
+        content: &mut impl PlayerLevelContent<'a>,
+    ) -> Result<(), LevelInitError> {
+        if self.initialized {
+            return Err(LevelInitError::AlreadyInitialized);
+        }
+
+        // Read the player's glTF before anything is loaded into the
+        // physics world, so a bad one doesn't leave the level half loaded
+        let player_gltf = Gltf::from_slice(self.player_gltf_bytes)
+            .map_err(|gltf_err| LevelInitError::InvalidGltf(gltf_err.to_string()))?;
+
+        // Load static colliders using trimeshes extracted from geometries
+        // within a glTF. This lets you create a level using your favorite 3D
+        // modeling tool.
+        let scene_gltf = self.requirements(content).load_gltf(
+            content.scene_gltf_bytes(),
+            &mut self.physics,
+            &mut self.pois,
+        )?;
+
+        // Both of these were just checked for
+        self.spawn_iso = self.pois[content.spawn_poi()];
+        self.checkpoint_iso = self.spawn_iso;
+        let finish_sensor_handle = self.physics.named_sensors[content.finish_sensor()];
+
+        self.animation_manager
+            .extend(AnimationManager::import_from_gltf(&scene_gltf));
+
+        self.player.initialize(
+            &content.config().player,
+            &player_gltf,
+            &mut self.physics,
+            Some(self.spawn_iso),
+            Some(String::from("PLAYER")),
+        );
+        self.player
+            .controller
+            .set_water_volumes(WaterVolumes::from_gltf(&scene_gltf, &self.physics));
+        self.player
+            .controller
+            .set_climbable_surfaces(ClimbableSurfaces::from_gltf(&scene_gltf, &self.physics));
+        self.player.grapple.find_targets(&scene_gltf, &self.physics);
+
+        self.physics.listen_to_collider(
+            finish_sensor_handle,
+            ColliderEventRelayer::from(self.finish_sensor_event_channel.clone_sender()),
+        );
+
+        self.physics.listen_to_collider(
+            self.player.controller.collider_handle(),
+            ColliderEventRelayer::from(self.player_event_channel.clone_sender()),
+        );
+
+        content.initialize(self);
+
+        self.loop_music(content);
+        self.ghost_recorder.start();
+        self.initialized = true;
+        Ok(())
+    }
+
+    fn loop_music(&mut self, content: &impl PlayerLevelContent<'a>) {
+        if let Some((music, music_volume)) = content.music() {
+            self.time_scale
+                .loop_audio(self.player.scene_object_name(), music, 1.0, music_volume);
+        }
+    }
+
+    fn stop_music(&mut self, content: &impl PlayerLevelContent<'a>) {
+        if let Some((music, _)) = content.music() {
+            self.time_scale
+                .stop_audio(self.player.scene_object_name(), music);
+        }
+    }
+
+    /// Put everything back the way it was when the level started,
+    /// reusing what's already been loaded into the physics world.
+    pub fn restart(&mut self, content: &mut impl PlayerLevelContent<'a>) -> Result<(), FfiError> {
+        if !self.initialized {
+            return Err(FfiError::invalid_state(
+                "Level can't be restarted before it's initialized",
+            ));
+        }
+        self.player.respawn(&mut self.physics, self.spawn_iso);
+        self.checkpoint_iso = self.spawn_iso;
+        self.level_completed = false;
+        content.restart(self);
+
+        // Anything that happened before the restart no longer matters
+        while self.player.get_event().is_ok() {}
+        while self.player_event_channel.get_message().is_ok() {}
+        while self.finish_sensor_event_channel.get_message().is_ok() {}
+        self.input.wipe();
+        self.timestep.reset();
+        self.previous_rigid_body_isometries
+            .capture(&self.physics.rigid_body_set);
+        self.previous_player_body_isometry = self.spawn_iso;
+        self.previous_camera_isometry = self.player.controller.camera_isometry();
+
+        self.input_log = InputLog::default();
+        self.input_log_bytes.clear();
+        self.ghost_recorder.start();
+        if let Some(ghost_playback) = &mut self.ghost_playback {
+            ghost_playback.restart();
+        }
+
+        let player_name = self.player.scene_object_name();
+        for audio_name in ["WALLRUN", "SLIDE", "LEVEL_VICTORY"] {
+            self.time_scale.stop_audio(player_name, audio_name);
+        }
+        self.stop_music(content);
+        self.loop_music(content);
+        Ok(())
+    }
+
+    /// Stop recording the player's run and keep it around for the interface to save.
+    pub fn finish_ghost_recording(&mut self) {
+        if self.ghost_recorder.is_recording() {
+            self.ghost_recording_bytes = self.ghost_recorder.stop().to_bytes();
+        }
+    }
+
+    /// Keep the completed run's input log around for the interface to save.
+    fn finish_input_log(&mut self) {
+        match self.input_log.try_to_toml() {
+            Ok(input_log_toml) => self.input_log_bytes = input_log_toml.into_bytes(),
+            Err(input_log_err) => warn!("Couldn't save input log: {}", input_log_err),
+        }
+    }
+
+    /// Run this level, which hasn't been initialized yet, from scratch with the
+    /// provided input log and make sure it's completed at the claimed time.
+    pub fn verify_input_log(
+        &mut self,
+        content: &mut impl PlayerLevelContent<'a>,
+        input_log: &InputLog,
+        claimed_completion_seconds: f32,
+    ) -> Result<(), String> {
+        self.make_headless();
+        self.initialize(content)
+            .map_err(|init_err| init_err.to_string())?;
+        let tick_seconds = self.timestep.tick_seconds();
+        input_log.verify_completion(tick_seconds, claimed_completion_seconds, |frame| {
+            self.settings = frame.settings;
+            self.input = frame.input;
+            self.tick(content, frame.delta_seconds);
+            ReplayedFrame {
+                level_completed: self.level_completed,
+                state_checksum: self.state_checksum,
+            }
+        })
+    }
+
+    /// Keep the interface from hearing about, playing, or animating anything the sim does.
+    /// This has to be done before the sim is initialized.
+    fn make_headless(&mut self) {
+        self.headless = true;
+        self.time_scale.set_headless(true);
+        self.player.set_headless(true);
+    }
+
+    /// Hash everything that decides how the level plays out from here on.
+    fn compute_state_checksum(&self, content: &impl PlayerLevelContent<'a>) -> u32 {
+        let mut checksum = StateChecksum::default();
+        checksum.write_rigid_bodies(&self.physics.rigid_body_set);
+        self.player.hash_state(&mut checksum);
+        content.hash_state(&mut checksum);
+        checksum.value()
+    }
+
+    /// Have the player's and the ghost's animations play as fast as simulated time passes.
+    fn apply_playback_rate(&mut self) {
+        let playback_rate = self.time_scale.playback_rate();
+        self.player.set_time_scale(playback_rate);
+        if let Some(ghost_movement_state) = self.ghost_movement_state {
+            loop_animation("GHOST", ghost_movement_state.as_ref(), playback_rate);
+        }
+    }
+
+    /// Make simulated time pass at the provided rate relative to real time.
+    pub fn set_time_scale(&mut self, new_time_scale: f32) {
+        // Animations and looping audio start over when given a new rate, so only do it if needed
+        if self.time_scale.set_scale(new_time_scale) {
+            self.apply_playback_rate();
+        }
+    }
+
+    /// Stop simulated time from passing, no matter how the simulation is stepped.
+    pub fn pause(&mut self) {
+        self.time_scale.pause();
+        self.apply_playback_rate();
+    }
+
+    /// Let simulated time pass again. Looping audio starts over from the beginning.
+    pub fn resume(&mut self) {
+        self.time_scale.resume();
+        self.apply_playback_rate();
+    }
+
+    fn update_ghost(&mut self, delta_seconds: f32) {
+        self.ghost_recorder.update(
+            &[*self.player.body_isometry()],
+            u8::from(self.player.movement_state()),
+            delta_seconds,
+        );
+
+        if let Some(ghost_playback) = &mut self.ghost_playback {
+            ghost_playback.advance(delta_seconds);
+            // Animate the ghost the same way the player is animated
+            let movement_state = MovementState::try_from(ghost_playback.animation_code()).ok();
+            if movement_state != self.ghost_movement_state {
+                if let Some(previous_movement_state) = self.ghost_movement_state {
+                    stop_animation("GHOST", previous_movement_state.as_ref());
+                }
+                if let Some(movement_state) = movement_state {
+                    loop_animation(
+                        "GHOST",
+                        movement_state.as_ref(),
+                        self.time_scale.playback_rate(),
+                    );
+                }
+                self.ghost_movement_state = movement_state;
+            }
+        }
+    }
+
+    /// Step the game simulation by the provided number of seconds. The
+    /// simulation ticks at a fixed rate, so a step may run any number of ticks.
+    pub fn step(
+        &mut self,
+        content: &mut impl PlayerLevelContent<'a>,
+        delta_seconds: f32,
+    ) -> Result<(), FfiError> {
+        if !self.initialized {
+            return Err(FfiError::invalid_state(
+                "Level can't be stepped before it's initialized",
+            ));
+        }
+        let tick_count = self
+            .timestep
+            .accumulate(self.time_scale.scaled_delta_seconds(delta_seconds));
+        for _ in 0..tick_count {
+            self.tick(content, self.timestep.tick_seconds());
+            // Looking only happens once however many ticks the step runs
+            self.input.consume_look();
+        }
+        // Input is held onto until a tick has gotten to use it
+        if tick_count > 0 {
+            self.input.wipe();
+        }
+        Ok(())
+    }
+
+    /// Tick the game simulation forward by the provided number of seconds.
+    pub fn tick(&mut self, content: &mut impl PlayerLevelContent<'a>, delta_seconds: f32) {
+        // There's nothing to simulate without a level
+        if !self.initialized {
+            return;
+        }
+
+        // Keep where everything was so the interface can interpolate from it
+        self.previous_rigid_body_isometries
+            .capture(&self.physics.rigid_body_set);
+        self.previous_player_body_isometry = *self.player.body_isometry();
+        self.previous_camera_isometry = self.player.controller.camera_isometry();
+
+        // The frame the level is completed on is the last one logged
+        let logging_frame = !self.level_completed;
+        let frame_input = self.input;
+
+        self.animation_manager.update(delta_seconds);
+
+        self.player.update(
+            &content.config().player,
+            &self.settings,
+            &self.input,
+            &mut self.physics,
+            delta_seconds,
+        );
+        content.update(self, delta_seconds);
+
+        self.physics.step(delta_seconds);
+
+        // How long the player will have been playing once this tick is logged
+        let run_seconds = self.input_log.elapsed_seconds() + delta_seconds;
+        content.handle_events(self, run_seconds);
+        self.finish_game_on_finish_sensor_detection(content, run_seconds);
+        self.reset_player_on_out_of_bounds(run_seconds);
+        self.relay_character_events_to_interface();
+        self.update_ghost(delta_seconds);
+
+        self.state_checksum = self.compute_state_checksum(content);
+        if logging_frame {
+            self.input_log.record(
+                delta_seconds,
+                self.settings,
+                frame_input,
+                self.state_checksum,
+            );
+            if self.level_completed {
+                self.finish_input_log();
+            }
+        }
+    }
+
+    /// Whether the provided collider is part of the player's body.
+    pub fn is_player_collider(&self, collider_handle: ColliderHandle) -> bool {
+        self.physics
+            .collider_set
+            .get(collider_handle)
+            .and_then(|collider| collider.parent())
+            .filter(|body_handle| *body_handle == self.player.controller.body_handle())
+            .is_some()
+    }
+
+    fn finish_game_on_finish_sensor_detection(
+        &mut self,
+        content: &impl PlayerLevelContent<'a>,
+        run_seconds: f32,
+    ) {
+        while let Ok(finish_sensor_event) = self.finish_sensor_event_channel.get_message() {
+            if let ColliderEvent::IntersectionStart(other) = finish_sensor_event {
+                if self.is_player_collider(other) && !self.level_completed {
+                    self.send_level_event(PlayerLevelEvent::LevelCompleted {
+                        completion_seconds: run_seconds,
+                    });
+                    self.finish_ghost_recording();
+                    self.stop_music(content);
+                    self.time_scale.play_audio(
+                        self.player.scene_object_name(),
+                        "LEVEL_VICTORY",
+                        1.0,
+                        0.5,
+                    );
+                    self.level_completed = true;
+                }
+            }
+        }
+    }
+
+    fn reset_player_on_out_of_bounds(&mut self, run_seconds: f32) {
+        while let Ok(player_collider_event) = self.player_event_channel.get_message() {
+            if let ColliderEvent::IntersectionStart(other) = player_collider_event {
+                let is_out_of_bounds = self
+                    .physics
+                    .named_sensors
+                    .name_of_handle(&other)
+                    .map(|sensor_name| Descriptor::from_name(sensor_name).has_tag("oob"))
+                    .unwrap_or(false);
+                if is_out_of_bounds && !self.level_completed {
+                    self.player.respawn(&mut self.physics, self.checkpoint_iso);
+                    self.time_scale.play_audio(
+                        self.player.scene_object_name(),
+                        "PLAYER_RESET",
+                        1.0,
+                        0.3,
+                    );
+                    self.send_level_event(PlayerLevelEvent::PlayerReset {
+                        reason: ResetReason::OutOfBounds,
+                        position: self.checkpoint_iso.translation.vector.into(),
+                        run_seconds,
+                    });
+                }
+            }
+        }
+    }
+
+    fn relay_character_events_to_interface(&mut self) {
+        while let Ok(player_event) = self.player.get_event() {
+            if !self.headless {
+                player_event.send_to_interface();
+            }
+            match player_event {
+                CharacterControllerEvent::Stepped => {
+                    self.time_scale
+                        .play_audio(self.player.scene_object_name(), "STEP", 1.0, 1.0)
+                }
+                CharacterControllerEvent::Jump | CharacterControllerEvent::AirJumped { .. } => self
+                    .time_scale
+                    .play_audio(self.player.scene_object_name(), "JUMP", 1.0, 1.0),
+                CharacterControllerEvent::StartedWallRunning { .. } => {
+                    self.time_scale
+                        .loop_audio(self.player.scene_object_name(), "WALLRUN", 1.0, 1.0)
+                }
+                CharacterControllerEvent::StoppedWallRunning => self
+                    .time_scale
+                    .stop_audio(self.player.scene_object_name(), "WALLRUN"),
+                CharacterControllerEvent::StartedSliding { .. } => {
+                    self.time_scale
+                        .loop_audio(self.player.scene_object_name(), "SLIDE", 1.0, 1.0)
+                }
+                CharacterControllerEvent::StoppedSliding => self
+                    .time_scale
+                    .stop_audio(self.player.scene_object_name(), "SLIDE"),
+                _ => {}
+            };
+        }
+    }
+
+    fn prop_body_handle(&self, prop_name: &str) -> Result<RigidBodyHandle, FfiError> {
+        self.physics
+            .named_rigid_bodies
+            .handle_with_name(prop_name)
+            .copied()
+            .ok_or_else(|| FfiError::not_found(format!("No prop is named \"{}\"", prop_name)))
+    }
+
+    pub fn find_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        let prop_body_handle = self.prop_body_handle(prop_name)?;
+        self.physics
+            .rigid_body_set
+            .get(prop_body_handle)
+            .map(|prop_body| *prop_body.position())
+            .ok_or_else(|| {
+                FfiError::not_found(format!("Prop \"{}\" isn't in the physics world", prop_name))
+            })
+    }
+
+    pub fn find_previous_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        let prop_body_handle = self.prop_body_handle(prop_name)?;
+        self.previous_rigid_body_isometries
+            .isometry(prop_body_handle)
+            .copied()
+            .ok_or_else(|| {
+                FfiError::not_found(format!(
+                    "Prop \"{}\" wasn't in the physics world last tick",
+                    prop_name
+                ))
+            })
+    }
+
+    pub fn find_poi(&self, poi_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.pois.point_with_name(poi_name).copied().ok_or_else(|| {
+            FfiError::not_found(format!("No point of interest is named \"{}\"", poi_name))
+        })
+    }
+
+    pub fn ghost_isometry(&self, isometry_index: usize) -> Result<Isometry3<f32>, FfiError> {
+        self.ghost_playback
+            .as_ref()
+            .map(|ghost_playback| ghost_playback.isometry(isometry_index))
+            .ok_or_else(|| FfiError::invalid_state("No ghost is loaded"))
+    }
+
+    /// Make room for a recording of the provided length for the interface to copy into.
+    pub fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        self.ghost_import_bytes = vec![0; recording_len];
+        self.ghost_import_bytes.as_mut_ptr()
+    }
+
+    fn load_imported_ghost(&mut self) -> Result<(), FfiError> {
+        let ghost_playback =
+            GhostPlayback::from_bytes(&self.ghost_import_bytes, GhostKind::Player, 1).map_err(
+                |ghost_err| FfiError::invalid_input(format!("Couldn't load ghost: {}", ghost_err)),
+            )?;
+        self.ghost_playback = Some(ghost_playback);
+        Ok(())
+    }
+
+    /// Make room for an input log of the provided length for the interface to copy into.
+    pub fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        self.replay_import_bytes = vec![0; input_log_len];
+        self.replay_import_bytes.as_mut_ptr()
+    }
+
+    fn verify_imported_replay(
+        &self,
+        content: &impl PlayerLevelContent<'a>,
+        claimed_completion_seconds: f32,
+    ) -> Result<(), FfiError> {
+        String::from_utf8(self.replay_import_bytes.clone())
+            .map_err(|utf8_err| utf8_err.to_string())
+            .and_then(|input_log_toml| InputLog::try_from_toml(&input_log_toml))
+            .and_then(|input_log| {
+                let mut replay_content = content.replay_copy();
+                PlayerLevel::new(replay_content.config()).verify_input_log(
+                    &mut replay_content,
+                    &input_log,
+                    claimed_completion_seconds,
+                )
+            })
+            .map_err(|verification_err| {
+                FfiError::invalid_input(format!("Couldn't verify replay: {}", verification_err))
+            })
+    }
+}
+
+/// The simulation of a level played as the player character.
+#[derive(Serialize, Deserialize)]
+pub struct PlayerLevelSim<'a, C> {
+    version: (u8, u8, u8),
+    pub content: C,
+    #[serde(borrow)]
+    pub level: PlayerLevel<'a>,
+}
+
+impl<'a, C: PlayerLevelContent<'a>> PlayerLevelSim<'a, C> {
+    pub fn new(content: C) -> Self {
+        Self {
+            version: (0, 0, 0),
+            level: PlayerLevel::new(content.config()),
+            content,
+        }
+    }
+}
+
+// Builds hosting every level export their own FFI instead
+#[cfg(all(
+    any(feature = "level_1", feature = "custom_level"),
+    not(feature = "all_levels")
+))]
+#[ffi]
+impl<'a> Sim<'a> {
+    pub fn scene_gltf_bytes_ptr(&self) -> *const u8 {
+        self.content.scene_gltf_bytes().as_ptr()
+    }
+
+    pub fn scene_gltf_bytes_len(&self) -> usize {
+        self.content.scene_gltf_bytes().len()
+    }
+
+    pub fn player_gltf_bytes_ptr(&self) -> *const u8 {
+        self.level.player_gltf_bytes().as_ptr()
+    }
+
+    pub fn player_gltf_bytes_len(&self) -> usize {
+        self.level.player_gltf_bytes().len()
+    }
+
+    pub fn prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.level.find_prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.level.last_error, prop_result)
+    }
+
+    pub fn previous_prop_isometry(
+        &mut self,
+        prop_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let prop_result = str_from_ffi(prop_name)
+            .and_then(|prop_name| self.level.find_previous_prop_isometry(prop_name))
+            .and_then(|prop_isometry| write_to_slot(isometry_slot, prop_isometry));
+        report_status(&mut self.level.last_error, prop_result)
+    }
+
+    pub fn poi(
+        &mut self,
+        poi_name: *const CString,
+        isometry_slot: *mut Isometry3<f32>,
+    ) -> FfiStatus {
+        let poi_result = str_from_ffi(poi_name)
+            .and_then(|poi_name| self.level.find_poi(poi_name))
+            .and_then(|poi_isometry| write_to_slot(isometry_slot, poi_isometry));
+        report_status(&mut self.level.last_error, poi_result)
+    }
+
+    // Making this an FFI-only wrapper because if the WASM has a
+    // function "initialize" it's not obvious what type it's initializing.
+    pub fn initialize_sim(&mut self) -> FfiStatus {
+        let init_result = self
+            .level
+            .initialize(&mut self.content)
+            .map_err(FfiError::from);
+        report_status(&mut self.level.last_error, init_result)
+    }
+
+    pub fn is_initialized(&self) -> u8 {
+        self.level.is_initialized() as u8
+    }
+
+    /// Why the most recent call that didn't return an OK status failed.
+    pub fn last_error_ptr(&self) -> *const u8 {
+        self.level.last_error.as_ptr()
+    }
+
+    pub fn last_error_len(&self) -> usize {
+        self.level.last_error.len()
+    }
+
+    /// Make room for a glTF of the provided length for the interface to copy into.
+    pub fn gltf_validation_buffer(&mut self, gltf_len: usize) -> *mut u8 {
+        self.level.gltf_validation_bytes = vec![0; gltf_len];
+        self.level.gltf_validation_bytes.as_mut_ptr()
+    }
+
+    /// Check the glTF in the validation buffer has everything the level needs.
+    pub fn validate_gltf(&mut self) -> FfiStatus {
+        let validation_result = self
+            .level
+            .requirements(&self.content)
+            .validate_gltf(&self.level.gltf_validation_bytes)
+            .map_err(FfiError::from);
+        report_status(&mut self.level.last_error, validation_result)
+    }
+
+    pub fn restart_level(&mut self) -> FfiStatus {
+        let restart_result = self.level.restart(&mut self.content);
+        report_status(&mut self.level.last_error, restart_result)
+    }
+
+    pub fn desired_fps(&self) -> f32 {
+        self.content.config().tick_rate
+    }
+
+    /// How far (from 0 to 1) rendering is between the previous tick and the current one.
+    pub fn interpolation_alpha(&self) -> f32 {
+        self.level.timestep.alpha()
+    }
+
+    /// Step the game simulation by the provided number of seconds. The
+    /// simulation ticks at a fixed rate, so a step may run any number of ticks.
+    pub fn step(&mut self, delta_seconds: f32) -> FfiStatus {
+        let step_result = self.level.step(&mut self.content, delta_seconds);
+        report_status(&mut self.level.last_error, step_result)
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.level.time_scale.scale()
+    }
+
+    /// Make simulated time pass at the provided rate relative to real time.
+    pub fn set_time_scale(&mut self, new_time_scale: f32) {
+        self.level.set_time_scale(new_time_scale);
+    }
+
+    pub fn is_paused(&self) -> u8 {
+        self.level.time_scale.is_paused() as u8
+    }
+
+    // Naming these "pause" and "resume" would clash with libc's pause function.
+    /// Stop simulated time from passing, no matter how the simulation is stepped.
+    pub fn pause_sim(&mut self) {
+        self.level.pause();
+    }
+
+    /// Let simulated time pass again. Looping audio starts over from the beginning.
+    pub fn resume_sim(&mut self) {
+        self.level.resume();
+    }
+
+    pub fn settings_left_right_look_sensitivity(&self) -> u8 {
+        self.level.settings.left_right_look_sensitivity()
+    }
+
+    pub fn settings_up_down_look_sensitivity(&self) -> u8 {
+        self.level.settings.up_down_look_sensitivity()
+    }
+
+    pub fn settings_set_left_right_look_sensitivity(&mut self, new_sensitivity: i32) {
+        self.level
+            .settings
+            .set_left_right_look_sensitivity(new_sensitivity as u8);
+    }
+
+    pub fn settings_set_up_down_look_sensitivity(&mut self, new_sensitivity: i32) {
+        self.level
+            .settings
+            .set_up_down_look_sensitivity(new_sensitivity as u8);
+    }
+
+    pub fn input_set_move_forward(&mut self, new_magnitude: f32) {
+        self.level.input.set_move_forward(new_magnitude);
+    }
+
+    pub fn input_set_move_right(&mut self, new_magnitude: f32) {
+        self.level.input.set_move_right(new_magnitude);
+    }
+
+    /// How far to look up since the last frame. This adds up until a tick uses it.
+    pub fn input_set_rotate_up(&mut self, new_magnitude: f32) {
+        self.level.input.add_rotate_up(new_magnitude);
+    }
+
+    /// How far to turn right since the last frame. This adds up until a tick uses it.
+    pub fn input_set_rotate_right(&mut self, new_magnitude: f32) {
+        self.level.input.add_rotate_right(new_magnitude);
+    }
+
+    pub fn input_set_jump(&mut self, jump_val: u8) {
+        self.level.input.set_jump(jump_val > 0)
+    }
+
+    pub fn input_set_aim(&mut self, aim_val: u8) {
+        self.level.input.set_aim(aim_val > 0)
+    }
+
+    pub fn input_set_dash(&mut self, dash_val: u8) {
+        self.level.input.set_dash(dash_val > 0)
+    }
+
+    pub fn input_set_grapple(&mut self, grapple_val: u8) {
+        self.level.input.set_grapple(grapple_val > 0)
+    }
+
+    pub fn input_set_reel(&mut self, new_magnitude: f32) {
+        self.level.input.set_reel(new_magnitude);
+    }
+
+    #[slot_return]
+    pub fn camera_global_isometry(&self) -> Isometry3<f32> {
+        // The player's head position
+        self.level.player.controller.camera_isometry()
+    }
+
+    #[slot_return]
+    pub fn previous_camera_global_isometry(&self) -> Isometry3<f32> {
+        self.level.previous_camera_isometry
+    }
+
+    #[slot_return]
+    pub fn player_body_isometry(&self) -> Isometry3<f32> {
+        *self.level.player.body_isometry()
+    }
+
+    #[slot_return]
+    pub fn previous_player_body_isometry(&self) -> Isometry3<f32> {
+        self.level.previous_player_body_isometry()
+    }
+
+    pub fn stop_ghost_recording(&mut self) {
+        self.level.finish_ghost_recording();
+    }
+
+    pub fn ghost_recording_ptr(&self) -> *const u8 {
+        self.level.ghost_recording_bytes.as_ptr()
+    }
+
+    pub fn ghost_recording_len(&self) -> usize {
+        self.level.ghost_recording_bytes.len()
+    }
+
+    /// Make room for a recording of the provided length for the interface to copy into.
+    pub fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        self.level.ghost_import_buffer(recording_len)
+    }
+
+    /// Play back the recording in the import buffer alongside the player.
+    pub fn load_ghost(&mut self) -> FfiStatus {
+        let ghost_result = self.level.load_imported_ghost();
+        report_status(&mut self.level.last_error, ghost_result)
+    }
+
+    pub fn has_ghost(&self) -> u8 {
+        self.level.ghost_playback.is_some() as u8
+    }
+
+    pub fn ghost_body_isometry(&mut self, isometry_slot: *mut Isometry3<f32>) -> FfiStatus {
+        let ghost_result = self
+            .level
+            .ghost_isometry(0)
+            .and_then(|ghost_isometry| write_to_slot(isometry_slot, ghost_isometry));
+        report_status(&mut self.level.last_error, ghost_result)
+    }
+
+    /// How many seconds the player has been playing, which is
+    /// their completion time once the level is completed.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.level.input_log.elapsed_seconds()
+    }
+
+    pub fn state_checksum(&self) -> u32 {
+        self.level.state_checksum
+    }
+
+    pub fn input_log_ptr(&self) -> *const u8 {
+        self.level.input_log_bytes.as_ptr()
+    }
+
+    pub fn input_log_len(&self) -> usize {
+        self.level.input_log_bytes.len()
+    }
+
+    /// Make room for an input log of the provided length for the interface to copy into.
+    pub fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        self.level.replay_import_buffer(input_log_len)
+    }
+
+    /// Re-run the level with the input log in the import buffer
+    /// and make sure it's completed at the claimed time.
+    pub fn verify_replay(&mut self, claimed_completion_seconds: f32) -> FfiStatus {
+        let verification = self
+            .level
+            .verify_imported_replay(&self.content, claimed_completion_seconds);
+        report_status(&mut self.level.last_error, verification)
+    }
+}
+
+impl PlayerCharacter for PlayerLevel<'_> {
+    fn player_gltf_bytes(&self) -> &[u8] {
+        self.player_gltf_bytes
+    }
+
+    fn body_isometry(&self) -> Isometry3<f32> {
+        *self.player.body_isometry()
+    }
+
+    fn previous_body_isometry(&self) -> Isometry3<f32> {
+        self.previous_player_body_isometry
+    }
+
+    fn ghost_body_isometry(&self) -> Result<Isometry3<f32>, FfiError> {
+        self.ghost_isometry(0)
+    }
+}
+
+impl<C: PlayerLevelContent<'static> + 'static> Level for PlayerLevelSim<'static, C> {
+    fn scene_gltf_bytes(&self) -> &[u8] {
+        self.content.scene_gltf_bytes()
+    }
+
+    fn requirements(&self) -> LevelRequirements {
+        self.level.requirements(&self.content)
+    }
+
+    fn initialize(&mut self) -> Result<(), LevelInitError> {
+        self.level.initialize(&mut self.content)
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.level.is_initialized()
+    }
+
+    fn restart(&mut self) -> Result<(), FfiError> {
+        self.level.restart(&mut self.content)
+    }
+
+    fn step(&mut self, delta_seconds: f32) -> Result<(), FfiError> {
+        self.level.step(&mut self.content, delta_seconds)
+    }
+
+    fn desired_fps(&self) -> f32 {
+        self.content.config().tick_rate
+    }
+
+    fn interpolation_alpha(&self) -> f32 {
+        self.level.timestep.alpha()
+    }
+
+    fn time_scale(&self) -> f32 {
+        self.level.time_scale.scale()
+    }
+
+    fn set_time_scale(&mut self, new_time_scale: f32) {
+        self.level.set_time_scale(new_time_scale);
+    }
+
+    fn is_paused(&self) -> bool {
+        self.level.time_scale.is_paused()
+    }
+
+    fn pause(&mut self) {
+        self.level.pause();
+    }
+
+    fn resume(&mut self) {
+        self.level.resume();
+    }
+
+    fn settings(&self) -> &GameSettings {
+        &self.level.settings
+    }
+
+    fn settings_mut(&mut self) -> &mut GameSettings {
+        &mut self.level.settings
+    }
+
+    fn input_mut(&mut self) -> &mut Input {
+        &mut self.level.input
+    }
+
+    fn prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.level.find_prop_isometry(prop_name)
+    }
+
+    fn previous_prop_isometry(&self, prop_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.level.find_previous_prop_isometry(prop_name)
+    }
+
+    fn poi(&self, poi_name: &str) -> Result<Isometry3<f32>, FfiError> {
+        self.level.find_poi(poi_name)
+    }
+
+    fn camera_global_isometry(&self) -> Isometry3<f32> {
+        self.level.player.controller.camera_isometry()
+    }
+
+    fn previous_camera_global_isometry(&self) -> Isometry3<f32> {
+        self.level.previous_camera_isometry
+    }
+
+    fn stop_ghost_recording(&mut self) {
+        self.level.finish_ghost_recording();
+    }
+
+    fn ghost_recording_bytes(&self) -> &[u8] {
+        &self.level.ghost_recording_bytes
+    }
+
+    fn ghost_import_buffer(&mut self, recording_len: usize) -> *mut u8 {
+        self.level.ghost_import_buffer(recording_len)
+    }
+
+    fn load_ghost(&mut self) -> Result<(), FfiError> {
+        self.level.load_imported_ghost()
+    }
+
+    fn has_ghost(&self) -> bool {
+        self.level.ghost_playback.is_some()
+    }
+
+    fn elapsed_seconds(&self) -> f32 {
+        self.level.input_log.elapsed_seconds()
+    }
+
+    fn state_checksum(&self) -> u32 {
+        self.level.state_checksum
+    }
+
+    fn input_log_bytes(&self) -> &[u8] {
+        &self.level.input_log_bytes
+    }
+
+    fn replay_import_buffer(&mut self, input_log_len: usize) -> *mut u8 {
+        self.level.replay_import_buffer(input_log_len)
+    }
+
+    fn verify_replay(&self, claimed_completion_seconds: f32) -> Result<(), FfiError> {
+        self.level
+            .verify_imported_replay(&self.content, claimed_completion_seconds)
+    }
+
+    fn player(&self) -> Option<&dyn PlayerCharacter> {
+        Some(&self.level)
+    }
+
+    fn level_import_mut(&mut self) -> Option<&mut dyn LevelImport> {
+        C::level_import_mut(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::shared::{ffi_status::FfiStatus, vectors::FORWARD_VECTOR};
+
+    /// A level with nothing in it but the player, so tests don't need any level's glTF.
+    struct EmptyLevel {
+        config: PlayerLevelConfig,
+    }
+
+    impl PlayerLevelContent<'static> for EmptyLevel {
+        fn config(&self) -> &PlayerLevelConfig {
+            &self.config
+        }
+
+        fn scene_gltf_bytes(&self) -> &[u8] {
+            &[]
+        }
+
+        fn spawn_poi(&self) -> &str {
+            "Player Start"
+        }
+
+        fn finish_sensor(&self) -> &str {
+            "Finish Sensor"
+        }
+
+        fn music(&self) -> Option<(&str, f32)> {
+            None
+        }
+
+        fn replay_copy(&self) -> Self {
+            Self {
+                config: self.config.clone(),
+            }
+        }
+    }
+
+    /// A headless level with the player standing on an endless floor.
+    fn headless_level(content: &EmptyLevel) -> PlayerLevel<'static> {
+        let mut level = PlayerLevel::new(content.config());
+        level.make_headless();
+        level.physics.collider_set.insert(
+            ColliderBuilder::cuboid(500.0, 0.5, 500.0)
+                .translation(Vector3::new(0.0, -0.5, 0.0))
+                .build(),
+        );
+        level.player.initialize(
+            &content.config().player,
+            &Gltf::from_slice(br#"{"asset":{"version":"2.0"}}"#).unwrap(),
+            &mut level.physics,
+            Some(Isometry::translation(0.0, 1.0, 0.0)),
+            Some(String::from("PLAYER")),
+        );
+        level.initialized = true;
+        level
+    }
+
+    #[test]
+    fn rejects_an_unreadable_player_gltf() {
+        let mut content = EmptyLevel {
+            config: PlayerLevelConfig::default(),
+        };
+        let mut level = PlayerLevel::new(content.config());
+        level.player_gltf_bytes = b"not a glTF";
+
+        assert!(matches!(
+            level.initialize(&mut content),
+            Err(LevelInitError::InvalidGltf(_))
+        ));
+        assert!(!level.is_initialized());
+        assert_eq!(level.physics.collider_set.len(), 0);
+    }
+
+    #[test]
+    fn only_steps_and_initializes_when_it_makes_sense() {
+        let mut content = EmptyLevel {
+            config: PlayerLevelConfig::default(),
+        };
+        let mut level = PlayerLevel::new(content.config());
+        assert_eq!(
+            level.step(&mut content, 0.1).unwrap_err().status,
+            FfiStatus::InvalidState
+        );
+
+        let mut level = headless_level(&content);
+        let collider_count = level.physics.collider_set.len();
+        let init_err = level.initialize(&mut content).unwrap_err();
+        assert_eq!(init_err, LevelInitError::AlreadyInitialized);
+        assert_eq!(FfiError::from(init_err).status, FfiStatus::InvalidState);
+        // Nothing was loaded a second time
+        assert_eq!(level.physics.collider_set.len(), collider_count);
+        assert!(level.step(&mut content, 0.1).is_ok());
+    }
+
+    /// Which way the camera faces after turning steadily for a second
+    /// with the interface drawing at the provided frame rate.
+    fn camera_yaw_after_turning(turn_per_second: f32, frame_rate: u32) -> f32 {
+        let mut content = EmptyLevel {
+            config: PlayerLevelConfig::default(),
+        };
+        let mut level = headless_level(&content);
+
+        let frame_seconds = 1.0 / frame_rate as f32;
+        for _ in 0..frame_rate {
+            level
+                .input
+                .add_rotate_right(turn_per_second * frame_seconds);
+            level.step(&mut content, frame_seconds).unwrap();
+        }
+        // Let a tick use whatever turning the last frames left behind
+        level.step(&mut content, 0.1).unwrap();
+
+        let camera_forward = level
+            .player
+            .controller
+            .camera_isometry()
+            .rotation
+            .transform_vector(&FORWARD_VECTOR);
+        camera_forward.x.atan2(-camera_forward.z)
+    }
+
+    #[test]
+    fn turns_as_far_at_any_frame_rate() {
+        let standing_yaw = camera_yaw_after_turning(0.0, 30);
+        let low_frame_rate_yaw = camera_yaw_after_turning(20.0, 30);
+        let high_frame_rate_yaw = camera_yaw_after_turning(20.0, 144);
+
+        assert!((low_frame_rate_yaw - standing_yaw).abs() > 0.5);
+        assert!((low_frame_rate_yaw - high_frame_rate_yaw).abs() < 1e-3);
+    }
+}
@@ -1,5 +1,7 @@
 mod player;
 mod sedan;
 
-pub use player::Player;
+#[cfg(any(feature = "level_1", feature = "custom_level"))]
+pub use player::{MovementState, Player};
+#[cfg(feature = "level_2")]
 pub use sedan::Sedan;
@@ -1,15 +1,19 @@
 use perigee::prelude::*;
 
 use crate::config::player::PlayerConfig;
-use crate::shared::controllers::character::utils::{CharacterPerspectiveMode, CrouchState};
-use crate::shared::controllers::CharacterController;
+use crate::shared::checksum::StateChecksum;
+use crate::shared::controllers::character::utils::{
+    CharacterPerspectiveMode, CrouchState, LedgeState, SwimmingState, WallClimbing,
+};
+use crate::shared::controllers::{CharacterController, GrappleController};
 use crate::shared::descriptor::Descriptor;
 use crate::shared::events::CharacterControllerEvent;
 use crate::shared::input::Input;
 use crate::shared::settings::GameSettings;
 use crate::shared::vectors::*;
 use serde::{Deserialize, Serialize};
-use utils::{MovementState, WalkDirection};
+pub use utils::MovementState;
+use utils::{ClimbDirection, WalkDirection};
 
 mod utils;
 
@@ -17,11 +21,19 @@ mod utils;
 pub struct Player<'a> {
     pub descriptor: Descriptor<'a>,
     pub controller: CharacterController,
+    pub grapple: GrappleController,
     movement_state: StateMachine<MovementState>,
+    /// How fast the player's animations play relative to real time
+    time_scale: f32,
+    /// Whether the player is left unanimated so the interface never hears about it
+    #[serde(skip)]
+    headless: bool,
     #[serde(skip)]
     event_channel: EventChannel<CharacterControllerEvent>,
     #[serde(skip)]
     animation_manager: AnimationManager,
+    #[serde(skip)]
+    animation_names: Vec<String>,
 }
 
 impl<'a> FromConfig for Player<'a> {
@@ -30,11 +42,15 @@ impl<'a> FromConfig for Player<'a> {
     fn from_config<'b>(config: Self::Config<'b>) -> Self {
         Self {
             controller: CharacterController::from_config(&config.character_controller),
+            grapple: GrappleController::default(),
             // [P]re-[C]onfigured [P]layer
             descriptor: Descriptor::from_name("PCP"),
             movement_state: StateMachine::new(MovementState::default()),
+            time_scale: 1.0,
+            headless: false,
             event_channel: EventChannel::with_capacity(config.event_queue_capacity),
             animation_manager: AnimationManager::default(),
+            animation_names: Vec::new(),
         }
     }
 }
@@ -62,7 +78,15 @@ impl<'a> Player<'a> {
             .named_rigid_bodies
             .insert(self.descriptor.as_ref(), self.controller.body_handle());
 
+        // Nothing about a headless player's animations reaches the interface
+        if self.headless {
+            return;
+        }
         let animation_manager = AnimationManager::import_from_gltf(gltf);
+        self.animation_names.extend(
+            gltf.animations()
+                .filter_map(|animation| animation.name().map(String::from)),
+        );
         self.animation_manager.extend(animation_manager);
         let player_event_sender = self.event_channel.clone_sender();
         let on_run_step = move || {
@@ -103,15 +127,36 @@ impl<'a> Player<'a> {
             input,
             delta_seconds,
         );
+        if let Some(grapple_config) = &config.grapple {
+            // Ledges and ladders hold the body in place, so it can't swing from them
+            if self.controller.is_on_ledge() || self.controller.is_climbing() {
+                self.grapple.release(physics);
+            } else {
+                self.grapple.update(
+                    grapple_config,
+                    input,
+                    &self.controller.camera_isometry(),
+                    self.controller.body_handle(),
+                    physics,
+                    delta_seconds,
+                );
+            }
+        }
 
-        if !self.controller.is_grounded() {
-            self.nudge_in_air(
-                &config,
-                delta_seconds,
-                input.move_right(),
-                input.move_forward(),
-                &mut physics.rigid_body_set,
-            );
+        if !self.controller.is_grounded()
+            && !self.controller.is_on_ledge()
+            && !self.controller.is_climbing()
+        {
+            // Swimming has its own movement
+            if !self.controller.is_swimming() {
+                self.nudge_in_air(
+                    &config,
+                    delta_seconds,
+                    input.move_right(),
+                    input.move_forward(),
+                    &mut physics.rigid_body_set,
+                );
+            }
 
             if self
                 .controller
@@ -141,7 +186,50 @@ impl<'a> Player<'a> {
             );
         }
 
-        self.animation_manager.update(delta_seconds);
+        // Animations are sped up by the time scale for the interface's sake, so they're
+        // advanced by real time to keep them in step with simulated time
+        if self.time_scale > 0.0 {
+            self.animation_manager
+                .update(delta_seconds / self.time_scale);
+        }
+    }
+
+    /// Put the player at the provided isometry and bring them to a stop.
+    pub fn respawn(&mut self, physics: &mut PhysicsWorld, isometry: Isometry3<f32>) {
+        self.controller.let_go_of_ledge(&mut physics.rigid_body_set);
+        self.controller
+            .let_go_of_ladder(&mut physics.rigid_body_set);
+        self.grapple.release(physics);
+        if let Some(player_body) = physics
+            .rigid_body_set
+            .get_mut(self.controller.body_handle())
+        {
+            player_body.set_position(isometry, true);
+            player_body.set_linvel(Vector3::zeros(), true);
+            player_body.set_angvel(Vector3::zeros(), true);
+        }
+        self.controller.snap_to_body(&mut physics.rigid_body_set);
+    }
+
+    /// Keep the player unanimated, for sims the interface never shows.
+    /// This has to be done before the player is initialized.
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+    }
+
+    /// Play the player's animations at the provided rate relative to real time.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+        for animation_name in &self.animation_names {
+            if let Some(animation) = self.animation_manager.get_mut(animation_name) {
+                animation.time_scale = time_scale;
+            }
+        }
+        // Let the interface know how fast the current animation should now play
+        self.animation_manager.loop_animation(
+            self.movement_state.current_state().as_ref(),
+            Some(self.descriptor.object_name()),
+        );
     }
 
     pub fn scene_object_name(&self) -> &str {
@@ -152,10 +240,21 @@ impl<'a> Player<'a> {
         self.controller.body_isometry()
     }
 
+    pub fn movement_state(&self) -> MovementState {
+        *self.movement_state.current_state()
+    }
+
     pub fn get_event(&self) -> Result<CharacterControllerEvent, TryRecvError> {
         self.controller
             .get_event()
-            .or(self.event_channel.get_message())
+            .or_else(|_| self.grapple.get_event())
+            .or_else(|_| self.event_channel.get_message())
+    }
+
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        self.controller.hash_state(checksum);
+        self.grapple.hash_state(checksum);
+        checksum.write_state(&self.movement_state);
     }
 
     fn determine_movement_state(
@@ -166,6 +265,48 @@ impl<'a> Player<'a> {
         let linvel = self.controller.body_linear_velocity();
         let body_handle = self.controller.body_handle();
         if let Some(body) = rigid_body_set.get(body_handle) {
+            match self.controller.ledge_state.current_state() {
+                LedgeState::Hanging(_) => {
+                    self.movement_state.transition_to(MovementState::Hanging);
+                    return;
+                }
+                LedgeState::Mantling(_) => {
+                    self.movement_state.transition_to(MovementState::Mantling);
+                    return;
+                }
+                LedgeState::None => {}
+            }
+            if self.controller.wall_climb_state != WallClimbing::None {
+                self.movement_state
+                    .transition_to(MovementState::WallClimbing);
+                return;
+            }
+            if self.controller.is_climbing() {
+                let climb_speed_threshold =
+                    config.character_controller.nonstationary_speed_threshold;
+                let climb_dir = if linvel.y > climb_speed_threshold {
+                    ClimbDirection::Up
+                } else if linvel.y < -climb_speed_threshold {
+                    ClimbDirection::Down
+                } else {
+                    ClimbDirection::Still
+                };
+                self.movement_state
+                    .transition_to(MovementState::Climbing(climb_dir));
+                return;
+            }
+            match self.controller.swimming_state.current_state() {
+                SwimmingState::Surface(_) => {
+                    self.movement_state.transition_to(MovementState::Swimming);
+                    return;
+                }
+                SwimmingState::Underwater(_) => {
+                    self.movement_state
+                        .transition_to(MovementState::SwimmingUnderwater);
+                    return;
+                }
+                SwimmingState::None => {}
+            }
             if !self.controller.is_grounded() {
                 self.movement_state.transition_to(MovementState::InAir);
                 return;
@@ -60,6 +60,14 @@ impl WalkDirection {
     }
 }
 
+/// Which way the player is moving along a ladder.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClimbDirection {
+    Up,
+    Down,
+    Still,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy, Eq, Debug)]
 pub enum MovementState {
     Stationary(CrouchState),
@@ -68,6 +76,12 @@ pub enum MovementState {
     Running,
     Sprinting,
     InAir,
+    Hanging,
+    Mantling,
+    WallClimbing,
+    Swimming,
+    SwimmingUnderwater,
+    Climbing(ClimbDirection),
 }
 
 impl PartialEq for MovementState {
@@ -83,6 +97,14 @@ impl PartialEq for MovementState {
                 self_walk_dir == other_walk_dir
             }
             (Self::InAir, Self::InAir) => true,
+            (Self::Hanging, Self::Hanging) => true,
+            (Self::Mantling, Self::Mantling) => true,
+            (Self::WallClimbing, Self::WallClimbing) => true,
+            (Self::Swimming, Self::Swimming) => true,
+            (Self::SwimmingUnderwater, Self::SwimmingUnderwater) => true,
+            (Self::Climbing(self_climb_dir), Self::Climbing(other_climb_dir)) => {
+                self_climb_dir == other_climb_dir
+            }
             _ => false,
         }
     }
@@ -94,6 +116,79 @@ impl Default for MovementState {
     }
 }
 
+impl From<MovementState> for u8 {
+    fn from(movement_state: MovementState) -> Self {
+        match movement_state {
+            MovementState::Stationary(CrouchState::Upright) => 0,
+            MovementState::Stationary(CrouchState::Crouched) => 1,
+            MovementState::Creeping => 2,
+            MovementState::Walking(walk_dir) => {
+                3 + match walk_dir {
+                    WalkDirection::Forward => 0,
+                    WalkDirection::RightForward => 1,
+                    WalkDirection::Right => 2,
+                    WalkDirection::RightBack => 3,
+                    WalkDirection::Back => 4,
+                    WalkDirection::LeftBack => 5,
+                    WalkDirection::Left => 6,
+                    WalkDirection::LeftForward => 7,
+                }
+            }
+            MovementState::Running => 11,
+            MovementState::Sprinting => 12,
+            MovementState::InAir => 13,
+            MovementState::Hanging => 14,
+            MovementState::Mantling => 15,
+            MovementState::WallClimbing => 16,
+            MovementState::Swimming => 17,
+            MovementState::SwimmingUnderwater => 18,
+            MovementState::Climbing(climb_dir) => {
+                19 + match climb_dir {
+                    ClimbDirection::Up => 0,
+                    ClimbDirection::Down => 1,
+                    ClimbDirection::Still => 2,
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<u8> for MovementState {
+    type Error = String;
+    fn try_from(movement_state_code: u8) -> Result<Self, Self::Error> {
+        Ok(match movement_state_code {
+            0 => Self::Stationary(CrouchState::Upright),
+            1 => Self::Stationary(CrouchState::Crouched),
+            2 => Self::Creeping,
+            3 => Self::Walking(WalkDirection::Forward),
+            4 => Self::Walking(WalkDirection::RightForward),
+            5 => Self::Walking(WalkDirection::Right),
+            6 => Self::Walking(WalkDirection::RightBack),
+            7 => Self::Walking(WalkDirection::Back),
+            8 => Self::Walking(WalkDirection::LeftBack),
+            9 => Self::Walking(WalkDirection::Left),
+            10 => Self::Walking(WalkDirection::LeftForward),
+            11 => Self::Running,
+            12 => Self::Sprinting,
+            13 => Self::InAir,
+            14 => Self::Hanging,
+            15 => Self::Mantling,
+            16 => Self::WallClimbing,
+            17 => Self::Swimming,
+            18 => Self::SwimmingUnderwater,
+            19 => Self::Climbing(ClimbDirection::Up),
+            20 => Self::Climbing(ClimbDirection::Down),
+            21 => Self::Climbing(ClimbDirection::Still),
+            _ => {
+                return Err(format!(
+                    "Unrecognized movement state {}",
+                    movement_state_code
+                ))
+            }
+        })
+    }
+}
+
 impl AsRef<str> for MovementState {
     fn as_ref(&self) -> &str {
         match self {
@@ -115,6 +210,30 @@ impl AsRef<str> for MovementState {
             Self::Running => "RUN_FORWARD",
             Self::Sprinting => "SPRINT_FORWARD",
             Self::InAir => "IN_AIR",
+            Self::Hanging => "HANG",
+            Self::Mantling => "MANTLE",
+            Self::WallClimbing => "WALL_CLIMB",
+            Self::Swimming => "SWIM",
+            Self::SwimmingUnderwater => "SWIM_UNDERWATER",
+            Self::Climbing(climb_dir) => match climb_dir {
+                ClimbDirection::Up => "CLIMB_UP",
+                ClimbDirection::Down => "CLIMB_DOWN",
+                ClimbDirection::Still => "CLIMB_IDLE",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn movement_state_codes_round_trip() {
+        for movement_state_code in 0..=21 {
+            let movement_state = MovementState::try_from(movement_state_code).unwrap();
+            assert_eq!(u8::from(movement_state), movement_state_code);
         }
+        assert!(MovementState::try_from(22).is_err());
     }
 }
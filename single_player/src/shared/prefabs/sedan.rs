@@ -2,12 +2,14 @@ use perigee::prelude::*;
 
 use crate::config::{sedan::CameraMode, SedanConfig};
 use crate::shared::boom::Boom;
+use crate::shared::checksum::StateChecksum;
 use crate::shared::controllers::RaycastVehicleController;
 use crate::shared::descriptor::Descriptor;
 use crate::shared::input::Input;
 use crate::shared::settings::GameSettings;
 use perigee::rapier3d::na::Translation3;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 #[derive(Serialize, Deserialize)]
 struct FollowCamExtras {
@@ -15,6 +17,10 @@ struct FollowCamExtras {
     arm_end_translation: Vector3<f32>,
     arm_rotation: UnitQuaternion<f32>,
     lerp_factor: f32,
+    /// How fast the car is going relative to the reference speed, from 0 to 1
+    speed_factor: f32,
+    /// How far (in degrees) the arm is swung to look into a turn
+    turn_look_yaw_angle: f32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -25,19 +31,28 @@ pub struct Sedan<'a> {
     camera_mode: CameraMode,
     follow_cam_rig: FollowCamExtras,
     camera_iso: Isometry3<f32>,
+    camera_fov_kick: f32,
+    follow_cam_iso: Isometry3<f32>,
+    /// The cabin-relative camera isometry that a camera mode switch blends from
+    camera_blend_start_iso: Isometry3<f32>,
+    camera_blend_progress: f32,
+    cinematic_camera_isometries: Vec<Isometry3<f32>>,
+    /// How long it's been since the last impact, or `None` if there hasn't been one
+    /// since the car spawned
+    impact_cooldown_timer: Option<PassiveClock>,
+    /// How fast the car's sounds play relative to real time
+    time_scale: f32,
+    /// Whether the car's sounds are kept from the interface
+    #[serde(skip)]
+    headless: bool,
+    #[serde(skip)]
+    cabin_event_channel: ColliderEventChannel,
 }
 
 impl<'a> FromConfig for Sedan<'a> {
     type Config<'b> = &'b SedanConfig;
 
     fn from_config<'b>(config: Self::Config<'b>) -> Self {
-        let follow_cam_quat = UnitQuaternion::identity();
-        let follow_cam_quat = follow_cam_quat.append_axisangle_linearized(
-            &(Vector3::x() * config.track_mode_look_pitch_angle.to_radians()),
-        );
-        let follow_cam_quat = follow_cam_quat.append_axisangle_linearized(
-            &(Vector3::y() * config.track_mode_look_yaw_angle.to_radians()),
-        );
         Self {
             controller: RaycastVehicleController::from_config(&config.raycast_vehicle_controller),
             // [P]re-[C]onfigured [S]edan
@@ -52,10 +67,24 @@ impl<'a> FromConfig for Sedan<'a> {
             follow_cam_rig: FollowCamExtras {
                 pivot_rotation: UnitQuaternion::identity(),
                 arm_end_translation: Vector3::new(0.0, 0.0, config.max_boom_length).into(),
-                arm_rotation: follow_cam_quat,
+                arm_rotation: Self::follow_camera_arm_rotation(
+                    config.track_mode_look_pitch_angle,
+                    config.track_mode_look_yaw_angle,
+                ),
                 lerp_factor: config.track_mode_cam_lerp_factor,
+                speed_factor: 0.0,
+                turn_look_yaw_angle: 0.0,
             },
             camera_iso: Isometry::identity(),
+            camera_fov_kick: 0.0,
+            follow_cam_iso: Isometry::identity(),
+            camera_blend_start_iso: Isometry::identity(),
+            camera_blend_progress: 1.0,
+            cinematic_camera_isometries: Vec::new(),
+            impact_cooldown_timer: None,
+            time_scale: 1.0,
+            headless: false,
+            cabin_event_channel: ColliderEventChannel::default(),
         }
     }
 }
@@ -74,6 +103,10 @@ impl<'a> Sedan<'a> {
             &mut physics.collider_set,
             initial_isometry,
         );
+        physics.listen_to_collider(
+            self.controller.cabin_collider_handle(),
+            ColliderEventRelayer::from(self.cabin_event_channel.clone_sender()),
+        );
 
         if let Some(descriptor) = descriptor_string {
             self.descriptor = descriptor.into();
@@ -88,6 +121,62 @@ impl<'a> Sedan<'a> {
         self.camera_iso.clone()
     }
 
+    /// The isometry of the cabin followed by the isometry of each wheel.
+    pub fn isometries(&self) -> Vec<Isometry3<f32>> {
+        let mut isometries = Vec::with_capacity(1 + self.controller.wheel_count());
+        isometries.push(*self.controller.cabin_isometry());
+        for wheel_index in 0..self.controller.wheel_count() {
+            isometries.push(self.controller.wheel_isometry(wheel_index));
+        }
+        isometries
+    }
+
+    /// How much (in degrees) the camera's field of view should be widened to sell the car's speed.
+    pub fn camera_fov_kick(&self) -> f32 {
+        self.camera_fov_kick
+    }
+
+    pub fn hash_state(&self, checksum: &mut StateChecksum) {
+        self.controller.hash_state(checksum);
+    }
+
+    /// Keep the car's sounds from the interface, for sims the interface never shows.
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+    }
+
+    /// Play the car's sounds at the provided rate relative to real time.
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale;
+    }
+
+    /// Put the car at the provided isometry, bring it to a stop, and repair it.
+    /// Put the car at the provided isometry, bring it to a stop, and fix it up.
+    /// The camera goes back to the mode the car started with.
+    pub fn respawn(
+        &mut self,
+        config: &SedanConfig,
+        physics: &mut PhysicsWorld,
+        isometry: Isometry3<f32>,
+    ) {
+        self.controller
+            .respawn(&mut physics.rigid_body_set, isometry);
+        self.impact_cooldown_timer = None;
+
+        // Snap the follow camera in behind the car rather than letting it
+        // swing around, then put whichever camera is in use in place from there
+        self.follow_cam_rig.pivot_rotation = isometry.rotation;
+        self.camera_mode = config.initial_camera_mode;
+        self.camera_blend_progress = 1.0;
+        self.update_camera(
+            config,
+            &GameSettings::default(),
+            &Input::default(),
+            physics,
+            0.0,
+        );
+    }
+
     pub fn update(
         &mut self,
         config: &SedanConfig,
@@ -96,54 +185,235 @@ impl<'a> Sedan<'a> {
         physics: &mut PhysicsWorld,
         delta_seconds: f32,
     ) {
+        self.drive(config, input, physics, delta_seconds);
+        self.update_camera(config, settings, input, physics, delta_seconds);
+    }
+
+    /// Update the car without moving its camera, for cars whose camera is never shown.
+    pub fn drive(
+        &mut self,
+        config: &SedanConfig,
+        input: &Input,
+        physics: &mut PhysicsWorld,
+        delta_seconds: f32,
+    ) {
+        self.handle_impacts(config, physics, delta_seconds);
+
         self.controller.update(
             &config.raycast_vehicle_controller,
             input,
             physics,
             delta_seconds,
         );
+    }
 
+    fn update_camera(
+        &mut self,
+        config: &SedanConfig,
+        settings: &GameSettings,
+        input: &Input,
+        physics: &mut PhysicsWorld,
+        delta_seconds: f32,
+    ) {
         if let Some(cabin_body) = physics
             .rigid_body_set
             .get(self.controller.cabin_body_handle())
         {
-            if self.camera_mode == CameraMode::Free {
-                Self::update_boom_isometry(
-                    &mut self.camera_boom,
-                    cabin_body,
-                    -input.rotate_right()
-                        * (2.5 * f32::from(settings.left_right_look_sensitivity()) / 5.0)
-                            .to_radians(),
-                    input.rotate_up()
-                        * (5.0 * f32::from(settings.up_down_look_sensitivity()) / 5.0).to_radians(),
-                    config.max_look_up_angle,
-                    config.min_look_up_angle,
-                );
+            let cabin_iso = *cabin_body.position();
+            let mut target_iso = self.camera_iso;
+            let mut follow_the_car = self.camera_mode == CameraMode::Track;
+            match self.camera_mode {
+                CameraMode::Free => {
+                    Self::update_boom_isometry(
+                        &mut self.camera_boom,
+                        cabin_body,
+                        -input.rotate_right()
+                            * (2.5 * f32::from(settings.left_right_look_sensitivity()) / 5.0)
+                                .to_radians(),
+                        input.rotate_up()
+                            * (5.0 * f32::from(settings.up_down_look_sensitivity()) / 5.0)
+                                .to_radians(),
+                        config.max_look_up_angle,
+                        config.min_look_up_angle,
+                    );
+
+                    Self::prevent_boom_obstructions(
+                        &mut self.camera_boom,
+                        &config,
+                        cabin_body,
+                        &physics.query_pipeline,
+                        &physics.rigid_body_set,
+                        &physics.collider_set,
+                        QueryFilter::new().exclude_rigid_body(self.controller.cabin_body_handle()),
+                    );
+
+                    target_iso = self.camera_boom.end_isometry();
+                }
+                CameraMode::Hood => {
+                    target_iso =
+                        cabin_iso * Translation3::from(Vector3::from(config.hood_camera_offset));
+                }
+                CameraMode::Bumper => {
+                    target_iso =
+                        cabin_iso * Translation3::from(Vector3::from(config.bumper_camera_offset));
+                }
+                CameraMode::Cinematic => {
+                    // Cut to whichever trackside camera is closest to the car, falling
+                    // back to following the car if the level doesn't have any
+                    match self.closest_cinematic_camera_translation(&cabin_iso) {
+                        Some(camera_translation) => {
+                            let cabin_to_camera =
+                                camera_translation.vector - cabin_iso.translation.vector;
+                            target_iso = Isometry3::from_parts(
+                                camera_translation,
+                                UnitQuaternion::face_towards(&cabin_to_camera, &Vector3::y()),
+                            );
+                        }
+                        None => follow_the_car = true,
+                    }
+                }
+                CameraMode::Track => {}
+            }
+            // The follow camera is smoothed, so it's kept up to date even when
+            // it's not in use to avoid lurching when it's switched to
+            Self::update_follow_camera_arm(
+                config,
+                &mut self.follow_cam_rig,
+                self.controller.forward_speed(&physics.rigid_body_set),
+                input.steer(),
+                delta_seconds,
+            );
+            self.camera_fov_kick = self.follow_cam_rig.speed_factor * config.max_fov_kick;
+            Self::update_follow_camera(
+                &physics.rigid_body_set,
+                &physics.collider_set,
+                &physics.query_pipeline,
+                QueryFilter::new().exclude_rigid_body(self.controller.cabin_body_handle()),
+                cabin_body,
+                &mut self.follow_cam_rig,
+                &mut self.follow_cam_iso,
+                config.track_mode_cam_lerp_factor,
+                delta_seconds,
+            );
+            if follow_the_car {
+                target_iso = self.follow_cam_iso;
+            }
 
-                Self::prevent_boom_obstructions(
-                    &mut self.camera_boom,
-                    &config,
-                    cabin_body,
-                    &physics.query_pipeline,
-                    &physics.rigid_body_set,
-                    &physics.collider_set,
-                    QueryFilter::new().exclude_rigid_body(self.controller.cabin_body_handle()),
+            if input.look_back() && self.camera_mode != CameraMode::Cinematic {
+                // Swing the camera around the cabin so that it faces the rear
+                let half_turn = Isometry3::from_parts(
+                    Translation3::identity(),
+                    UnitQuaternion::from_axis_angle(&Vector3::y_axis(), std::f32::consts::PI),
                 );
+                target_iso = cabin_iso * half_turn * cabin_iso.inverse() * target_iso;
+            }
 
-                self.camera_iso = self.camera_boom.end_isometry();
+            self.camera_blend_progress = if config.camera_mode_blend_duration > 0.0 {
+                (self.camera_blend_progress + delta_seconds / config.camera_mode_blend_duration)
+                    .min(1.0)
             } else {
-                Self::update_follow_camera(
-                    &physics.rigid_body_set,
-                    &physics.collider_set,
-                    &physics.query_pipeline,
-                    QueryFilter::new().exclude_rigid_body(self.controller.cabin_body_handle()),
-                    config,
-                    cabin_body,
-                    &mut self.follow_cam_rig,
-                    &mut self.camera_iso,
-                    config.track_mode_cam_lerp_factor,
-                    delta_seconds,
-                );
+                1.0
+            };
+            self.camera_iso = if self.camera_blend_progress < 1.0 {
+                // Ease in and out of the blend
+                let blend_t = self.camera_blend_progress
+                    * self.camera_blend_progress
+                    * (3.0 - 2.0 * self.camera_blend_progress);
+                (cabin_iso * self.camera_blend_start_iso).lerp_slerp(&target_iso, blend_t)
+            } else {
+                target_iso
+            };
+        }
+    }
+
+    /// Switch to the next camera mode, blending from the current camera view.
+    pub fn cycle_camera_mode(&mut self, physics: &PhysicsWorld) {
+        self.camera_mode = self.camera_mode.next();
+        if let Some(cabin_body) = physics
+            .rigid_body_set
+            .get(self.controller.cabin_body_handle())
+        {
+            // The blend starts relative to the cabin so that it doesn't trail
+            // behind the car while blending
+            self.camera_blend_start_iso = cabin_body.position().inverse() * self.camera_iso;
+            self.camera_blend_progress = 0.0;
+        }
+    }
+
+    /// Set the trackside cameras used by the cinematic camera mode.
+    pub fn set_cinematic_camera_isometries(&mut self, isometries: Vec<Isometry3<f32>>) {
+        self.cinematic_camera_isometries = isometries;
+    }
+
+    fn closest_cinematic_camera_translation(
+        &self,
+        cabin_iso: &Isometry3<f32>,
+    ) -> Option<Translation3<f32>> {
+        self.cinematic_camera_isometries
+            .iter()
+            .map(|camera_iso| camera_iso.translation)
+            .min_by(|a, b| {
+                let a_distance = (a.vector - cabin_iso.translation.vector).norm_squared();
+                let b_distance = (b.vector - cabin_iso.translation.vector).norm_squared();
+                a_distance.total_cmp(&b_distance)
+            })
+    }
+
+    fn handle_impacts(&mut self, config: &SedanConfig, physics: &PhysicsWorld, delta_seconds: f32) {
+        if let Some(impact_cooldown_timer) = &mut self.impact_cooldown_timer {
+            impact_cooldown_timer.tick(delta_seconds);
+        }
+        while let Ok(cabin_event) = self.cabin_event_channel.get_message() {
+            if let ColliderEvent::ContactForceEvent(_other, contact_force_event) = cabin_event {
+                if self
+                    .impact_cooldown_timer
+                    .as_ref()
+                    .is_some_and(|impact_cooldown_timer| {
+                        impact_cooldown_timer.elapsed()
+                            < Duration::from_secs_f32(config.min_impact_interval)
+                    })
+                {
+                    continue;
+                }
+                self.impact_cooldown_timer = Some(PassiveClock::default());
+
+                let impact_force = contact_force_event.max_force_magnitude;
+                let impact_volume = remap(
+                    impact_force,
+                    config.raycast_vehicle_controller.impact_force_threshold,
+                    config.impact_audio_max_force,
+                    0.0,
+                    1.0,
+                )
+                .clamp(0.0, 1.0);
+                if !self.headless {
+                    play_audio(
+                        self.scene_object_name(),
+                        "IMPACT",
+                        self.time_scale,
+                        impact_volume,
+                    );
+                }
+
+                // Contact normals point from the first collider toward the second
+                let mut impact_direction = contact_force_event.max_force_direction;
+                if contact_force_event.collider2 == self.controller.cabin_collider_handle() {
+                    impact_direction = -impact_direction;
+                }
+                if let Some(cabin_body) = physics
+                    .rigid_body_set
+                    .get(self.controller.cabin_body_handle())
+                {
+                    let cabin_relative_impact_direction = cabin_body
+                        .position()
+                        .rotation
+                        .inverse_transform_vector(&impact_direction);
+                    self.controller.apply_impact(
+                        &config.raycast_vehicle_controller,
+                        impact_force,
+                        &cabin_relative_impact_direction,
+                    );
+                }
             }
         }
     }
@@ -171,12 +441,54 @@ impl<'a> Sedan<'a> {
         );
     }
 
+    fn follow_camera_arm_rotation(pitch_angle: f32, yaw_angle: f32) -> UnitQuaternion<f32> {
+        let arm_rotation = UnitQuaternion::identity();
+        let arm_rotation =
+            arm_rotation.append_axisangle_linearized(&(Vector3::x() * pitch_angle.to_radians()));
+        arm_rotation.append_axisangle_linearized(&(Vector3::y() * yaw_angle.to_radians()))
+    }
+
+    /// Pull the follow camera back and lower as the car speeds up, and swing it
+    /// around to look into turns.
+    fn update_follow_camera_arm(
+        config: &SedanConfig,
+        follow_rig: &mut FollowCamExtras,
+        forward_speed: f32,
+        steer_input: f32,
+        delta_seconds: f32,
+    ) {
+        let interp_t = framerate_independent_interp_t(follow_rig.lerp_factor, delta_seconds);
+        let target_speed_factor = if config.track_mode_reference_speed > 0.0 {
+            (forward_speed.abs() / config.track_mode_reference_speed).min(1.0)
+        } else {
+            0.0
+        };
+        follow_rig.speed_factor = lerp(follow_rig.speed_factor, target_speed_factor, interp_t);
+        // Steering right swings the camera left so that it looks toward the right
+        follow_rig.turn_look_yaw_angle = lerp(
+            follow_rig.turn_look_yaw_angle,
+            -steer_input.clamp(-1.0, 1.0) * config.track_mode_turn_look_yaw_angle,
+            interp_t,
+        );
+
+        follow_rig.arm_rotation = Self::follow_camera_arm_rotation(
+            config.track_mode_look_pitch_angle
+                + follow_rig.speed_factor * config.track_mode_speed_pitch_offset,
+            config.track_mode_look_yaw_angle + follow_rig.turn_look_yaw_angle,
+        );
+        follow_rig.arm_end_translation = Vector3::new(
+            0.0,
+            0.0,
+            config.max_boom_length
+                + follow_rig.speed_factor * config.track_mode_speed_boom_extension,
+        );
+    }
+
     fn update_follow_camera(
         rigid_body_set: &RigidBodySet,
         collider_set: &ColliderSet,
         query_pipeline: &QueryPipeline,
         query_filter_excluding_cabin: QueryFilter,
-        config: &SedanConfig,
         cabin_body: &RigidBody,
         follow_rig: &mut FollowCamExtras,
         camera_iso: &mut Isometry3<f32>,
@@ -208,7 +520,7 @@ impl<'a> Sedan<'a> {
             rigid_body_set,
             collider_set,
             &ray,
-            config.max_boom_length,
+            follow_rig.arm_end_translation.norm(),
             true,
             query_filter_excluding_cabin,
         ) {
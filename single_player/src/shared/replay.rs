@@ -0,0 +1,243 @@
+use crate::shared::{input::Input, settings::GameSettings};
+use perigee::{
+    toml,
+    traits::{TryFromToml, TryToToml},
+};
+use serde::{Deserialize, Serialize};
+
+/// How far (in seconds) a replayed step may be from the length of the level's ticks,
+/// allowing for rounding when the log was written out. A step any longer would let
+/// a replay skip through walls that a real run would have to go around.
+static TICK_SECONDS_TOLERANCE: f32 = 0.000001;
+
+/// How far (in seconds) a claimed completion time may be from
+/// the end of the frame the level was actually completed on.
+static COMPLETION_TIME_TOLERANCE: f32 = 0.001;
+
+/// Everything a level simulation was given during a single step,
+/// along with the checksum of the state it stepped to.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct InputLogFrame {
+    pub delta_seconds: f32,
+    pub state_checksum: u32,
+    pub settings: GameSettings,
+    pub input: Input,
+}
+
+/// What became of a level simulation after replaying a single step.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayedFrame {
+    pub level_completed: bool,
+    pub state_checksum: u32,
+}
+
+/// A step-by-step log of a run through a level, used to re-run
+/// the level exactly as it was played.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct InputLog {
+    frames: Vec<InputLogFrame>,
+}
+
+impl TryFromToml for InputLog {
+    fn try_from_toml(toml_str: &str) -> Result<Self, String> {
+        match toml::from_str::<InputLog>(toml_str) {
+            Ok(input_log) => Ok(input_log),
+            Err(toml_de_err) => Err(toml_de_err.to_string()),
+        }
+    }
+}
+
+impl TryToToml for InputLog {
+    fn try_to_toml(&self) -> Result<String, String> {
+        match toml::to_string(self) {
+            Ok(input_log_toml) => Ok(input_log_toml),
+            Err(toml_ser_err) => Err(toml_ser_err.to_string()),
+        }
+    }
+}
+
+impl InputLog {
+    pub fn frames(&self) -> &[InputLogFrame] {
+        &self.frames
+    }
+
+    pub fn record(
+        &mut self,
+        delta_seconds: f32,
+        settings: GameSettings,
+        input: Input,
+        state_checksum: u32,
+    ) {
+        self.frames.push(InputLogFrame {
+            delta_seconds,
+            state_checksum,
+            settings,
+            input,
+        });
+    }
+
+    /// How many seconds of play the log covers.
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.frames.iter().map(|frame| frame.delta_seconds).sum()
+    }
+
+    /// Feed every frame of the log to `step`, making sure every frame is a single tick
+    /// of the provided length, the replay never diverges from the recording, and the
+    /// level was completed at the claimed time.
+    pub fn verify_completion(
+        &self,
+        tick_seconds: f32,
+        claimed_completion_seconds: f32,
+        mut step: impl FnMut(&InputLogFrame) -> ReplayedFrame,
+    ) -> Result<(), String> {
+        let mut elapsed_seconds = 0.0;
+        for (frame_index, frame) in self.frames.iter().enumerate() {
+            // Checked before stepping, since stepping by NaN or infinity would wreck the sim
+            if frame.delta_seconds.is_nan()
+                || (frame.delta_seconds - tick_seconds).abs() > TICK_SECONDS_TOLERANCE
+            {
+                return Err(format!(
+                    "Frame {} steps by {} seconds rather than the level's {} second tick",
+                    frame_index, frame.delta_seconds, tick_seconds
+                ));
+            }
+            let frame_start_seconds = elapsed_seconds;
+            elapsed_seconds += frame.delta_seconds;
+
+            let replayed_frame = step(frame);
+            if replayed_frame.state_checksum != frame.state_checksum {
+                return Err(format!(
+                    "Replay diverged from the recording on frame {}",
+                    frame_index
+                ));
+            }
+            if replayed_frame.level_completed {
+                // The claimed time has to fall within the frame the level was completed on
+                return if claimed_completion_seconds > frame_start_seconds
+                    && claimed_completion_seconds <= elapsed_seconds + COMPLETION_TIME_TOLERANCE
+                {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Level was completed at {} seconds rather than the claimed {} seconds",
+                        elapsed_seconds, claimed_completion_seconds
+                    ))
+                };
+            }
+        }
+        Err(String::from("Level was never completed"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fixed_rate_log(frame_count: u32) -> InputLog {
+        let mut input_log = InputLog::default();
+        for frame_index in 0..frame_count {
+            input_log.record(
+                0.0625,
+                GameSettings::default(),
+                Input::default(),
+                frame_index,
+            );
+        }
+        input_log
+    }
+
+    /// Replays frames with the checksums they were recorded with until the completing frame.
+    fn complete_on_frame(
+        completing_frame_count: u32,
+    ) -> impl FnMut(&InputLogFrame) -> ReplayedFrame {
+        let mut frames_stepped = 0;
+        move |frame| {
+            frames_stepped += 1;
+            ReplayedFrame {
+                level_completed: frames_stepped == completing_frame_count,
+                state_checksum: frame.state_checksum,
+            }
+        }
+    }
+
+    #[test]
+    fn verifies_completion_time() {
+        let input_log = fixed_rate_log(8);
+        let complete_on_fifth_frame = || complete_on_frame(5);
+
+        assert!(input_log
+            .verify_completion(0.0625, 0.3125, complete_on_fifth_frame())
+            .is_ok());
+        assert!(input_log
+            .verify_completion(0.0625, 0.25, complete_on_fifth_frame())
+            .is_err());
+        assert!(input_log
+            .verify_completion(0.0625, 0.5, complete_on_frame(0))
+            .is_err());
+        // A replay that ends up somewhere else than the recording did doesn't count
+        assert!(input_log
+            .verify_completion(0.0625, 0.3125, |_| ReplayedFrame {
+                level_completed: true,
+                state_checksum: u32::MAX,
+            })
+            .is_err());
+
+        let mut toml_log = InputLog::try_from_toml(&input_log.try_to_toml().unwrap()).unwrap();
+        assert_eq!(toml_log.frames().len(), 8);
+        // Skipping ahead a whole second in one step isn't a legitimate run
+        toml_log.record(1.0, GameSettings::default(), Input::default(), 8);
+        assert!(toml_log
+            .verify_completion(0.0625, 1.5, complete_on_frame(9))
+            .is_err());
+    }
+
+    #[test]
+    fn verifies_logs_at_any_tick_rate() {
+        let mut input_log = InputLog::default();
+        for frame_index in 0..4 {
+            input_log.record(0.2, GameSettings::default(), Input::default(), frame_index);
+        }
+        let input_log = InputLog::try_from_toml(&input_log.try_to_toml().unwrap()).unwrap();
+
+        assert!(input_log
+            .verify_completion(0.2, 0.8, complete_on_frame(4))
+            .is_ok());
+        // The log has to have been recorded at the level's tick rate
+        assert!(input_log
+            .verify_completion(0.0625, 0.8, complete_on_frame(4))
+            .is_err());
+    }
+
+    #[test]
+    fn loads_logs_missing_newer_inputs() {
+        let input_log_toml = fixed_rate_log(1)
+            .try_to_toml()
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with("look_back") && !line.starts_with("reset"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let input_log = InputLog::try_from_toml(&input_log_toml).unwrap();
+        assert!(!input_log.frames()[0].input.look_back());
+        assert!(!input_log.frames()[0].input.reset());
+    }
+
+    #[test]
+    fn rejects_bad_steps_before_stepping() {
+        for delta_seconds in [f32::NAN, f32::INFINITY, 0.0, -0.0625, 0.125] {
+            let mut input_log = fixed_rate_log(2);
+            input_log.record(delta_seconds, GameSettings::default(), Input::default(), 2);
+            let mut frames_stepped = 0;
+            assert!(input_log
+                .verify_completion(0.0625, 0.25, |frame| {
+                    frames_stepped += 1;
+                    ReplayedFrame {
+                        level_completed: false,
+                        state_checksum: frame.state_checksum,
+                    }
+                })
+                .is_err());
+            assert_eq!(frames_stepped, 2);
+        }
+    }
+}
@@ -0,0 +1,460 @@
+use crate::shared::descriptor::Descriptor;
+use crate::shared::vectors::UP_VECTOR;
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Points of interest with this tag make up a path.
+static PATH_TAG: &str = "path";
+/// Paths with a point of interest that has this tag loop back to their first point.
+static LOOP_TAG: &str = "loop";
+static SAMPLES_PER_SEGMENT: usize = 16;
+
+/// The points of interest that make up the named path in order, and whether the path loops.
+///
+/// Path points are named `<path name>.path.<order>`, like `Racing Line.path.1`,
+/// and are traveled in order. If any of them is also tagged with `loop`, like
+/// `Racing Line.path.loop.1`, the path loops back to its first point.
+pub fn path_points_of_interest<'n>(
+    pois: &PointsOfInterest,
+    poi_names: impl IntoIterator<Item = &'n str>,
+    path_name: &str,
+) -> (Vec<Isometry3<f32>>, bool) {
+    let mut closed = false;
+    let mut ordered_points: Vec<(u32, Isometry3<f32>)> = poi_names
+        .into_iter()
+        .filter_map(|poi_name| {
+            let descriptor = Descriptor::from_name(poi_name);
+            if descriptor.object_name() != path_name || !descriptor.has_tag(PATH_TAG) {
+                return None;
+            }
+            let order = poi_name.rsplit('.').next()?.trim().parse::<u32>().ok()?;
+            let poi = pois.point_with_name(poi_name)?;
+            closed |= descriptor.has_tag(LOOP_TAG);
+            Some((order, *poi))
+        })
+        .collect();
+    ordered_points.sort_by_key(|(order, _)| *order);
+
+    (
+        ordered_points.into_iter().map(|(_, point)| point).collect(),
+        closed,
+    )
+}
+
+/// A cubic Bezier curve making up a single piece of a spline.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct BezierSegment {
+    control_points: [Vector3<f32>; 4],
+}
+
+impl BezierSegment {
+    fn point(&self, t: f32) -> Vector3<f32> {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        p0 * (u * u * u) + p1 * (3.0 * u * u * t) + p2 * (3.0 * u * t * t) + p3 * (t * t * t)
+    }
+
+    fn derivative(&self, t: f32) -> Vector3<f32> {
+        let [p0, p1, p2, p3] = self.control_points;
+        let u = 1.0 - t;
+        (p1 - p0) * (3.0 * u * u) + (p2 - p1) * (6.0 * u * t) + (p3 - p2) * (3.0 * t * t)
+    }
+
+    fn second_derivative(&self, t: f32) -> Vector3<f32> {
+        let [p0, p1, p2, p3] = self.control_points;
+        (p2 - p1 * 2.0 + p0) * (6.0 * (1.0 - t)) + (p3 - p2 * 2.0 + p1) * (6.0 * t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ArcLengthSample {
+    /// How far along the spline this sample is
+    distance: f32,
+    /// The segment index plus how far along that segment (from 0 to 1) this sample is
+    parameter: f32,
+    point: Vector3<f32>,
+}
+
+/// A smooth path made of cubic Bezier segments that can be traveled by distance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spline {
+    segments: Vec<BezierSegment>,
+    closed: bool,
+    arc_length_samples: Vec<ArcLengthSample>,
+}
+
+impl Spline {
+    /// Create a Catmull-Rom spline that passes through every provided point.
+    pub fn from_catmull_rom(points: &[Vector3<f32>], closed: bool) -> Option<Self> {
+        let point_count = points.len();
+        if point_count < 2 {
+            return None;
+        }
+        let point = |index: isize| -> Vector3<f32> {
+            if closed {
+                points[index.rem_euclid(point_count as isize) as usize]
+            } else if index < 0 {
+                // Reflect the neighbors of the end points so that the ends stay natural
+                points[0] * 2.0 - points[1]
+            } else if index as usize >= point_count {
+                points[point_count - 1] * 2.0 - points[point_count - 2]
+            } else {
+                points[index as usize]
+            }
+        };
+        let segment_count = if closed { point_count } else { point_count - 1 };
+        let mut control_points: Vec<Vector3<f32>> = (0..segment_count as isize)
+            .flat_map(|index| {
+                let (p0, p1, p2, p3) = (
+                    point(index - 1),
+                    point(index),
+                    point(index + 1),
+                    point(index + 2),
+                );
+                [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0]
+            })
+            .collect();
+        if !closed {
+            control_points.push(points[point_count - 1]);
+        }
+        Self::from_bezier(&control_points, closed)
+    }
+
+    /// Create a spline from Bezier control points laid out as point, handle, handle,
+    /// point, and so on. Open splines end on a point, while closed ones end on a handle.
+    pub fn from_bezier(control_points: &[Vector3<f32>], closed: bool) -> Option<Self> {
+        let control_point_count = control_points.len();
+        let trailing_point_count = if closed { 0 } else { 1 };
+        if control_point_count < 3 || control_point_count % 3 != trailing_point_count {
+            return None;
+        }
+        let segments = (0..control_point_count / 3)
+            .map(|index| BezierSegment {
+                control_points: [
+                    control_points[index * 3],
+                    control_points[index * 3 + 1],
+                    control_points[index * 3 + 2],
+                    control_points[(index * 3 + 3) % control_point_count],
+                ],
+            })
+            .collect();
+        Some(Self::from_segments(segments, closed))
+    }
+
+    /// Create a Catmull-Rom spline through the points of interest that make up the named path.
+    /// If any of them is also tagged with `loop`, like `Racing Line.path.loop.1`,
+    /// the path loops back to its first point.
+    pub fn from_points_of_interest<'n>(
+        pois: &PointsOfInterest,
+        poi_names: impl IntoIterator<Item = &'n str>,
+        path_name: &str,
+    ) -> Option<Self> {
+        let (path_points, closed) = path_points_of_interest(pois, poi_names, path_name);
+        let points: Vec<Vector3<f32>> = path_points
+            .iter()
+            .map(|point| point.translation.vector)
+            .collect();
+        Self::from_catmull_rom(&points, closed)
+    }
+
+    fn from_segments(segments: Vec<BezierSegment>, closed: bool) -> Self {
+        let mut arc_length_samples = Vec::with_capacity(segments.len() * SAMPLES_PER_SEGMENT + 1);
+        let mut distance = 0.0;
+        let mut previous_point = segments[0].point(0.0);
+        arc_length_samples.push(ArcLengthSample {
+            distance,
+            parameter: 0.0,
+            point: previous_point,
+        });
+        for (segment_index, segment) in segments.iter().enumerate() {
+            for sample_index in 1..=SAMPLES_PER_SEGMENT {
+                let t = sample_index as f32 / SAMPLES_PER_SEGMENT as f32;
+                let point = segment.point(t);
+                distance += (point - previous_point).norm();
+                arc_length_samples.push(ArcLengthSample {
+                    distance,
+                    parameter: segment_index as f32 + t,
+                    point,
+                });
+                previous_point = point;
+            }
+        }
+
+        Self {
+            segments,
+            closed,
+            arc_length_samples,
+        }
+    }
+
+    /// How long the spline is from end to end.
+    pub fn length(&self) -> f32 {
+        self.arc_length_samples
+            .last()
+            .map(|sample| sample.distance)
+            .unwrap_or(0.0)
+    }
+
+    /// Keep a distance on the spline, wrapping it around closed
+    /// splines and clamping it to the ends of open ones.
+    pub fn wrap_distance(&self, distance: f32) -> f32 {
+        if self.closed {
+            distance.rem_euclid(self.length())
+        } else {
+            distance.clamp(0.0, self.length())
+        }
+    }
+
+    /// The point the provided distance along the spline.
+    pub fn point_at_distance(&self, distance: f32) -> Vector3<f32> {
+        let (segment, t) = self.segment_at_distance(distance);
+        segment.point(t)
+    }
+
+    /// How far along the spline the segment with the provided index starts. Catmull-Rom
+    /// splines start a segment at each point they pass through, so this is also
+    /// how far along the spline that point is.
+    pub fn segment_start_distance(&self, segment_index: usize) -> f32 {
+        self.arc_length_samples
+            .get(segment_index * SAMPLES_PER_SEGMENT)
+            .map(|sample| sample.distance)
+            .unwrap_or_else(|| self.length())
+    }
+
+    /// The direction of travel the provided distance along the spline.
+    pub fn tangent_at_distance(&self, distance: f32) -> Vector3<f32> {
+        let (segment, t) = self.segment_at_distance(distance);
+        segment
+            .derivative(t)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| {
+                // Handles sitting on their points stop the curve at its ends
+                (segment.control_points[3] - segment.control_points[0])
+                    .try_normalize(f32::EPSILON)
+                    .unwrap_or_else(Vector3::zeros)
+            })
+    }
+
+    /// An isometry the provided distance along the spline that faces (-Z) along
+    /// the direction of travel and keeps its top (+Y) as close to up as possible.
+    pub fn frame_at_distance(&self, distance: f32) -> Isometry3<f32> {
+        let tangent = self.tangent_at_distance(distance);
+        // Fall back to facing forward when traveling straight up or down
+        let up = if tangent.cross(&UP_VECTOR).norm_squared() > f32::EPSILON {
+            UP_VECTOR
+        } else {
+            Vector3::z()
+        };
+        Isometry3::from_parts(
+            self.point_at_distance(distance).into(),
+            UnitQuaternion::face_towards(&-tangent, &up),
+        )
+    }
+
+    /// How sharply the spline bends the provided distance along it,
+    /// which is 1 over the radius of the bend.
+    pub fn curvature_at_distance(&self, distance: f32) -> f32 {
+        let (segment, t) = self.segment_at_distance(distance);
+        let velocity = segment.derivative(t);
+        let speed = velocity.norm();
+        if speed <= f32::EPSILON {
+            return 0.0;
+        }
+        velocity.cross(&segment.second_derivative(t)).norm() / (speed * speed * speed)
+    }
+
+    /// How far along the spline its closest point to the provided position is.
+    pub fn closest_distance(&self, position: &Vector3<f32>) -> f32 {
+        self.closest_distance_in_samples(position, 0, self.arc_length_samples.len() - 1)
+    }
+
+    /// How far along the spline its closest point to the provided position is, only
+    /// considering the part of the spline within a window around the provided distance.
+    /// This keeps paths that cross or run alongside themselves from being confused.
+    pub fn closest_distance_near(
+        &self,
+        position: &Vector3<f32>,
+        around_distance: f32,
+        window: f32,
+    ) -> f32 {
+        if self.closed && window * 2.0 >= self.length() {
+            return self.closest_distance(position);
+        }
+        let window_start = around_distance - window;
+        let window_end = around_distance + window;
+        if !self.closed || (window_start >= 0.0 && window_end <= self.length()) {
+            let first_index = self.sample_index_at_distance(window_start.max(0.0));
+            let last_index = self.sample_index_at_distance(window_end.min(self.length())) + 1;
+            return self.closest_distance_in_samples(
+                position,
+                first_index,
+                last_index.min(self.arc_length_samples.len() - 1),
+            );
+        }
+
+        // The window wraps around the start of a closed spline
+        let wrapped_start = self.wrap_distance(window_start);
+        let wrapped_end = self.wrap_distance(window_end);
+        let last_sample_index = self.arc_length_samples.len() - 1;
+        let before_seam = self.closest_distance_in_samples(
+            position,
+            self.sample_index_at_distance(wrapped_start),
+            last_sample_index,
+        );
+        let after_seam = self.closest_distance_in_samples(
+            position,
+            0,
+            (self.sample_index_at_distance(wrapped_end) + 1).min(last_sample_index),
+        );
+        if (self.point_at_distance(before_seam) - position).norm_squared()
+            <= (self.point_at_distance(after_seam) - position).norm_squared()
+        {
+            before_seam
+        } else {
+            after_seam
+        }
+    }
+
+    fn closest_distance_in_samples(
+        &self,
+        position: &Vector3<f32>,
+        first_index: usize,
+        last_index: usize,
+    ) -> f32 {
+        let mut closest_distance_squared = f32::MAX;
+        let mut closest_distance = self.arc_length_samples[first_index].distance;
+        for index in first_index..last_index {
+            let start = &self.arc_length_samples[index];
+            let end = &self.arc_length_samples[index + 1];
+            let sample_span = end.point - start.point;
+            let span_length_squared = sample_span.norm_squared();
+            let t = if span_length_squared <= f32::EPSILON {
+                0.0
+            } else {
+                ((position - start.point).dot(&sample_span) / span_length_squared).clamp(0.0, 1.0)
+            };
+            let distance_squared = (start.point + sample_span * t - position).norm_squared();
+            if distance_squared < closest_distance_squared {
+                closest_distance_squared = distance_squared;
+                closest_distance = start.distance + (end.distance - start.distance) * t;
+            }
+        }
+        closest_distance
+    }
+
+    /// The index of the last arc length sample at or before the provided distance.
+    fn sample_index_at_distance(&self, distance: f32) -> usize {
+        let index = self
+            .arc_length_samples
+            .partition_point(|sample| sample.distance <= distance);
+        index
+            .saturating_sub(1)
+            .min(self.arc_length_samples.len() - 2)
+    }
+
+    fn segment_at_distance(&self, distance: f32) -> (&BezierSegment, f32) {
+        let distance = self.wrap_distance(distance);
+        let index = self.sample_index_at_distance(distance);
+        let start = &self.arc_length_samples[index];
+        let end = &self.arc_length_samples[index + 1];
+        let sample_span = end.distance - start.distance;
+        let sample_t = if sample_span <= f32::EPSILON {
+            0.0
+        } else {
+            (distance - start.distance) / sample_span
+        };
+        let parameter = start.parameter + (end.parameter - start.parameter) * sample_t;
+
+        let segment_index = (parameter.floor() as usize).min(self.segments.len() - 1);
+        (
+            &self.segments[segment_index],
+            parameter - segment_index as f32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn travels_by_distance() {
+        let spline = Spline::from_catmull_rom(
+            &[
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, -5.0),
+                Vector3::new(0.0, 0.0, -10.0),
+            ],
+            false,
+        )
+        .unwrap();
+        assert!((spline.length() - 10.0).abs() < 0.01);
+        assert!((spline.point_at_distance(2.5) - Vector3::new(0.0, 0.0, -2.5)).norm() < 0.01);
+        assert!((spline.closest_distance(&Vector3::new(3.0, 1.0, -4.0)) - 4.0).abs() < 0.01);
+        assert!((spline.segment_start_distance(1) - 5.0).abs() < 0.01);
+        assert!((spline.tangent_at_distance(7.0) - Vector3::new(0.0, 0.0, -1.0)).norm() < 0.01);
+    }
+
+    #[test]
+    fn frames_face_along_the_spline() {
+        // A quarter circle turning from heading -Z to heading +X
+        let handle_length = 10.0 * 0.552_285;
+        let spline = Spline::from_bezier(
+            &[
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, -handle_length),
+                Vector3::new(10.0 - handle_length, 0.0, -10.0),
+                Vector3::new(10.0, 0.0, -10.0),
+            ],
+            false,
+        )
+        .unwrap();
+        assert!(Spline::from_bezier(&[Vector3::zeros(); 3], false).is_none());
+
+        assert!((spline.tangent_at_distance(0.0) - Vector3::new(0.0, 0.0, -1.0)).norm() < 0.01);
+        assert!(
+            (spline.tangent_at_distance(spline.length()) - Vector3::new(1.0, 0.0, 0.0)).norm()
+                < 0.01
+        );
+
+        for step in 0..=10 {
+            let distance = spline.length() * step as f32 / 10.0;
+            let frame = spline.frame_at_distance(distance);
+            let forward = frame.rotation * Vector3::new(0.0, 0.0, -1.0);
+            let right = frame.rotation * Vector3::x();
+            let up = frame.rotation * Vector3::y();
+            assert!((forward - spline.tangent_at_distance(distance)).norm() < 0.01);
+            assert!((up - Vector3::y()).norm() < 0.01);
+            for axis in [forward, right, up] {
+                assert!((axis.norm() - 1.0).abs() < 0.001);
+            }
+            assert!(forward.dot(&right).abs() < 0.001);
+            assert!(forward.dot(&up).abs() < 0.001);
+            assert!(right.dot(&up).abs() < 0.001);
+            assert!((frame.translation.vector - spline.point_at_distance(distance)).norm() < 0.001);
+        }
+    }
+
+    #[test]
+    fn closed_splines_loop() {
+        let radius = 10.0;
+        let points: Vec<Vector3<f32>> = (0..16)
+            .map(|index| {
+                let angle = index as f32 / 16.0 * std::f32::consts::TAU;
+                Vector3::new(angle.cos() * radius, 0.0, angle.sin() * radius)
+            })
+            .collect();
+        let spline = Spline::from_catmull_rom(&points, true).unwrap();
+        let circumference = std::f32::consts::TAU * radius;
+        assert!((spline.length() - circumference).abs() < 0.1);
+        assert!((spline.curvature_at_distance(3.0) - 1.0 / radius).abs() < 0.01);
+        assert!(
+            (spline.point_at_distance(spline.length() + 1.0) - spline.point_at_distance(1.0))
+                .norm()
+                < 0.01
+        );
+
+        let seam_distance = spline.closest_distance_near(&points[0], 1.0, 5.0);
+        assert!(seam_distance < 0.01 || (spline.length() - seam_distance) < 0.01);
+    }
+}
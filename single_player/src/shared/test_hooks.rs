@@ -0,0 +1,75 @@
+//! Tests don't run inside an interface, so nothing else answers the hooks
+//! the sim calls out to. These stand in for the interface and ignore everything.
+
+#[no_mangle]
+extern "C" fn level_event_hook(_event_json_ptr: *const u8, _event_json_len: usize) {}
+
+#[no_mangle]
+extern "C" fn character_event_hook(_event_json_ptr: *const u8, _event_json_len: usize) {}
+
+#[no_mangle]
+extern "C" fn play_audio_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _audio_name_ptr: *const u8,
+    _audio_name_len: usize,
+    _playback_rate: f32,
+    _volume: f32,
+) {
+}
+
+#[no_mangle]
+extern "C" fn loop_audio_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _audio_name_ptr: *const u8,
+    _audio_name_len: usize,
+    _playback_rate: f32,
+    _volume: f32,
+) {
+}
+
+#[no_mangle]
+extern "C" fn stop_audio_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _audio_name_ptr: *const u8,
+    _audio_name_len: usize,
+) {
+}
+
+#[no_mangle]
+extern "C" fn play_animation_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _anim_name_ptr: *const u8,
+    _anim_name_len: usize,
+    _time_scale: f32,
+) {
+}
+
+#[no_mangle]
+extern "C" fn loop_animation_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _anim_name_ptr: *const u8,
+    _anim_name_len: usize,
+    _time_scale: f32,
+) {
+}
+
+#[no_mangle]
+extern "C" fn stop_animation_hook(
+    _scene_obj_name_ptr: *const u8,
+    _scene_obj_name_len: usize,
+    _anim_name_ptr: *const u8,
+    _anim_name_len: usize,
+) {
+}
+
+#[no_mangle]
+extern "C" fn assistive_device_announce_hook(
+    _announcement_msg_name_ptr: *const u8,
+    _announcement_msg_name_len: usize,
+) {
+}
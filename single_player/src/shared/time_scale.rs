@@ -0,0 +1,175 @@
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The slowest and fastest simulated time may pass relative to real time.
+/// Stopping time altogether is done by pausing instead.
+static MIN_TIME_SCALE: f32 = 0.05;
+static MAX_TIME_SCALE: f32 = 4.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoopingAudio {
+    scene_object_name: String,
+    audio_name: String,
+    playback_rate: f32,
+    volume: f32,
+}
+
+/// How fast simulated time passes relative to real time. Audio played through
+/// this has its playback rate follow along, even while it's looping.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeScale {
+    scale: f32,
+    paused: bool,
+    /// Keep track of audio without ever telling the interface to play it
+    #[serde(default)]
+    headless: bool,
+    looping_audio: Vec<LoopingAudio>,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            paused: false,
+            headless: false,
+            looping_audio: Vec::new(),
+        }
+    }
+}
+
+impl TimeScale {
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+    }
+
+    /// How fast audio and animations should play, which is 0 while paused.
+    pub fn playback_rate(&self) -> f32 {
+        if self.paused {
+            0.0
+        } else {
+            self.scale
+        }
+    }
+
+    /// How much simulated time passes over the provided number of real seconds.
+    pub fn scaled_delta_seconds(&self, delta_seconds: f32) -> f32 {
+        delta_seconds * self.playback_rate()
+    }
+
+    /// Change how fast simulated time passes and return whether it actually changed.
+    pub fn set_scale(&mut self, new_scale: f32) -> bool {
+        let new_scale = new_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+        if new_scale == self.scale {
+            return false;
+        }
+        self.scale = new_scale;
+        if !self.paused {
+            // Looping audio can only be given a new playback rate by starting it over
+            self.stop_looping_audio();
+            self.start_looping_audio();
+        }
+        true
+    }
+
+    pub fn pause(&mut self) {
+        if !self.paused {
+            self.paused = true;
+            self.stop_looping_audio();
+        }
+    }
+
+    /// Let simulated time pass again. The interface can only stop looping audio rather
+    /// than pause it, so looping audio starts over from the beginning.
+    pub fn resume(&mut self) {
+        if self.paused {
+            self.paused = false;
+            self.start_looping_audio();
+        }
+    }
+
+    pub fn play_audio(
+        &self,
+        scene_object_name: &str,
+        audio_name: &str,
+        playback_rate: f32,
+        volume: f32,
+    ) {
+        if !self.paused && !self.headless {
+            play_audio(
+                scene_object_name,
+                audio_name,
+                playback_rate * self.scale,
+                volume,
+            );
+        }
+    }
+
+    pub fn loop_audio(
+        &mut self,
+        scene_object_name: &str,
+        audio_name: &str,
+        playback_rate: f32,
+        volume: f32,
+    ) {
+        self.forget_looping_audio(scene_object_name, audio_name);
+        self.looping_audio.push(LoopingAudio {
+            scene_object_name: String::from(scene_object_name),
+            audio_name: String::from(audio_name),
+            playback_rate,
+            volume,
+        });
+        if !self.paused && !self.headless {
+            loop_audio(
+                scene_object_name,
+                audio_name,
+                playback_rate * self.scale,
+                volume,
+            );
+        }
+    }
+
+    pub fn stop_audio(&mut self, scene_object_name: &str, audio_name: &str) {
+        self.forget_looping_audio(scene_object_name, audio_name);
+        if !self.headless {
+            stop_audio(scene_object_name, audio_name);
+        }
+    }
+
+    fn forget_looping_audio(&mut self, scene_object_name: &str, audio_name: &str) {
+        self.looping_audio.retain(|looping_audio| {
+            looping_audio.scene_object_name != scene_object_name
+                || looping_audio.audio_name != audio_name
+        });
+    }
+
+    fn start_looping_audio(&self) {
+        if self.headless {
+            return;
+        }
+        for looping_audio in &self.looping_audio {
+            loop_audio(
+                &looping_audio.scene_object_name,
+                &looping_audio.audio_name,
+                looping_audio.playback_rate * self.scale,
+                looping_audio.volume,
+            );
+        }
+    }
+
+    fn stop_looping_audio(&self) {
+        if self.headless {
+            return;
+        }
+        for looping_audio in &self.looping_audio {
+            stop_audio(&looping_audio.scene_object_name, &looping_audio.audio_name);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use perigee::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The most ticks a single step may run. If the interface falls further behind
+/// than this, the simulation slows down rather than spending even longer catching up.
+static MAX_TICKS_PER_STEP: u32 = 8;
+/// The tick rate used in place of one that can't be ticked at.
+static FALLBACK_TICK_RATE: f32 = 30.0;
+
+/// Banks the time an interface steps a simulation by and pays it out in ticks
+/// of a fixed length, so the simulation plays out the same at any frame rate.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FixedTimestep {
+    tick_seconds: f32,
+    accumulated_seconds: f32,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f32) -> Self {
+        let tick_rate = if tick_rate.is_finite() && tick_rate > 0.0 {
+            tick_rate
+        } else {
+            warn!(
+                "Can't tick {} times per second, ticking {} times per second instead",
+                tick_rate, FALLBACK_TICK_RATE
+            );
+            FALLBACK_TICK_RATE
+        };
+        Self {
+            tick_seconds: 1.0 / tick_rate,
+            accumulated_seconds: 0.0,
+        }
+    }
+
+    pub fn tick_seconds(&self) -> f32 {
+        self.tick_seconds
+    }
+
+    /// Throw away any banked time.
+    pub fn reset(&mut self) {
+        self.accumulated_seconds = 0.0;
+    }
+
+    /// Bank the provided number of seconds and return how many ticks are due.
+    /// Steps that aren't a finite number of seconds are ignored.
+    pub fn accumulate(&mut self, delta_seconds: f32) -> u32 {
+        if !delta_seconds.is_finite() {
+            return 0;
+        }
+        self.accumulated_seconds += delta_seconds.max(0.0);
+        let ticks_due = (self.accumulated_seconds / self.tick_seconds).floor();
+        if ticks_due > MAX_TICKS_PER_STEP as f32 {
+            self.accumulated_seconds = 0.0;
+            return MAX_TICKS_PER_STEP;
+        }
+        self.accumulated_seconds =
+            (self.accumulated_seconds - ticks_due * self.tick_seconds).max(0.0);
+        ticks_due as u32
+    }
+
+    /// How far (from 0 to 1) the banked time is between the latest tick and the next one.
+    /// Interfaces can use this to interpolate between previous and current isometries.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulated_seconds / self.tick_seconds).clamp(0.0, 1.0)
+    }
+}
+
+/// The isometry every rigid body had before the latest tick.
+#[derive(Debug, Default)]
+pub struct RigidBodySnapshot {
+    isometries: HashMap<RigidBodyHandle, Isometry3<f32>>,
+}
+
+impl RigidBodySnapshot {
+    pub fn capture(&mut self, rigid_body_set: &RigidBodySet) {
+        self.isometries.clear();
+        for (body_handle, rigid_body) in rigid_body_set.iter() {
+            self.isometries.insert(body_handle, *rigid_body.position());
+        }
+    }
+
+    pub fn isometry(&self, body_handle: RigidBodyHandle) -> Option<&Isometry3<f32>> {
+        self.isometries.get(&body_handle)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ticks_at_fixed_rate() {
+        let mut timestep = FixedTimestep::new(32.0);
+
+        // Stepping faster than the tick rate only ticks once enough time has banked up
+        assert_eq!(timestep.accumulate(1.0 / 128.0), 0);
+        assert_eq!(timestep.alpha(), 0.25);
+        assert_eq!(timestep.accumulate(3.0 / 128.0), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        // Stepping slower than the tick rate catches up, but only so far
+        assert_eq!(timestep.accumulate(3.0 / 32.0), 3);
+        assert_eq!(timestep.accumulate(1.0), MAX_TICKS_PER_STEP);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        // Steps that can't be banked are ignored rather than hanging or poisoning the bank
+        assert_eq!(timestep.accumulate(f32::MAX), MAX_TICKS_PER_STEP);
+        assert_eq!(timestep.accumulate(f32::INFINITY), 0);
+        assert_eq!(timestep.accumulate(f32::NAN), 0);
+        assert_eq!(timestep.alpha(), 0.0);
+
+        for tick_rate in [0.0, -30.0, f32::NAN] {
+            assert_eq!(
+                FixedTimestep::new(tick_rate).tick_seconds(),
+                1.0 / FALLBACK_TICK_RATE
+            );
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use crate::shared::descriptor::Descriptor;
+use perigee::prelude::*;
+use perigee::rapier3d::na::Point3;
+use serde::{Deserialize, Serialize};
+
+/// The tag that marks a sensor in a level's glTF as water.
+pub static WATER_TAG: &str = "water";
+
+/// The sensors in a level that are water. Anything inside one of them is in water.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WaterVolumes {
+    sensor_handles: Vec<ColliderHandle>,
+}
+
+impl WaterVolumes {
+    /// Find the sensors tagged `water` in a level's glTF, e.g. `Lake.water`.
+    pub fn from_gltf(gltf: &Gltf, physics: &PhysicsWorld) -> Self {
+        let sensor_handles = gltf
+            .nodes()
+            .filter_map(|node| node.name())
+            .filter(|object_name| Descriptor::from_name(*object_name).has_tag(WATER_TAG))
+            .filter_map(|object_name| physics.named_sensors.handle_with_name(object_name).copied())
+            .collect();
+        Self { sensor_handles }
+    }
+
+    /// The height of the surface of the water a point is in, or `None` if it's not in water.
+    /// The surface is the top of the water's bounding box, so water volumes should be flat on top.
+    pub fn surface_height_at(
+        &self,
+        point: &Point3<f32>,
+        collider_set: &ColliderSet,
+    ) -> Option<f32> {
+        self.sensor_handles
+            .iter()
+            .filter_map(|sensor_handle| collider_set.get(*sensor_handle))
+            .filter(|water| water.shape().contains_point(water.position(), point))
+            .map(|water| water.compute_aabb().maxs.y)
+            .reduce(f32::max)
+    }
+}
@@ -1,4 +1,5 @@
-import { Simulation } from '../shared/sim-utils.js'
+import { Simulation, SimStatus } from '../shared/sim-utils.js'
+import { GameEvents } from '../shared/events.js'
 
 class Level1Sim extends Simulation {
   constructor() {
@@ -7,17 +8,23 @@ class Level1Sim extends Simulation {
     // this._vectorPointer = null
     // this._quaternionPointer = null
     this._isometryPointer = null
+    // Modules hosting every level create this one by its ID.
+    // Modules hosting only this level ignore it.
+    this._levelId = 1
   }
 
   async loadWasm(wasmPath) {
     const wasmFunctionImports = {
+      // Level events arrive as JSON objects, each with its name in "type"
       level_event_hook: (ptr, len) => {
-        this.events.emit(this.getString(ptr, len), [])
+        const levelEvent = JSON.parse(this.getString(ptr, len))
+        this.events.emit(levelEvent.type, [levelEvent])
       },
       ...this.nowHandlers(),
       ...this.audioHandlers(),
       ...this.animationHandlers(),
       ...this.assistiveDeviceHandlers(),
+      ...this.characterEventHandlers(),
       ...this.logHandlers(),
     }
 
@@ -34,20 +41,69 @@ class Level1Sim extends Simulation {
     // const vecPtr = wasmExports.allocate_vector3f32_space()
     // const quatPtr = wasmExports.allocate_unitquaternionf32_space()
     this._isometryPointer = this._wasmExports.allocate_isometry3_f32__space()
-    this._simPointer = this._wasmExports.create_sim()
+    this._simPointer = this._wasmExports.create_sim(this._levelId)
   }
 
+  // Returns whether the level initialized. If it didn't,
+  // lastError() says why.
   initialize() {
-    this._wasmExports.initialize_sim(this._simPointer)
+    return (
+      this._wasmExports.initialize_sim(this._simPointer) === SimStatus.OK
+    )
+  }
+
+  // Check a glTF has everything the level needs without loading it.
+  // Returns null if it does, or a message listing what's wrong.
+  validateGltf(gltfBytes) {
+    const bytes = new Uint8Array(gltfBytes)
+    const ptrToImportBuffer = this._wasmExports.gltf_validation_buffer(
+      this._simPointer,
+      bytes.length
+    )
+    new Uint8Array(this._wasmMemory.buffer, ptrToImportBuffer, bytes.length).set(
+      bytes
+    )
+    if (this._wasmExports.validate_gltf(this._simPointer) === SimStatus.OK) {
+      return null
+    }
+    return this.lastError()
+  }
+
+  restartLevel() {
+    this.checkStatus(this._wasmExports.restart_level(this._simPointer))
   }
 
   desiredFps() {
-    return this._wasmExports.desired_fps()
+    return this._wasmExports.desired_fps(this._simPointer)
+  }
+
+  timeScale() {
+    return this._wasmExports.time_scale(this._simPointer)
+  }
+
+  setTimeScale(newTimeScale) {
+    this._wasmExports.set_time_scale(this._simPointer, newTimeScale)
+  }
+
+  isPaused() {
+    return this._wasmExports.is_paused(this._simPointer) > 0
+  }
+
+  pause() {
+    this._wasmExports.pause_sim(this._simPointer)
+  }
+
+  resume() {
+    this._wasmExports.resume_sim(this._simPointer)
+  }
+
+  interpolationAlpha() {
+    return this._wasmExports.interpolation_alpha(this._simPointer)
   }
 
   reset() {
     this._wasmExports.destroy_sim(this._simPointer)
-    this._simPointer = this._wasmExports.create_sim()
+    this._simPointer = this._wasmExports.create_sim(this._levelId)
     this.initialize()
   }
 
@@ -87,8 +143,20 @@ class Level1Sim extends Simulation {
     this._wasmExports.input_set_aim(this._simPointer, aimVal ? 1 : 0)
   }
 
+  inputSetDash(dashVal) {
+    this._wasmExports.input_set_dash(this._simPointer, dashVal ? 1 : 0)
+  }
+
+  inputSetGrapple(grappleVal) {
+    this._wasmExports.input_set_grapple(this._simPointer, grappleVal ? 1 : 0)
+  }
+
+  inputSetReel(newMagnitude) {
+    this._wasmExports.input_set_reel(this._simPointer, newMagnitude)
+  }
+
   step(deltaSeconds) {
-    this._wasmExports.step(this._simPointer, deltaSeconds)
+    this.checkStatus(this._wasmExports.step(this._simPointer, deltaSeconds))
   }
 
   leftRightLookSensitivity() {
@@ -116,19 +184,34 @@ class Level1Sim extends Simulation {
   }
 
   propIsometry(name) {
-    this._wasmExports.prop_isometry(
-      this._simPointer,
-      this.ptrToString(name, this._wasmExports.alloc_string),
-      this._isometryPointer
+    this.checkStatus(
+      this._wasmExports.prop_isometry(
+        this._simPointer,
+        this.ptrToString(name, this._wasmExports.alloc_string),
+        this._isometryPointer
+      )
+    )
+    return this.getIsometryF32(this._isometryPointer)
+  }
+
+  previousPropIsometry(name) {
+    this.checkStatus(
+      this._wasmExports.previous_prop_isometry(
+        this._simPointer,
+        this.ptrToString(name, this._wasmExports.alloc_string),
+        this._isometryPointer
+      )
     )
     return this.getIsometryF32(this._isometryPointer)
   }
 
   getPoiIsometry(name) {
-    this._wasmExports.poi(
-      this._simPointer,
-      this.ptrToString(name, this._wasmExports.alloc_string),
-      this._isometryPointer
+    this.checkStatus(
+      this._wasmExports.poi(
+        this._simPointer,
+        this.ptrToString(name, this._wasmExports.alloc_string),
+        this._isometryPointer
+      )
     )
     return this.getIsometryF32(this._isometryPointer)
   }
@@ -141,6 +224,14 @@ class Level1Sim extends Simulation {
     return this.getIsometryF32(this._isometryPointer)
   }
 
+  previousPlayerBodyIsometry() {
+    this._wasmExports.previous_player_body_isometry(
+      this._simPointer,
+      this._isometryPointer
+    )
+    return this.getIsometryF32(this._isometryPointer)
+  }
+
   cameraGlobalIsometry() {
     this._wasmExports.camera_global_isometry(
       this._simPointer,
@@ -148,6 +239,95 @@ class Level1Sim extends Simulation {
     )
     return this.getIsometryF32(this._isometryPointer)
   }
+
+  previousCameraGlobalIsometry() {
+    this._wasmExports.previous_camera_global_isometry(
+      this._simPointer,
+      this._isometryPointer
+    )
+    return this.getIsometryF32(this._isometryPointer)
+  }
+
+  stopGhostRecording() {
+    this._wasmExports.stop_ghost_recording(this._simPointer)
+  }
+
+  getGhostRecordingBytes() {
+    const ptrToRecording = this._wasmExports.ghost_recording_ptr(this._simPointer)
+    const recordingLen = this._wasmExports.ghost_recording_len(this._simPointer)
+    return this._wasmMemory.buffer.slice(
+      ptrToRecording,
+      ptrToRecording + recordingLen
+    )
+  }
+
+  loadGhost(recordingBytes) {
+    const bytes = new Uint8Array(recordingBytes)
+    const ptrToImportBuffer = this._wasmExports.ghost_import_buffer(
+      this._simPointer,
+      bytes.length
+    )
+    new Uint8Array(this._wasmMemory.buffer, ptrToImportBuffer, bytes.length).set(
+      bytes
+    )
+    return this._wasmExports.load_ghost(this._simPointer) === SimStatus.OK
+  }
+
+  hasGhost() {
+    return this._wasmExports.has_ghost(this._simPointer) > 0
+  }
+
+  ghostBodyIsometry() {
+    this.checkStatus(
+      this._wasmExports.ghost_body_isometry(
+        this._simPointer,
+        this._isometryPointer
+      )
+    )
+    return this.getIsometryF32(this._isometryPointer)
+  }
+
+  elapsedSeconds() {
+    return this._wasmExports.elapsed_seconds(this._simPointer)
+  }
+
+  stateChecksum() {
+    // WebAssembly hands back a signed integer, so reinterpret it as unsigned
+    return this._wasmExports.state_checksum(this._simPointer) >>> 0
+  }
+
+  getInputLogBytes() {
+    const ptrToInputLog = this._wasmExports.input_log_ptr(this._simPointer)
+    const inputLogLen = this._wasmExports.input_log_len(this._simPointer)
+    return this._wasmMemory.buffer.slice(
+      ptrToInputLog,
+      ptrToInputLog + inputLogLen
+    )
+  }
+
+  verifyReplay(inputLogBytes, claimedCompletionSeconds) {
+    const bytes = new Uint8Array(inputLogBytes)
+    const ptrToImportBuffer = this._wasmExports.replay_import_buffer(
+      this._simPointer,
+      bytes.length
+    )
+    new Uint8Array(this._wasmMemory.buffer, ptrToImportBuffer, bytes.length).set(
+      bytes
+    )
+    // The replay runs headlessly, so nothing it emits should reach listeners
+    const events = this.events
+    this.events = new GameEvents()
+    try {
+      return (
+        this._wasmExports.verify_replay(
+          this._simPointer,
+          claimedCompletionSeconds
+        ) === SimStatus.OK
+      )
+    } finally {
+      this.events = events
+    }
+  }
 }
 
 export { Level1Sim }
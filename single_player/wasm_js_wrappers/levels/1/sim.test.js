@@ -3,6 +3,11 @@ import {
   describe,
   it,
 } from 'https://deno.land/std@0.152.0/testing/bdd.ts'
+import {
+  assertEquals,
+  assertThrows,
+} from 'https://deno.land/std@0.152.0/testing/asserts.ts'
+import { SimError, SimStatus } from '../shared/sim-utils.js'
 import { Level1Sim } from './sim.js'
 
 const isReleaseBuild = !!Deno.env.get('RELEASE')
@@ -28,4 +33,14 @@ describe('Level 1', () => {
       sim.step(deltaSeconds)
     }
   })
+
+  it('reports unknown props instead of crashing', () => {
+    const simErr = assertThrows(
+      () => sim.propIsometry('Nonexistent Prop'),
+      SimError
+    )
+    assertEquals(simErr.status, SimStatus.NOT_FOUND)
+    // The simulation is still usable afterward
+    sim.step(1 / sim.desiredFps())
+  })
 })
@@ -1,5 +1,22 @@
 import { GameEvents } from './events.js'
 
+// Statuses returned by simulation calls that can fail. Anything
+// other than OK means the simulation's lastError() says why.
+const SimStatus = Object.freeze({
+  OK: 0,
+  NOT_FOUND: 1,
+  INVALID_INPUT: 2,
+  INVALID_STATE: 3,
+})
+
+class SimError extends Error {
+  constructor(status, message) {
+    super(message)
+    this.name = 'SimError'
+    this.status = status
+  }
+}
+
 // Provide utility functions as a super class
 // to make it easier to "import" them.
 //
@@ -114,6 +131,19 @@ class Simulation {
     }
   }
 
+  // Character events (LANDED, STARTED_WALL_RUNNING, etc) arrive as JSON
+  // and are emitted under their type with the parsed event as the payload
+  characterEventHandlers() {
+    return {
+      character_event_hook: (eventJsonPtr, eventJsonLen) => {
+        const characterEvent = JSON.parse(
+          this.getString(eventJsonPtr, eventJsonLen)
+        )
+        this.events.emit(characterEvent.type, [characterEvent])
+      },
+    }
+  }
+
   // This is static because it's used in the WASM imports
   // which are created before a Level1Sim is constructed
   getCString(memoryTypedArray, memoryAddress) {
@@ -138,6 +168,21 @@ class Simulation {
     return this._textDecoder.decode(strBytes)
   }
 
+  // Why the most recent call that didn't return an OK status failed
+  lastError() {
+    return this.getString(
+      this._wasmExports.last_error_ptr(this._simPointer),
+      this._wasmExports.last_error_len(this._simPointer)
+    )
+  }
+
+  // Throw the simulation's last error if a call that can fail didn't succeed
+  checkStatus(status) {
+    if (status !== SimStatus.OK) {
+      throw new SimError(status, this.lastError())
+    }
+  }
+
   logHandlers() {
     return {
       on_error: (stringPtr, strLen) => {
@@ -251,4 +296,4 @@ class Simulation {
   }
 }
 
-export { Simulation }
+export { Simulation, SimError, SimStatus }